@@ -0,0 +1,277 @@
+//! Checkpoints: everything needed to carry a long run on later, from
+//! where it was, after it has been stopped or has crashed.
+//!
+//! `--checkpoint-every N --checkpoint-file run.icat` writes one every `N`
+//! generations and again when the run ends, and `--resume` picks the run
+//! up from the file. A checkpoint starts with a few lines of text saying
+//! what it holds, and then the cells, zstd-compressed:
+//!
+//! ```text
+//! iron-cat checkpoint 1
+//! rule B3/S23
+//! size 23 38
+//! generation 1103
+//! elapsed 1103.250
+//! seed 1234567
+//!
+//! <the state and the age of every cell, row by row, compressed>
+//! ```
+//!
+//! The seed is where everything random carries on from, so a resumed run
+//! reseeds just as the run would have. Each cell is written as its state
+//! and its age, four bytes each, little end first.
+//!
+//! Any other lines before the cells are kept as they are, for whatever
+//! else is saved along with the world, such as how a project shows it.
+
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+use std::time::Duration;
+use std::{error, fmt};
+
+use crate::{Matrix, Rulestring};
+
+/// The version of the format written, the only one read.
+pub const VERSION: u32 = 1;
+
+/// The first line of every checkpoint, up to the version.
+const MAGIC: &str = "iron-cat checkpoint ";
+
+/// How hard to compress the cells, one of zstd's quicker levels.
+const LEVEL: i32 = 3;
+
+/// Why a checkpoint couldn't be read or written.
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(io::Error),
+    NotCheckpoint,
+    Version(String),
+    Header(String),
+    Cells,
+}
+
+impl error::Error for CheckpointError {}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Io(e) => write!(f, "Could not read or write checkpoint: {}", e),
+            CheckpointError::NotCheckpoint => write!(f, "Invalid checkpoint: it isn't one"),
+            CheckpointError::Version(v) => write!(
+                f,
+                "Invalid checkpoint: version {} where only {} can be read",
+                v, VERSION
+            ),
+            CheckpointError::Header(s) => write!(f, "Invalid line in checkpoint: {}", s),
+            CheckpointError::Cells => write!(f, "Invalid checkpoint: the cells don't fit its size"),
+        }
+    }
+}
+
+/// A run as it was at some generation.
+#[derive(Clone)]
+pub struct Checkpoint {
+    pub matrix: Matrix,
+    /// How long the run had been going, over all the times it was resumed.
+    pub elapsed: Duration,
+    /// Where everything random carries on from.
+    pub seed: u64,
+    /// Lines of anything else, each a key and its value, in order.
+    pub settings: Vec<(String, String)>,
+}
+
+impl Checkpoint {
+    /// Writes the checkpoint to a file next to `path` and then moves it into
+    /// place, so a run stopped halfway through leaves the last one whole.
+    pub fn save(&self, path: &Path) -> Result<(), CheckpointError> {
+        let matrix = &self.matrix;
+        let mut out = format!(
+            "{}{}\nrule {}\nsize {} {}\ngeneration {}\nelapsed {:.3}\nseed {}\n\n",
+            MAGIC,
+            VERSION,
+            matrix.rules,
+            matrix.m,
+            matrix.n,
+            matrix.generation,
+            self.elapsed.as_secs_f64(),
+            self.seed
+        )
+        .into_bytes();
+        // Before the empty line that ends the text.
+        out.pop();
+        for (key, value) in &self.settings {
+            out.extend(format!("{} {}\n", key, value).into_bytes());
+        }
+        out.push(b'\n');
+
+        let mut cells = Vec::with_capacity(matrix.rows.len() * 8);
+        for (&state, &age) in matrix.rows.iter().zip(&matrix.ages) {
+            cells.extend_from_slice(&(state as u32).to_le_bytes());
+            cells.extend_from_slice(&age.to_le_bytes());
+        }
+        out.extend(zstd::encode_all(&cells[..], LEVEL).map_err(CheckpointError::Io)?);
+
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+        fs::write(&partial, out).map_err(CheckpointError::Io)?;
+        fs::rename(&partial, path).map_err(CheckpointError::Io)
+    }
+
+    /// Whether a file is a checkpoint, going by how it starts.
+    pub fn is_checkpoint(path: &Path) -> bool {
+        let mut start = [0; MAGIC.len()];
+        File::open(path)
+            .and_then(|mut f| f.read_exact(&mut start))
+            .is_ok()
+            && start == MAGIC.as_bytes()
+    }
+
+    pub fn load(path: &Path) -> Result<Checkpoint, CheckpointError> {
+        let bytes = fs::read(path).map_err(CheckpointError::Io)?;
+
+        // The text ends at the first empty line.
+        let end = bytes
+            .windows(2)
+            .position(|w| w == b"\n\n")
+            .ok_or(CheckpointError::NotCheckpoint)?;
+        let text =
+            std::str::from_utf8(&bytes[..end]).map_err(|_| CheckpointError::NotCheckpoint)?;
+        let mut lines = text.lines();
+
+        let version = lines
+            .next()
+            .and_then(|l| l.strip_prefix(MAGIC))
+            .ok_or(CheckpointError::NotCheckpoint)?;
+        if version != VERSION.to_string() {
+            return Err(CheckpointError::Version(version.to_string()));
+        }
+
+        let (mut rule, mut size, mut generation, mut elapsed, mut seed) =
+            (None, None, None, None, None);
+        let mut settings = Vec::new();
+        for line in lines {
+            let bad = || CheckpointError::Header(line.to_string());
+            let (key, value) = line.split_once(' ').ok_or_else(bad)?;
+            match key {
+                "rule" => rule = Some(value.parse::<Rulestring>().map_err(|_| bad())?),
+                "size" => {
+                    let (m, n) = value.split_once(' ').ok_or_else(bad)?;
+                    let m = m.parse::<usize>().map_err(|_| bad())?;
+                    let n = n.parse::<usize>().map_err(|_| bad())?;
+                    size = Some((m, n));
+                }
+                "generation" => generation = Some(value.parse::<u64>().map_err(|_| bad())?),
+                "elapsed" => elapsed = Some(value.parse::<f64>().map_err(|_| bad())?),
+                "seed" => seed = Some(value.parse::<u64>().map_err(|_| bad())?),
+                _ => settings.push((key.to_string(), value.to_string())),
+            }
+        }
+        let missing = |what: &str| CheckpointError::Header(format!("no {}", what));
+        let rule = rule.ok_or_else(|| missing("rule"))?;
+        let (m, n) = size.ok_or_else(|| missing("size"))?;
+
+        let cells = zstd::decode_all(&bytes[end + 2..]).map_err(|_| CheckpointError::Cells)?;
+        if m == 0 || n == 0 || m.checked_mul(n).and_then(|c| c.checked_mul(8)) != Some(cells.len())
+        {
+            return Err(CheckpointError::Cells);
+        }
+
+        let mut matrix = Matrix::new(m, n, rule);
+        matrix.generation = generation.ok_or_else(|| missing("generation"))?;
+        for (i, cell) in cells.chunks(8).enumerate() {
+            let word = |at: usize| {
+                u32::from_le_bytes([cell[at], cell[at + 1], cell[at + 2], cell[at + 3]])
+            };
+            matrix.rows[i] = word(0) as usize;
+            matrix.ages[i] = word(4);
+        }
+
+        Ok(Checkpoint {
+            matrix,
+            elapsed: Duration::try_from_secs_f64(elapsed.unwrap_or(0.0)).unwrap_or_default(),
+            seed: seed.ok_or_else(|| missing("seed"))?,
+            settings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A file of its own for each test, as they run side by side.
+    fn scratch(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("iron-cat-{}-{}.icat", std::process::id(), name))
+    }
+
+    fn glider() -> Matrix {
+        let mut matrix = Matrix::new(6, 7, "B36/S23".parse().unwrap());
+        for (r, c) in [(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)] {
+            matrix.set(r, c, 1);
+        }
+        matrix.ages[6 * 7 - 1] = 12;
+        matrix.generation = 1103;
+        matrix
+    }
+
+    #[test]
+    fn round_trips() {
+        let path = scratch("round-trip");
+        let checkpoint = Checkpoint {
+            matrix: glider(),
+            elapsed: Duration::from_millis(1103250),
+            seed: 1234567,
+            settings: vec![("theme".to_string(), "neon".to_string())],
+        };
+        checkpoint.save(&path).unwrap();
+        assert!(Checkpoint::is_checkpoint(&path));
+
+        let loaded = Checkpoint::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let (a, b) = (&checkpoint.matrix, &loaded.matrix);
+        assert_eq!((b.m, b.n, b.generation), (a.m, a.n, a.generation));
+        assert_eq!(b.rules.to_string(), a.rules.to_string());
+        assert_eq!(b.rows, a.rows);
+        assert_eq!(b.ages, a.ages);
+        assert_eq!(loaded.elapsed, checkpoint.elapsed);
+        assert_eq!(loaded.seed, checkpoint.seed);
+        assert_eq!(loaded.settings, checkpoint.settings);
+    }
+
+    #[test]
+    fn errors() {
+        let path = scratch("errors");
+        let load = |header: &str, cells: &[u8]| {
+            let mut bytes = header.as_bytes().to_vec();
+            bytes.extend(zstd::encode_all(cells, LEVEL).unwrap());
+            fs::write(&path, bytes).unwrap();
+            Checkpoint::load(&path)
+        };
+        let header = "rule B3/S23\nsize 1 2\ngeneration 5\nseed 7\n\n";
+
+        assert!(matches!(
+            load(&format!("{}2\n{}", MAGIC, header), &[0; 16]),
+            Err(CheckpointError::Version(v)) if v == "2"
+        ));
+        assert!(matches!(
+            load(&format!("{}1\n{}", MAGIC, header), &[0; 8]),
+            Err(CheckpointError::Cells)
+        ));
+        assert!(matches!(
+            load(&format!("{}1\nrule B3/S23\nsize 1 2\n\n", MAGIC), &[0; 16]),
+            Err(CheckpointError::Header(s)) if s == "no generation"
+        ));
+        assert!(matches!(
+            load(&format!("{}1\nsize 1 x\n\n", MAGIC), &[0; 16]),
+            Err(CheckpointError::Header(_))
+        ));
+        assert!(matches!(
+            load("x = 3, y = 3\n\n", &[]),
+            Err(CheckpointError::NotCheckpoint)
+        ));
+        assert!(!Checkpoint::is_checkpoint(&path));
+        fs::remove_file(&path).unwrap();
+    }
+}
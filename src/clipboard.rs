@@ -0,0 +1,62 @@
+//! The system clipboard, shared with Golly and every other program.
+//!
+//! Patterns are copied there as RLE, with the rule in the header, just as
+//! Golly copies them, so they can be pasted straight into it. Pasting takes
+//! whatever pattern is there, in any format `--pattern` reads, as Golly
+//! pastes it too.
+
+use std::{error, fmt};
+
+use crate::pattern::{self, Pattern, PatternFile, ReadError};
+use crate::Rulestring;
+
+/// Why the system clipboard couldn't be used.
+#[derive(Debug)]
+pub enum ClipboardError {
+    System(arboard::Error),
+    Pattern(ReadError),
+}
+
+impl error::Error for ClipboardError {}
+
+impl fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClipboardError::System(e) => write!(f, "Could not use the system clipboard: {}", e),
+            ClipboardError::Pattern(e) => {
+                write!(f, "Invalid pattern in the system clipboard: {}", e)
+            }
+        }
+    }
+}
+
+/// The system clipboard, opened the first time it's used. On X11 what was
+/// copied is only there for as long as it's kept open, so it stays open
+/// until the run ends.
+#[derive(Default)]
+pub struct System {
+    clipboard: Option<arboard::Clipboard>,
+}
+
+impl System {
+    fn open(&mut self) -> Result<&mut arboard::Clipboard, ClipboardError> {
+        if self.clipboard.is_none() {
+            self.clipboard = Some(arboard::Clipboard::new().map_err(ClipboardError::System)?);
+        }
+        // Opened above.
+        Ok(self.clipboard.as_mut().unwrap())
+    }
+
+    /// Copies a pattern as RLE.
+    pub fn copy(&mut self, pattern: &Pattern, rules: &Rulestring) -> Result<(), ClipboardError> {
+        self.open()?
+            .set_text(pattern::rle::write(pattern, rules))
+            .map_err(ClipboardError::System)
+    }
+
+    /// Reads the pattern in the clipboard.
+    pub fn paste(&mut self) -> Result<PatternFile, ClipboardError> {
+        let text = self.open()?.get_text().map_err(ClipboardError::System)?;
+        pattern::read(&text).map_err(ClipboardError::Pattern)
+    }
+}
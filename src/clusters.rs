@@ -0,0 +1,108 @@
+//! Clusters of live cells and how big they are, the thing to watch in rules
+//! that percolate: near the threshold the sizes spread out into a power
+//! law, and one cluster grows to span the world.
+//!
+//! Cells touching along a side or at a corner are in the same cluster, and
+//! so are cells on opposite edges of the world, since it wraps round.
+//! `--clusters-out` writes how many clusters there are of every size every
+//! `--clusters-every` generations, and `census --clusters` once:
+//!
+//! ```text
+//! generation,size,count
+//! 0,1,16
+//! 0,2,17
+//! 0,3,9
+//! ```
+
+use std::io::{self, Write};
+
+use crate::provenance::Provenance;
+use crate::Matrix;
+
+/// The size of every cluster in the world, biggest first.
+pub fn sizes(matrix: &Matrix) -> Vec<usize> {
+    let (m, n) = (matrix.m, matrix.n);
+    let mut seen = vec![false; matrix.rows.len()];
+    let mut sizes = Vec::new();
+    let mut stack = Vec::new();
+
+    for start in 0..matrix.rows.len() {
+        if matrix.rows[start] == 0 || seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        stack.push(start);
+        let mut size = 0;
+        while let Some(i) = stack.pop() {
+            size += 1;
+            let (r, c) = (i / n, i % n);
+            for dr in [m - 1, 0, 1] {
+                for dc in [n - 1, 0, 1] {
+                    let j = (r + dr) % m * n + (c + dc) % n;
+                    if matrix.rows[j] != 0 && !seen[j] {
+                        seen[j] = true;
+                        stack.push(j);
+                    }
+                }
+            }
+        }
+        sizes.push(size);
+    }
+
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    sizes
+}
+
+/// How many clusters there are of every size, smallest first, from the
+/// sizes `sizes` gives.
+pub fn histogram(sizes: &[usize]) -> Vec<(usize, usize)> {
+    let mut counts: Vec<(usize, usize)> = Vec::new();
+    for &size in sizes.iter().rev() {
+        match counts.last_mut() {
+            Some((s, count)) if *s == size => *count += 1,
+            _ => counts.push((size, 1)),
+        }
+    }
+    counts
+}
+
+/// Writes the histogram of cluster sizes every so many generations as CSV,
+/// a line for every size there is, with the provenance first as `#` lines
+/// as `StatsLog` does.
+pub struct ClusterLog {
+    out: Box<dyn Write>,
+    every: u64,
+    provenance: Option<Provenance>,
+}
+
+impl ClusterLog {
+    pub fn new(out: Box<dyn Write>, every: u64, provenance: &Provenance) -> ClusterLog {
+        ClusterLog {
+            out,
+            every: every.max(1),
+            provenance: Some(provenance.clone()),
+        }
+    }
+
+    /// Writes the world's clusters, if it's one of the generations they're
+    /// counted in.
+    pub fn write(&mut self, matrix: &Matrix) -> io::Result<()> {
+        if !matrix.generation.is_multiple_of(self.every) {
+            return Ok(());
+        }
+        if let Some(provenance) = self.provenance.take() {
+            for line in provenance.lines() {
+                writeln!(self.out, "# {}", line)?;
+            }
+            writeln!(self.out, "generation,size,count")?;
+        }
+        for (size, count) in histogram(&sizes(matrix)) {
+            writeln!(self.out, "{},{},{}", matrix.generation, size, count)?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
@@ -0,0 +1,173 @@
+//! Crashing two patterns into each other every way there is, the workhorse
+//! of glider synthesis: `iron-cat collide name:glider name:glider:rot180`
+//! puts the second pattern down at every offset from the first up to
+//! `--range` cells away, and at every phase up to `--phases` generations
+//! on, runs each until it settles and adds up what came out by apgcode.
+//!
+//! Placements where the two would touch straight away are left out, as are
+//! those where they miss each other, which come out as the same objects as
+//! the two of them run apart.
+
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+
+use crate::pattern::{apgcode, Pattern};
+use crate::search::settle;
+use crate::{Matrix, Rulestring};
+
+/// The room left around the two patterns, for what comes out of them to
+/// settle in before anything flying off comes round the world again.
+const ROOM: usize = 48;
+
+/// Where the second pattern went: its top left corner's offset from the
+/// first's in columns and rows, and how many generations it was run on
+/// first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Placement {
+    pub dx: i64,
+    pub dy: i64,
+    pub phase: u64,
+}
+
+/// The objects a collision settles into, by apgcode, most first, or
+/// nothing at all.
+pub type Outcome = Vec<(Option<String>, usize)>;
+
+/// How many collisions came out the same way, and the first that did.
+#[derive(Clone, Copy, Debug)]
+pub struct Tally {
+    pub count: usize,
+    pub first: Placement,
+}
+
+/// What every collision came to, most common first, and how many missed.
+pub struct Collisions {
+    pub outcomes: Vec<(Outcome, Tally)>,
+    pub missed: usize,
+}
+
+/// Runs `pattern` on by `phase` generations, far enough from any edge that
+/// it doesn't come round, and takes what it has become.
+fn advance(pattern: &Pattern, rules: &Rulestring, phase: u64) -> Pattern {
+    let pad = phase as usize + 2;
+    let mut matrix = Matrix::new(
+        pattern.height + 2 * pad,
+        pattern.width + 2 * pad,
+        rules.clone(),
+    );
+    pattern.overlay(&mut matrix, (pad, pad));
+    while matrix.generation < phase {
+        matrix.pulse();
+    }
+    Pattern::live(&matrix)
+}
+
+/// The live cells of a pattern as `(x, y)`.
+fn cells(pattern: &Pattern) -> Vec<(i64, i64)> {
+    (0..pattern.cells.len())
+        .filter(|&i| pattern.cells[i] != 0)
+        .map(|i| ((i % pattern.width) as i64, (i / pattern.width) as i64))
+        .collect()
+}
+
+/// Runs the two patterns together in a world with room around them, the
+/// second with its top left corner `(dx, dy)` from the first's, and counts
+/// what they've settled into after at most `longest` generations.
+fn run(
+    rules: &Rulestring,
+    a: &Pattern,
+    b: Option<(&Pattern, i64, i64)>,
+    range: usize,
+    longest: u64,
+) -> Outcome {
+    let (height, width) = match b {
+        Some((b, _, _)) => (a.height.max(b.height), a.width.max(b.width)),
+        None => (a.height, a.width),
+    };
+    let (m, n) = (height + 2 * (range + ROOM), width + 2 * (range + ROOM));
+    let mut matrix = Matrix::new(m, n, rules.clone());
+    let at = range + ROOM;
+    a.overlay(&mut matrix, (at, at));
+    if let Some((b, dx, dy)) = b {
+        b.overlay(
+            &mut matrix,
+            ((at as i64 + dy) as usize, (at as i64 + dx) as usize),
+        );
+    }
+    settle(&mut matrix, longest);
+    apgcode::census(&matrix)
+}
+
+/// Collides `b` with `a` at every offset up to `range` cells and every
+/// phase below `phases`, running each for at most `longest` generations.
+pub fn collide(
+    rules: &Rulestring,
+    a: &Pattern,
+    b: &Pattern,
+    range: usize,
+    phases: u64,
+    longest: u64,
+) -> Collisions {
+    let phased: Vec<Pattern> = (0..phases.max(1))
+        .map(|phase| advance(b, rules, phase))
+        .collect();
+    let first = cells(a);
+    let reach = range as i64;
+
+    let placements: Vec<Placement> = (0..phases.max(1))
+        .flat_map(|phase| {
+            (-reach..=reach)
+                .flat_map(move |dy| (-reach..=reach).map(move |dx| Placement { dx, dy, phase }))
+        })
+        .filter(|p| {
+            // Cells two apart already share a neighbour.
+            cells(&phased[p.phase as usize]).iter().all(|&(x, y)| {
+                first
+                    .iter()
+                    .all(|&(fx, fy)| (x + p.dx - fx).abs() > 2 || (y + p.dy - fy).abs() > 2)
+            })
+        })
+        .collect();
+
+    // Both of them on their own, to tell the misses by.
+    let mut apart: HashMap<Option<String>, usize> = HashMap::new();
+    for (code, count) in run(rules, a, None, range, longest)
+        .into_iter()
+        .chain(run(rules, b, None, range, longest))
+    {
+        *apart.entry(code).or_insert(0) += count;
+    }
+
+    let results: Vec<(Placement, Outcome)> = placements
+        .into_par_iter()
+        .map(|p| {
+            let b = &phased[p.phase as usize];
+            (p, run(rules, a, Some((b, p.dx, p.dy)), range, longest))
+        })
+        .collect();
+
+    let mut missed = 0;
+    let mut tallies: HashMap<Outcome, Tally> = HashMap::new();
+    for (placement, outcome) in results {
+        let counts: HashMap<_, _> = outcome.iter().cloned().collect();
+        if counts == apart {
+            missed += 1;
+            continue;
+        }
+        tallies
+            .entry(outcome)
+            .and_modify(|t| {
+                t.count += 1;
+                t.first = t.first.min(placement);
+            })
+            .or_insert(Tally {
+                count: 1,
+                first: placement,
+            });
+    }
+
+    let mut outcomes: Vec<_> = tallies.into_iter().collect();
+    outcomes.sort_by(|a, b| b.1.count.cmp(&a.1.count).then_with(|| a.0.cmp(&b.0)));
+    Collisions { outcomes, missed }
+}
@@ -0,0 +1,475 @@
+//! Conditions on how a run is going, such as `population<10`, for stopping
+//! it at the interesting moment.
+//!
+//! A condition compares one of the numbers in `Stats` with a value, or asks
+//! what the dashboard has seen the world settle into:
+//!
+//! ```text
+//! generation>=1103    population<10    births==0    density>0.5
+//! period_detected     dead             still        oscillating    ship
+//! ```
+//!
+//! and any of them can be put together with `&&`, `||`, `!` and brackets,
+//! `&&` going first, as in `births==0 || (population>5000 && !ship)`.
+//!
+//! `gen` and `pop` will do for the longer names, `=` for `==`, and `and`,
+//! `or` and `not` for `&&`, `||` and `!`. A number on its own stands for
+//! the generation it is.
+
+use std::str::FromStr;
+use std::{error, fmt};
+
+use crate::dashboard::Period;
+use crate::stats::Stats;
+
+/// Why a condition couldn't be read.
+#[derive(Debug)]
+pub enum ConditionError {
+    Comparison(String),
+    Quantity(String),
+    Value(String),
+}
+
+impl error::Error for ConditionError {}
+
+impl fmt::Display for ConditionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConditionError::Comparison(s) => {
+                write!(f, "Invalid condition {:?}: expected something like population<10", s)
+            }
+            ConditionError::Quantity(s) => write!(
+                f,
+                "Invalid condition: {:?} isn't one of generation, population, births, deaths, density, activity, period_detected, dead, still, oscillating or ship",
+                s
+            ),
+            ConditionError::Value(s) => write!(f, "Invalid condition: {:?} isn't a number", s),
+        }
+    }
+}
+
+/// The numbers a condition can look at.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Quantity {
+    Generation,
+    Population,
+    Births,
+    Deaths,
+    Density,
+    Activity,
+}
+
+impl Quantity {
+    fn of(self, stats: &Stats) -> f64 {
+        match self {
+            Quantity::Generation => stats.generation as f64,
+            Quantity::Population => stats.population as f64,
+            Quantity::Births => stats.births as f64,
+            Quantity::Deaths => stats.deaths as f64,
+            Quantity::Density => stats.density(),
+            Quantity::Activity => stats.activity(),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Quantity::Generation => "generation",
+            Quantity::Population => "population",
+            Quantity::Births => "births",
+            Quantity::Deaths => "deaths",
+            Quantity::Density => "density",
+            Quantity::Activity => "activity",
+        }
+    }
+}
+
+impl FromStr for Quantity {
+    type Err = ConditionError;
+
+    fn from_str(s: &str) -> Result<Quantity, ConditionError> {
+        match s.to_ascii_lowercase().as_str() {
+            "generation" | "gen" => Ok(Quantity::Generation),
+            "population" | "pop" => Ok(Quantity::Population),
+            "births" => Ok(Quantity::Births),
+            "deaths" => Ok(Quantity::Deaths),
+            "density" => Ok(Quantity::Density),
+            "activity" => Ok(Quantity::Activity),
+            _ => Err(ConditionError::Quantity(s.to_string())),
+        }
+    }
+}
+
+/// What the world has settled into, as far as the dashboard can tell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Settled {
+    /// Anything at all: a still life, an oscillator, a spaceship or nothing.
+    Any,
+    Dead,
+    Still,
+    Oscillating,
+    Ship,
+}
+
+impl Settled {
+    fn of(self, period: Period) -> bool {
+        match self {
+            Settled::Any => period != Period::Changing,
+            Settled::Dead => period == Period::Dead,
+            Settled::Still => period == Period::Still,
+            Settled::Oscillating => matches!(period, Period::Cycle(_)),
+            Settled::Ship => matches!(period, Period::Ship { .. }),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Settled::Any => "period_detected",
+            Settled::Dead => "dead",
+            Settled::Still => "still",
+            Settled::Oscillating => "oscillating",
+            Settled::Ship => "ship",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Settled> {
+        match s.to_ascii_lowercase().as_str() {
+            "period_detected" | "settled" => Some(Settled::Any),
+            "dead" => Some(Settled::Dead),
+            "still" => Some(Settled::Still),
+            "oscillating" => Some(Settled::Oscillating),
+            "ship" | "spaceship" => Some(Settled::Ship),
+            _ => None,
+        }
+    }
+}
+
+/// How a quantity is compared with the value. The longer signs come first,
+/// so `<=` isn't read as `<`.
+const COMPARISONS: &[&str] = &["<=", ">=", "==", "!=", "<", ">", "="];
+
+/// A comparison, what the world has settled into, or some of them put
+/// together.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    Compare {
+        quantity: Quantity,
+        comparison: &'static str,
+        value: f64,
+    },
+    Settled(Settled),
+    Not(Box<Condition>),
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+}
+
+impl Condition {
+    /// True once the run reaches the given generation.
+    pub fn generation(generation: u64) -> Condition {
+        Condition::Compare {
+            quantity: Quantity::Generation,
+            comparison: "==",
+            value: generation as f64,
+        }
+    }
+
+    /// Whether the condition holds for a generation, which the dashboard
+    /// says has settled into `period`.
+    pub fn holds(&self, stats: &Stats, period: Period) -> bool {
+        match self {
+            Condition::Compare {
+                quantity,
+                comparison,
+                value,
+            } => {
+                let (x, value) = (quantity.of(stats), *value);
+                match *comparison {
+                    "<=" => x <= value,
+                    ">=" => x >= value,
+                    "!=" => x != value,
+                    "<" => x < value,
+                    ">" => x > value,
+                    _ => x == value,
+                }
+            }
+            Condition::Settled(settled) => settled.of(period),
+            Condition::Not(c) => !c.holds(stats, period),
+            Condition::All(cs) => cs.iter().all(|c| c.holds(stats, period)),
+            Condition::Any(cs) => cs.iter().any(|c| c.holds(stats, period)),
+        }
+    }
+}
+
+/// Splits a condition into names, numbers, comparison signs, brackets,
+/// `&&`, `||` and `!`, with `and`, `or` and `not` read as the last three.
+fn tokens(s: &str) -> Result<Vec<String>, ConditionError> {
+    let word = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-');
+    let mut tokens = Vec::new();
+    let mut rest = s.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let len = if word(c) {
+            rest.find(|c| !word(c)).unwrap_or(rest.len())
+        } else if let Some(sign) = ["&&", "||", "(", ")"]
+            .iter()
+            .chain(COMPARISONS)
+            .find(|sign| rest.starts_with(*sign))
+        {
+            sign.len()
+        } else if c == '!' {
+            1
+        } else {
+            return Err(ConditionError::Comparison(s.trim().to_string()));
+        };
+        let token = match rest[..len].to_ascii_lowercase().as_str() {
+            "and" => "&&".to_string(),
+            "or" => "||".to_string(),
+            "not" => "!".to_string(),
+            _ => rest[..len].to_string(),
+        };
+        tokens.push(token);
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+/// Reads conditions joined by `||`, each of which is conditions joined by
+/// `&&`, each of which is a comparison, a name, a condition in brackets or
+/// the opposite of any of those.
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<String>,
+    at: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.at).map(String::as_str)
+    }
+
+    fn bad(&self) -> ConditionError {
+        ConditionError::Comparison(self.text.to_string())
+    }
+
+    fn any(&mut self) -> Result<Condition, ConditionError> {
+        let mut all = vec![self.all()?];
+        while self.peek() == Some("||") {
+            self.at += 1;
+            all.push(self.all()?);
+        }
+        Ok(if all.len() == 1 {
+            all.remove(0)
+        } else {
+            Condition::Any(all)
+        })
+    }
+
+    fn all(&mut self) -> Result<Condition, ConditionError> {
+        let mut all = vec![self.one()?];
+        while self.peek() == Some("&&") {
+            self.at += 1;
+            all.push(self.one()?);
+        }
+        Ok(if all.len() == 1 {
+            all.remove(0)
+        } else {
+            Condition::All(all)
+        })
+    }
+
+    fn one(&mut self) -> Result<Condition, ConditionError> {
+        let token = self.peek().ok_or_else(|| self.bad())?.to_string();
+        self.at += 1;
+        match token.as_str() {
+            "!" => Ok(Condition::Not(Box::new(self.one()?))),
+            "(" => {
+                let inside = self.any()?;
+                if self.peek() != Some(")") {
+                    return Err(self.bad());
+                }
+                self.at += 1;
+                Ok(inside)
+            }
+            ")" | "&&" | "||" => Err(self.bad()),
+            _ => match self.peek() {
+                Some(sign) if COMPARISONS.contains(&sign) => {
+                    let sign = COMPARISONS.iter().find(|&&c| c == sign).unwrap();
+                    let value = self.tokens.get(self.at + 1).ok_or_else(|| self.bad())?;
+                    let value = value
+                        .parse()
+                        .map_err(|_| ConditionError::Value(value.to_string()))?;
+                    self.at += 2;
+                    Ok(Condition::Compare {
+                        quantity: token.parse()?,
+                        comparison: if *sign == "=" { "==" } else { sign },
+                        value,
+                    })
+                }
+                _ => named(&token),
+            },
+        }
+    }
+}
+
+/// Reads a name on its own, or a generation.
+fn named(s: &str) -> Result<Condition, ConditionError> {
+    if let Ok(generation) = s.parse::<u64>() {
+        return Ok(Condition::generation(generation));
+    }
+    Settled::parse(s)
+        .map(Condition::Settled)
+        .ok_or_else(|| ConditionError::Quantity(s.to_string()))
+}
+
+impl FromStr for Condition {
+    type Err = ConditionError;
+
+    fn from_str(s: &str) -> Result<Condition, ConditionError> {
+        let mut parser = Parser {
+            text: s.trim(),
+            tokens: tokens(s)?,
+            at: 0,
+        };
+        let condition = parser.any()?;
+        if parser.at < parser.tokens.len() {
+            return Err(parser.bad());
+        }
+        Ok(condition)
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Only conditions joined with `||` inside `&&` need brackets to be
+        // read back the same.
+        let join = |f: &mut fmt::Formatter<'_>, cs: &[Condition], op: &str| {
+            for (i, c) in cs.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {} ", op)?;
+                }
+                match c {
+                    Condition::Any(_) if op == "&&" => write!(f, "({})", c)?,
+                    _ => write!(f, "{}", c)?,
+                }
+            }
+            Ok(())
+        };
+        match self {
+            Condition::Compare {
+                quantity,
+                comparison,
+                value,
+            } => write!(f, "{}{}{}", quantity.name(), comparison, value),
+            Condition::Settled(settled) => write!(f, "{}", settled.name()),
+            Condition::Not(c) => match **c {
+                Condition::All(_) | Condition::Any(_) => write!(f, "!({})", c),
+                _ => write!(f, "!{}", c),
+            },
+            Condition::All(cs) => join(f, cs, "&&"),
+            Condition::Any(cs) => join(f, cs, "||"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(generation: u64, population: usize) -> Stats {
+        Stats {
+            generation,
+            population,
+            births: 1,
+            deaths: 0,
+            cells: 100,
+            fps: 0.0,
+            trend: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn comparisons() {
+        assert_eq!(
+            "pop < 10".parse::<Condition>().unwrap(),
+            Condition::Compare {
+                quantity: Quantity::Population,
+                comparison: "<",
+                value: 10.0,
+            }
+        );
+        assert_eq!(
+            "gen=1103".parse::<Condition>().unwrap(),
+            Condition::generation(1103)
+        );
+        assert_eq!(
+            "1103".parse::<Condition>().unwrap(),
+            Condition::generation(1103)
+        );
+    }
+
+    #[test]
+    fn and_goes_before_or() {
+        let condition: Condition = "births==0 or population>5000 and not ship".parse().unwrap();
+        let Condition::Any(any) = &condition else {
+            panic!("expected ||, got {:?}", condition);
+        };
+        assert_eq!(any.len(), 2);
+        assert!(matches!(&any[1], Condition::All(all) if all.len() == 2));
+
+        assert!(condition.holds(&stats(5, 6000), Period::Changing));
+        assert!(!condition.holds(
+            &stats(5, 6000),
+            Period::Ship {
+                period: 4,
+                dy: 1,
+                dx: 1
+            }
+        ));
+    }
+
+    #[test]
+    fn settled() {
+        let condition: Condition = "period_detected".parse().unwrap();
+        assert!(!condition.holds(&stats(0, 5), Period::Changing));
+        assert!(condition.holds(&stats(0, 5), Period::Still));
+        assert!("dead && gen>10"
+            .parse::<Condition>()
+            .unwrap()
+            .holds(&stats(11, 0), Period::Dead));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            "size>3".parse::<Condition>(),
+            Err(ConditionError::Quantity(_))
+        ));
+        assert!(matches!(
+            "pop<lots".parse::<Condition>(),
+            Err(ConditionError::Value(_))
+        ));
+        for bad in ["", "pop<", "(dead", "dead)", "dead && || still", "pop ~ 3"] {
+            assert!(
+                matches!(bad.parse::<Condition>(), Err(ConditionError::Comparison(_))),
+                "{:?}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for text in [
+            "population<10",
+            "generation==1103",
+            "births==0 || population>5000 && !ship",
+            "(dead || still) && generation>=100",
+            "!(density>0.5 && activity<0.1)",
+        ] {
+            let condition: Condition = text.parse().unwrap();
+            assert_eq!(condition.to_string(), text);
+            assert_eq!(
+                condition.to_string().parse::<Condition>().unwrap(),
+                condition
+            );
+        }
+    }
+}
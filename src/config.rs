@@ -0,0 +1,92 @@
+//! The config file, for choices that should outlast a single run.
+//!
+//! It lives at `iron-cat/config.toml` in the config directory, or wherever
+//! `--config` says. A missing file at the usual place is the same as an
+//! empty one. So far it only has a `[keys]` section, described in `keys`,
+//! where each action is given one key or a list of them.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::{error, fmt, fs, io};
+
+use serde::Deserialize;
+
+/// Why the config file couldn't be loaded.
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+}
+
+impl error::Error for ConfigError {}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "Could not read config file: {}", e),
+            ConfigError::Parse(e) => write!(f, "Could not parse config file: {}", e),
+        }
+    }
+}
+
+/// Everything the config file can hold.
+#[derive(Debug, Default)]
+pub struct Config {
+    /// Actions by name, and the keys to do them with instead of the usual.
+    pub keys: BTreeMap<String, Vec<String>>,
+}
+
+/// The raw shape of the config file.
+#[derive(Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    keys: BTreeMap<String, Keys>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Where our files live: `iron-cat` in `$XDG_CONFIG_HOME`, or else in
+/// `~/.config`, or `%APPDATA%` on Windows.
+pub fn dir() -> Option<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+
+    Some(config.join("iron-cat"))
+}
+
+impl Config {
+    /// Reads the config file at `path`, or at the usual place if there's no
+    /// `path`, where it needn't exist.
+    pub fn load(path: Option<&Path>) -> Result<Config, ConfigError> {
+        let text = match path {
+            Some(path) => fs::read_to_string(path).map_err(ConfigError::Io)?,
+            None => match dir().map(|d| fs::read_to_string(d.join("config.toml"))) {
+                Some(Ok(text)) => text,
+                Some(Err(e)) if e.kind() != io::ErrorKind::NotFound => {
+                    return Err(ConfigError::Io(e))
+                }
+                _ => return Ok(Config::default()),
+            },
+        };
+        let file: ConfigFile = toml::from_str(&text).map_err(ConfigError::Parse)?;
+
+        Ok(Config {
+            keys: file
+                .keys
+                .into_iter()
+                .map(|(action, keys)| match keys {
+                    Keys::One(key) => (action, vec![key]),
+                    Keys::Many(keys) => (action, keys),
+                })
+                .collect(),
+        })
+    }
+}
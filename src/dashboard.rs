@@ -0,0 +1,522 @@
+//! The dashboard: a panel of statistics next to the grid, for keeping an
+//! eye on how a run is going.
+//!
+//! It shows the rule, the generation and the population, a graph of the
+//! population over the last few generations, whether the world has
+//! settled into a still life, an oscillator or a spaceship flying off,
+//! since when and between what populations, and if so the apgcodes of what
+//! it settled into, and what has happened lately. It goes to the right of
+//! the grid when the terminal is wide enough to spare the room, and
+//! squeezes into a few lines under it when it isn't.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::json;
+use crate::pattern::apgcode;
+use crate::stats::Stats;
+use crate::view::DIVIDER;
+use crate::Matrix;
+
+/// Columns the panel takes up beside the grid, which is also how many
+/// generations its graph goes back.
+pub const WIDTH: usize = 28;
+
+/// Rows the panel takes up under the grid, when there's no room beside it.
+const BELOW: usize = 3;
+
+/// Rows of the population graph beside the grid.
+const GRAPH: usize = 5;
+
+/// How many generations back to look for the world repeating itself.
+const LOOKBACK: usize = 256;
+
+/// How many things that happened to remember.
+const EVENTS: usize = 32;
+
+/// The most live cells a settled world may have for its objects to be
+/// identified, which takes a while.
+const CENSUS: usize = 10_000;
+
+/// How many kinds of object to list.
+const OBJECTS: usize = 4;
+
+/// Whether the world has stopped changing, or only goes round in circles,
+/// or flies off as a spaceship, coming back to the same shape `period`
+/// generations later moved `dy` rows down and `dx` columns right.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Period {
+    Changing,
+    Dead,
+    Still,
+    Cycle(u64),
+    Ship { period: u64, dy: i64, dx: i64 },
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Period::Changing => write!(f, "still changing"),
+            Period::Dead => write!(f, "died out"),
+            Period::Still => write!(f, "still life"),
+            Period::Cycle(p) => write!(f, "period {}", p),
+            Period::Ship { period, dy, dx } => write!(f, "{} spaceship", speed(*period, *dy, *dx)),
+        }
+    }
+}
+
+/// When the world settled, and the least and most cells alive in it since.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cycle {
+    pub start: u64,
+    pub low: usize,
+    pub high: usize,
+}
+
+impl Cycle {
+    /// The populations it goes between, or the one it stays at.
+    fn populations(&self) -> String {
+        if self.low == self.high {
+            self.low.to_string()
+        } else {
+            format!("{}–{}", self.low, self.high)
+        }
+    }
+}
+
+/// What's remembered of a generation: hashes of its cells and of their
+/// shape wherever they are, where that shape is and how many cells are
+/// alive.
+#[derive(Debug)]
+struct Seen {
+    generation: u64,
+    hash: u64,
+    shape: u64,
+    origin: (usize, usize),
+    population: usize,
+}
+
+/// What the dashboard knows about the run.
+#[derive(Debug)]
+pub struct Dashboard {
+    /// Whether it's on screen.
+    pub shown: bool,
+    pub period: Period,
+    /// Where the cycle began, once the world is repeating itself.
+    pub cycle: Option<Cycle>,
+    /// The objects the world settled into, by apgcode, and how many of
+    /// each there are.
+    pub objects: Vec<(Option<String>, usize)>,
+    /// Each recent generation, to spot the world repeating.
+    seen: VecDeque<Seen>,
+    /// What happened lately, and when, oldest first.
+    events: VecDeque<(u64, String)>,
+}
+
+impl Dashboard {
+    pub fn new(shown: bool) -> Dashboard {
+        Dashboard {
+            shown,
+            period: Period::Changing,
+            cycle: None,
+            objects: Vec::new(),
+            seen: VecDeque::with_capacity(LOOKBACK),
+            events: VecDeque::with_capacity(EVENTS),
+        }
+    }
+
+    /// Keeps something that just happened among the recent events.
+    pub fn log(&mut self, generation: u64, event: String) {
+        if self.events.len() == EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back((generation, event));
+    }
+
+    /// Looks for the world repeating itself after a generation, in place
+    /// or moved along as a spaceship, and says so when it starts or stops,
+    /// for the events.
+    pub fn watch(&mut self, matrix: &Matrix) -> Option<String> {
+        let mut hasher = DefaultHasher::new();
+        matrix.rows.hash(&mut hasher);
+        let hash = hasher.finish();
+        let (shape, origin) = shape(matrix);
+        let generation = matrix.generation;
+        let population = matrix.rows.iter().filter(|&&c| c != 0).count();
+
+        // Anything from later on was undone by going back.
+        while self.seen.back().is_some_and(|s| s.generation >= generation) {
+            self.seen.pop_back();
+        }
+
+        // A spaceship comes back round to where it started too, in a world
+        // that wraps, but its shape comes back moved long before.
+        let repeat = self.seen.iter().rev().find(|s| s.hash == hash);
+        let moved = self
+            .seen
+            .iter()
+            .rev()
+            .find(|s| s.shape == shape)
+            .filter(|s| s.origin != origin);
+        let (period, start) = match (repeat, moved) {
+            _ if population == 0 => (Period::Dead, generation),
+            (_, Some(s)) => (
+                Period::Ship {
+                    period: generation - s.generation,
+                    dy: offset(s.origin.0, origin.0, matrix.m),
+                    dx: offset(s.origin.1, origin.1, matrix.n),
+                },
+                s.generation,
+            ),
+            (Some(s), _) if generation - s.generation == 1 => (Period::Still, s.generation),
+            (Some(s), _) => (Period::Cycle(generation - s.generation), s.generation),
+            (None, None) => (Period::Changing, generation),
+        };
+
+        if self.seen.len() == LOOKBACK {
+            self.seen.pop_front();
+        }
+        self.seen.push_back(Seen {
+            generation,
+            hash,
+            shape,
+            origin,
+            population,
+        });
+
+        if period == self.period {
+            return None;
+        }
+        let event = match period {
+            Period::Changing => "Started changing again".to_string(),
+            Period::Dead => "Died out".to_string(),
+            Period::Still => "Settled into a still life".to_string(),
+            Period::Cycle(p) => format!("Settled into period {}", p),
+            Period::Ship { .. } => format!("Flying as a {}", period),
+        };
+        self.period = period;
+
+        // The first time round, the cycle began with the generation it
+        // has just come back to.
+        let populations = self
+            .seen
+            .iter()
+            .filter(|s| s.generation >= start)
+            .map(|s| s.population);
+        self.cycle = match period {
+            Period::Changing => None,
+            _ => Some(Cycle {
+                start,
+                low: populations.clone().min().unwrap_or(0),
+                high: populations.max().unwrap_or(0),
+            }),
+        };
+        self.objects = match period {
+            Period::Still | Period::Cycle(_) | Period::Ship { .. } if population <= CENSUS => {
+                apgcode::census(matrix)
+            }
+            _ => Vec::new(),
+        };
+        Some(event)
+    }
+
+    /// Forgets what the world has been doing, after it has been changed
+    /// by hand.
+    pub fn unsettle(&mut self) {
+        self.period = Period::Changing;
+        self.cycle = None;
+        self.objects.clear();
+    }
+
+    /// What the world settled into and since when, for the status bar.
+    pub fn report(&self) -> Option<String> {
+        let cycle = self.cycle?;
+        Some(match self.period {
+            Period::Changing => return None,
+            Period::Dead => format!("died out at gen {}", cycle.start),
+            Period::Still => format!("still life since gen {}", cycle.start),
+            Period::Cycle(p) => format!(
+                "period {} since gen {}, pop {}",
+                p,
+                cycle.start,
+                cycle.populations()
+            ),
+            Period::Ship { dy, dx, .. } => format!(
+                "{} heading {} since gen {}",
+                self.period,
+                heading(dy, dx),
+                cycle.start
+            ),
+        })
+    }
+
+    /// How the run ended, for `--summary`: as a line or two of text, or as
+    /// a JSON object. The damage between twins is only given for a run
+    /// that had one.
+    pub fn summary(&self, stats: &Stats, damage: Option<usize>, as_json: bool) -> String {
+        let period = match self.period {
+            Period::Changing | Period::Dead => None,
+            Period::Still => Some(1),
+            Period::Cycle(p) | Period::Ship { period: p, .. } => Some(p),
+        };
+        if !as_json {
+            let mut text = format!(
+                "generation {}, population {}\n",
+                stats.generation, stats.population
+            );
+            text.push_str(&self.report().unwrap_or_else(|| self.period.to_string()));
+            if let Some(damage) = damage {
+                text.push_str(&format!("\ndamage {}", damage));
+            }
+            return text;
+        }
+
+        let or_null = |n: Option<String>| n.unwrap_or_else(|| "null".to_string());
+        format!(
+            "{{\"generation\": {}, \"population\": {}, \"state\": {}, \"period\": {}, \"since\": {}, \"populations\": {}, \"displacement\": {}, \"speed\": {}{}}}",
+            stats.generation,
+            stats.population,
+            json::string(match self.period {
+                Period::Changing => "changing",
+                Period::Dead => "dead",
+                Period::Still => "still",
+                Period::Cycle(_) => "cycle",
+                Period::Ship { .. } => "ship",
+            }),
+            or_null(period.map(|p| p.to_string())),
+            or_null(self.cycle.map(|c| c.start.to_string())),
+            or_null(self.cycle.map(|c| format!("[{}, {}]", c.low, c.high))),
+            match self.period {
+                Period::Ship { dy, dx, .. } => format!("[{}, {}]", dy, dx),
+                _ => "null".to_string(),
+            },
+            match self.period {
+                Period::Ship { period, dy, dx } => json::string(&speed(period, dy, dx)),
+                _ => "null".to_string(),
+            },
+            damage.map_or(String::new(), |d| format!(", \"damage\": {}", d)),
+        )
+    }
+
+    /// The panel for beside the grid, `height` lines at most.
+    pub fn panel(&self, matrix: &Matrix, stats: &Stats, height: usize) -> Vec<String> {
+        let populations = &stats.trend[stats.trend.len().saturating_sub(WIDTH)..];
+        let peak = populations.iter().max().copied().unwrap_or(0);
+
+        let mut lines = vec![
+            "Dashboard".to_string(),
+            format!("rule     {}", matrix.rules),
+            format!("gen      {}", stats.generation),
+            format!(
+                "pop      {} (+{} -{})",
+                stats.population, stats.births, stats.deaths
+            ),
+            format!("density  {:.1}%", stats.density() * 100.0),
+            format!("period   {}", self.period),
+        ];
+        if let (Some(cycle), Period::Still | Period::Cycle(_) | Period::Ship { .. }) =
+            (self.cycle, self.period)
+        {
+            lines.push(format!("since    gen {}", cycle.start));
+            if let Period::Cycle(_) = self.period {
+                lines.push(format!("pop      {}", cycle.populations()));
+            }
+        }
+        for (code, count) in self.objects.iter().take(OBJECTS) {
+            let code = code.as_deref().unwrap_or("unidentified");
+            lines.push(format!("{:>8} {}", format!("{}x", count), code));
+        }
+        if self.objects.len() > OBJECTS {
+            lines.push(format!(
+                "{:>8} more",
+                format!("+{}", self.objects.len() - OBJECTS)
+            ));
+        }
+        lines.extend([String::new(), format!("population, peak {}", peak)]);
+        lines.extend(graph(populations, GRAPH));
+        lines.push(String::new());
+        lines.push("Recent".to_string());
+
+        let room = height.saturating_sub(lines.len());
+        let skip = self.events.len().saturating_sub(room);
+        lines.extend(
+            self.events
+                .iter()
+                .skip(skip)
+                .map(|(g, event)| format!("{:>5} {}", g, event)),
+        );
+
+        lines
+            .into_iter()
+            .take(height)
+            .map(|line| line.chars().take(WIDTH).collect::<String>() + "\x1B[K")
+            .collect()
+    }
+
+    /// The panel squeezed into a few lines for under the grid.
+    pub fn strip(&self, matrix: &Matrix, stats: &Stats, width: usize) -> Vec<String> {
+        let populations = &stats.trend[stats.trend.len().saturating_sub(width)..];
+        let last = self
+            .events
+            .back()
+            .map_or(String::new(), |(g, event)| format!("{:>5} {}", g, event));
+
+        vec![
+            format!(
+                "{} │ {} │ peak {}",
+                matrix.rules,
+                self.period,
+                populations.iter().max().copied().unwrap_or(0)
+            ),
+            graph(populations, 1).remove(0),
+            last,
+        ]
+        .into_iter()
+        .map(|line| line.chars().take(width).collect::<String>() + "\x1B[K")
+        .collect()
+    }
+}
+
+/// Hashes the live cells as they are wherever they are in the world, and
+/// says where that is: the top left corner of the smallest rectangle
+/// holding them all. The world wraps round, so the rectangle starts after
+/// the widest stretch of empty rows or columns, and may go off one edge and
+/// come back on the other.
+fn shape(matrix: &Matrix) -> (u64, (usize, usize)) {
+    let mut rows = vec![false; matrix.m];
+    let mut columns = vec![false; matrix.n];
+    for (i, _) in matrix.rows.iter().enumerate().filter(|(_, &c)| c != 0) {
+        rows[i / matrix.n] = true;
+        columns[i % matrix.n] = true;
+    }
+    let (top, height) = span(&rows);
+    let (left, width) = span(&columns);
+
+    let mut hasher = DefaultHasher::new();
+    (height, width).hash(&mut hasher);
+    for r in 0..height {
+        for c in 0..width {
+            let (y, x) = ((top + r) % matrix.m, (left + c) % matrix.n);
+            matrix.rows[y * matrix.n + x].hash(&mut hasher);
+        }
+    }
+    (hasher.finish(), (top, left))
+}
+
+/// Where the occupied stretch of a ring of rows or columns starts and how
+/// long it is, leaving out the longest empty stretch.
+fn span(occupied: &[bool]) -> (usize, usize) {
+    let size = occupied.len();
+    let Some(first) = occupied.iter().position(|&o| o) else {
+        return (0, 0);
+    };
+
+    // Going round once from an occupied one, every empty stretch is seen
+    // whole.
+    let (mut gap, mut end, mut run) = (0, first, 0);
+    for i in 1..=size {
+        if occupied[(first + i) % size] {
+            if run > gap {
+                (gap, end) = (run, (first + i) % size);
+            }
+            run = 0;
+        } else {
+            run += 1;
+        }
+    }
+    (end, size - gap)
+}
+
+/// How far a corner moved from `from` to `to` along a ring of `size`, the
+/// shorter way round.
+fn offset(from: usize, to: usize, size: usize) -> i64 {
+    let ahead = (to + size - from) % size;
+    if ahead * 2 > size {
+        ahead as i64 - size as i64
+    } else {
+        ahead as i64
+    }
+}
+
+/// A spaceship's speed as it's usually written, such as `c/4 diagonal` for
+/// a glider or `c/2 orthogonal` for a lightweight spaceship.
+fn speed(period: u64, dy: i64, dx: i64) -> String {
+    let (a, b) = (dy.unsigned_abs(), dx.unsigned_abs());
+    let (distance, kind) = match (a, b) {
+        (0, d) | (d, 0) => (d, "orthogonal"),
+        _ if a == b => (a, "diagonal"),
+        _ => return format!("({}, {})c/{} oblique", a.max(b), a.min(b), period),
+    };
+
+    let mut gcd = (distance, period);
+    while gcd.1 != 0 {
+        gcd = (gcd.1, gcd.0 % gcd.1);
+    }
+    let (distance, period) = (distance / gcd.0, period / gcd.0);
+    match distance {
+        1 => format!("c/{} {}", period, kind),
+        _ => format!("{}c/{} {}", distance, period, kind),
+    }
+}
+
+/// Which way a spaceship is going, as a point of the compass with north
+/// at the top.
+fn heading(dy: i64, dx: i64) -> String {
+    let north = match dy.signum() {
+        -1 => "north",
+        1 => "south",
+        _ => "",
+    };
+    let east = match dx.signum() {
+        -1 => "west",
+        1 => "east",
+        _ => "",
+    };
+    match (north, east) {
+        ("", e) => e.to_string(),
+        (n, "") => n.to_string(),
+        (n, e) => format!("{}-{}", n, e),
+    }
+}
+
+/// Whether the panel goes beside a grid in a terminal `columns` wide,
+/// which it does when the grid still gets twice its room.
+pub fn beside(columns: usize) -> bool {
+    columns >= WIDTH * 3 + DIVIDER.chars().count()
+}
+
+/// The room left for the grid in a terminal of `columns` by `rows`
+/// characters, once the panel has taken its share.
+pub fn room((columns, rows): (usize, usize)) -> (usize, usize) {
+    if beside(columns) {
+        (columns - WIDTH - DIVIDER.chars().count(), rows)
+    } else {
+        (columns, rows.saturating_sub(BELOW))
+    }
+}
+
+/// Plots populations from zero up to the highest as bars `height` rows
+/// tall, a column each.
+fn graph(populations: &[usize], height: usize) -> Vec<String> {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let peak = populations.iter().max().copied().unwrap_or(0).max(1);
+    let levels: Vec<usize> = populations
+        .iter()
+        .map(|&p| (p * height * BARS.len()).div_ceil(peak))
+        .collect();
+
+    (0..height)
+        .rev()
+        .map(|row| {
+            levels
+                .iter()
+                .map(|&level| match level.saturating_sub(row * BARS.len()) {
+                    0 => ' ',
+                    fill => BARS[fill.min(BARS.len()) - 1],
+                })
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,315 @@
+//! Demos: tours of patterns and rules that run by themselves, for leaving
+//! on a screen.
+//!
+//! A demo is a TOML file of steps, taken one after another:
+//!
+//! ```toml
+//! repeat = true
+//!
+//! [[step]]
+//! pattern = "R-pentomino"
+//! rule = "B3/S23"
+//! say = "The R-pentomino takes 1103 generations to settle"
+//! speed = 0.05
+//! run = 1103
+//!
+//! [[step]]
+//! reseed = 0.3
+//! rule = "B36/S23"
+//! say = "HighLife"
+//! zoom = 2
+//! run = 200
+//!
+//! [[step]]
+//! pause = 3.0
+//! ```
+//!
+//! Within a step, `rule` changes the rule, `pattern` clears the world and
+//! puts a built-in pattern or a pattern file in the middle of it, `reseed`
+//! fills it at random as densely as asked instead, `zoom` sets how many
+//! cells each character covers (zero fits the world to the screen),
+//! `speed` sets the seconds between generations and `say` shows a line
+//! under the grid, or else the pattern's name. Then the step `run`s for so
+//! many generations, or `pause`s for so many seconds, or both, before the
+//! next step.
+//!
+//! With `repeat` the demo starts over once it's done, for good; without,
+//! the program ends with the demo. Pattern files are found relative to
+//! the demo.
+//!
+//! `rule = "random"` draws a new rule every time the step is taken, for
+//! leaving a demo on as a screensaver:
+//!
+//! ```toml
+//! repeat = true
+//! judge = 300
+//!
+//! [[step]]
+//! reseed = 0.35
+//! rule = "random"
+//! run = 1000
+//! ```
+//!
+//! Most rules are dull, with everything dying out, freezing or boiling over
+//! straight away, so with `judge` every rule drawn is first run out of
+//! sight on the world as the step has left it for that many generations
+//! and scored on how interesting it looks: nothing for a world that has
+//! died out or stopped, little for one that has filled up or boils, and
+//! most for one that has settled down to a few things still moving, as
+//! Life does. Rules scoring under a third are skipped, and after twenty
+//! tries the best of them is shown anyway. The score of every rule shown
+//! is said under the grid and kept among the dashboard's events.
+
+use std::path::Path;
+use std::time::Duration;
+use std::{error, fmt, fs, io};
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::pattern::{library, Pattern};
+use crate::stats::Stats;
+use crate::{Matrix, RuleError, Rulestring};
+
+/// How many random rules are drawn for a step before giving up on finding
+/// an interesting one.
+const TRIES: usize = 20;
+
+/// The least score a random rule is shown for, when judging.
+const INTERESTING: f64 = 1.0 / 3.0;
+
+/// Why a demo couldn't be loaded.
+#[derive(Debug)]
+pub enum DemoError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Pattern(String, String),
+    Rule(String, RuleError),
+    Forever,
+}
+
+impl error::Error for DemoError {}
+
+impl fmt::Display for DemoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DemoError::Io(e) => write!(f, "Could not read demo: {}", e),
+            DemoError::Parse(e) => write!(f, "Could not parse demo: {}", e),
+            DemoError::Pattern(p, e) => write!(f, "Could not load pattern {} in demo: {}", p, e),
+            DemoError::Rule(r, e) => write!(f, "Invalid rule {} in demo: {}", r, e),
+            DemoError::Forever => write!(
+                f,
+                "Invalid demo: it repeats without ever running or pausing"
+            ),
+        }
+    }
+}
+
+/// The raw shape of a demo file.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DemoFile {
+    #[serde(default)]
+    repeat: bool,
+    judge: Option<u64>,
+    #[serde(default)]
+    step: Vec<StepFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StepFile {
+    rule: Option<String>,
+    pattern: Option<String>,
+    reseed: Option<f64>,
+    zoom: Option<usize>,
+    speed: Option<f64>,
+    say: Option<String>,
+    run: Option<u64>,
+    pause: Option<f64>,
+}
+
+/// The rule a step changes to.
+#[derive(Clone, Debug)]
+pub enum Rule {
+    Given(Rulestring),
+    Random,
+}
+
+/// One step of a demo, everything in it checked.
+#[derive(Clone, Debug)]
+pub struct Step {
+    pub rule: Option<Rule>,
+    /// A pattern to put in the middle of an empty world, and its name.
+    pub pattern: Option<(String, Pattern)>,
+    pub reseed: Option<f64>,
+    pub zoom: Option<usize>,
+    pub speed: Option<Duration>,
+    pub say: Option<String>,
+    pub run: u64,
+    pub pause: Duration,
+}
+
+/// A demo, and how far through it we are.
+#[derive(Debug)]
+pub struct Demo {
+    pub steps: Vec<Step>,
+    pub repeat: bool,
+    /// How many generations to try random rules out for, if at all.
+    pub judge: Option<u64>,
+    /// The step to take next.
+    pub next: usize,
+}
+
+impl Demo {
+    pub fn load(path: &Path) -> Result<Demo, DemoError> {
+        let text = fs::read_to_string(path).map_err(DemoError::Io)?;
+        let file: DemoFile = toml::from_str(&text).map_err(DemoError::Parse)?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+        let mut steps = Vec::new();
+        for step in file.step {
+            let rule = match step.rule {
+                Some(r) if r.eq_ignore_ascii_case("random") => Some(Rule::Random),
+                Some(r) => Some(Rule::Given(r.parse().map_err(|e| DemoError::Rule(r, e))?)),
+                None => None,
+            };
+            let pattern = match step.pattern {
+                Some(name) => {
+                    let pattern =
+                        pattern(dir, &name).map_err(|e| DemoError::Pattern(name.clone(), e))?;
+                    Some((name, pattern))
+                }
+                None => None,
+            };
+
+            steps.push(Step {
+                rule,
+                pattern,
+                reseed: step.reseed,
+                zoom: step.zoom,
+                speed: step.speed.map(|s| Duration::from_secs_f64(s.max(0.0))),
+                say: step.say,
+                run: step.run.unwrap_or(0),
+                pause: Duration::from_secs_f64(step.pause.unwrap_or(0.0).max(0.0)),
+            });
+        }
+
+        // Repeating steps that take no time at all would never let go.
+        if file.repeat && steps.iter().all(|s| s.run == 0 && s.pause.is_zero()) {
+            return Err(DemoError::Forever);
+        }
+
+        Ok(Demo {
+            steps,
+            repeat: file.repeat,
+            judge: file.judge.filter(|&g| g > 0),
+            next: 0,
+        })
+    }
+
+    /// The next step to take, or `None` once the demo is over.
+    pub fn step(&mut self) -> Option<Step> {
+        if self.next == self.steps.len() && self.repeat {
+            self.next = 0;
+        }
+        let step = self.steps.get(self.next).cloned();
+        self.next += 1;
+        step
+    }
+}
+
+/// A random rule drawn for a step, and how it scored if it was judged.
+pub struct Drawn {
+    pub rules: Rulestring,
+    pub score: Option<f64>,
+    /// How many rules were drawn and skipped before it.
+    pub skipped: usize,
+}
+
+/// Draws a rule without B0, which would make the whole world flash, with
+/// every other birth and survival as likely as not.
+fn random_rule(rng: &mut StdRng) -> Rulestring {
+    let mut rules = Rulestring::new();
+    for i in 1..9 {
+        rules.b[i] = rng.gen_bool(0.3);
+    }
+    for i in 0..9 {
+        rules.s[i] = rng.gen_bool(0.4);
+    }
+    rules
+}
+
+/// How interesting `rules` look run on `matrix` for `generations`, from 0
+/// to 1, going by how many cells are alive at the end and how many are
+/// still changing in the second half of the run.
+fn interest(matrix: &Matrix, rules: &Rulestring, generations: u64) -> f64 {
+    let mut world = matrix.clone();
+    world.rules = rules.clone();
+    let mut activity = 0.0;
+    for g in 0..generations {
+        world.pulse();
+        if g >= generations / 2 {
+            activity += Stats::of(&world).activity();
+        }
+    }
+    let activity = activity / (generations - generations / 2) as f64;
+    let density = Stats::of(&world).density();
+    if density == 0.0 {
+        return 0.0;
+    }
+
+    // Something has to be moving, but not everything, and the world can't
+    // have filled up.
+    let moving = activity / (activity + 0.002);
+    let calm = 1.0 - (activity / 0.25).min(1.0);
+    let room = 1.0 - (density / 0.45).min(1.0);
+    moving * calm * room
+}
+
+/// Draws a random rule for the world as a step has left it, skipping
+/// those that don't look interesting enough if the demo judges them.
+pub fn draw(matrix: &Matrix, rng: &mut StdRng, judge: Option<u64>) -> Drawn {
+    let Some(generations) = judge else {
+        return Drawn {
+            rules: random_rule(rng),
+            score: None,
+            skipped: 0,
+        };
+    };
+
+    let mut best: Option<(Rulestring, f64)> = None;
+    for tried in 0..TRIES {
+        let rules = random_rule(rng);
+        let score = interest(matrix, &rules, generations);
+        if score >= INTERESTING {
+            return Drawn {
+                rules,
+                score: Some(score),
+                skipped: tried,
+            };
+        }
+        if best.as_ref().is_none_or(|b| score > b.1) {
+            best = Some((rules, score));
+        }
+    }
+    let (rules, score) = best.expect("at least one rule was tried");
+    Drawn {
+        rules,
+        score: Some(score),
+        skipped: TRIES - 1,
+    }
+}
+
+/// A built-in pattern by name, or else a pattern file.
+fn pattern(dir: &Path, name: &str) -> Result<Pattern, String> {
+    if let Some(pattern) = library::get(name) {
+        return Ok(pattern);
+    }
+
+    let text = fs::read_to_string(dir.join(name)).map_err(|e| e.to_string())?;
+    crate::pattern::read(&text)
+        .map(|file| file.pattern)
+        .map_err(|e| e.to_string())
+}
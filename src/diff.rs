@@ -0,0 +1,174 @@
+//! Where two states of a world differ, for comparing what two engines made
+//! of the same pattern, or two runs that went their own ways:
+//! `iron-cat diff a.rle b.rle` says how many cells are alive in one and not
+//! the other and where, and the `=` key does the same between the world as
+//! it is and a bookmark.
+//!
+//! The two are lined up by their top left corners, as they would be loaded
+//! into a world, and whatever one has beyond the other's edge is compared
+//! with dead cells.
+
+use crate::pattern::Pattern;
+use crate::render::Canvas;
+use crate::theme::Color;
+
+/// The colours of cells dead in both, alive in both, alive only in the
+/// first and alive only in the second.
+const COLORS: [Color; 4] = [
+    Color { r: 0, g: 0, b: 0 },
+    Color {
+        r: 96,
+        g: 96,
+        b: 96,
+    },
+    Color {
+        r: 214,
+        g: 39,
+        b: 40,
+    },
+    Color {
+        r: 44,
+        g: 160,
+        b: 44,
+    },
+];
+
+/// Every cell of two states laid over each other.
+pub struct Diff {
+    pub height: usize,
+    pub width: usize,
+    /// 0 for dead in both, 1 for alive in both, 2 for alive only in the
+    /// first and 3 for alive only in the second.
+    cells: Vec<u8>,
+}
+
+impl Diff {
+    pub fn new(first: &Pattern, second: &Pattern) -> Diff {
+        let (height, width) = (
+            first.height.max(second.height),
+            first.width.max(second.width),
+        );
+        let alive = |p: &Pattern, r: usize, c: usize| {
+            r < p.height && c < p.width && p.cells[r * p.width + c] != 0
+        };
+        let cells = (0..height * width)
+            .map(|i| {
+                let (r, c) = (i / width, i % width);
+                match (alive(first, r, c), alive(second, r, c)) {
+                    (false, false) => 0,
+                    (true, true) => 1,
+                    (true, false) => 2,
+                    (false, true) => 3,
+                }
+            })
+            .collect();
+        Diff {
+            height,
+            width,
+            cells,
+        }
+    }
+
+    fn count(&self, kind: u8) -> usize {
+        self.cells.iter().filter(|&&c| c == kind).count()
+    }
+
+    /// Whether every cell is the same in both.
+    pub fn same(&self) -> bool {
+        self.cells.iter().all(|&c| c < 2)
+    }
+
+    /// The top left and bottom right corners, as `(row, column)`, of the
+    /// cells that differ, if any do.
+    pub fn bounds(&self) -> Option<((usize, usize), (usize, usize))> {
+        let differ: Vec<usize> = (0..self.cells.len())
+            .filter(|&i| self.cells[i] >= 2)
+            .collect();
+        let rows = differ.iter().map(|&i| i / self.width);
+        let cols = differ.iter().map(|&i| i % self.width);
+        Some((
+            (rows.clone().min()?, cols.clone().min()?),
+            (rows.max()?, cols.max()?),
+        ))
+    }
+
+    /// A line saying how much differs and where, calling the two states
+    /// by the names given.
+    pub fn summary(&self, first: &str, second: &str) -> String {
+        let both = self.count(1);
+        let Some(((top, left), (bottom, right))) = self.bounds() else {
+            return format!(
+                "{} and {} are the same, {} cells alive",
+                first, second, both
+            );
+        };
+        let (only_first, only_second) = (self.count(2), self.count(3));
+        format!(
+            "{} cells differ, {} alive only in {} and {} only in {}, with {} alive in both, between rows {} and {} and columns {} and {}",
+            only_first + only_second,
+            only_first,
+            first,
+            only_second,
+            second,
+            both,
+            top,
+            bottom,
+            left,
+            right
+        )
+    }
+
+    /// A small picture of the cells that differ, no more than `height`
+    /// lines of `width` characters: `-` alive only in the first, `+` only
+    /// in the second, `█` alive in both and `·` dead in both. Bigger
+    /// differences are shrunk as `Pattern::thumbnail` does, with `±` where
+    /// a character stands for cells of both kinds.
+    pub fn picture(&self, height: usize, width: usize) -> Vec<String> {
+        let Some(((top, left), (bottom, right))) = self.bounds() else {
+            return Vec::new();
+        };
+        let (rows, cols) = (bottom - top + 1, right - left + 1);
+        let scale = rows
+            .div_ceil(height.max(1))
+            .max(cols.div_ceil(width.max(1)))
+            .max(1);
+        let square = |r: usize, c: usize| {
+            let mut seen = [false; 4];
+            for r in top + r * scale..(top + (r + 1) * scale).min(bottom + 1) {
+                for c in left + c * scale..(left + (c + 1) * scale).min(right + 1) {
+                    seen[self.cells[r * self.width + c] as usize] = true;
+                }
+            }
+            match seen {
+                [_, _, true, true] => '±',
+                [_, _, true, false] => '-',
+                [_, _, false, true] => '+',
+                [_, true, _, _] => '█',
+                _ => '·',
+            }
+        };
+
+        (0..rows.div_ceil(scale))
+            .map(|r| (0..cols.div_ceil(scale)).map(|c| square(r, c)).collect())
+            .collect()
+    }
+
+    /// The whole of both states, `scale` pixels per cell, with the cells
+    /// alive in both grey, those only in the first red and those only in
+    /// the second green.
+    pub fn canvas(&self, scale: usize) -> Canvas {
+        let scale = scale.max(1);
+        let (width, height) = (self.width * scale, self.height * scale);
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(COLORS[self.cells[y / scale * self.width + x / scale] as usize]);
+            }
+        }
+        Canvas {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
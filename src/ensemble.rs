@@ -0,0 +1,69 @@
+//! Running many soups alike but for their seeds, to see how a rule behaves
+//! on the whole rather than in one run.
+//!
+//! `--ensemble 100` runs a hundred soups, seeded from `--rng-seed` on, each
+//! until it settles or for `--generations`, and instead of drawing any of
+//! them says how long they lasted, how many cells they ended with and how
+//! long they took to settle, on average and how much that varies. Run `I`
+//! is the soup the run would start from with the seed plus `I`.
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+
+use crate::search;
+use crate::soup::Soup;
+use crate::{Matrix, Rulestring};
+
+/// How one run went.
+#[derive(Clone, Copy, Debug)]
+pub struct Outcome {
+    /// The generation it settled from, if it did.
+    pub settled: Option<u64>,
+    /// Whether it had died out by then.
+    pub died: bool,
+    /// The cells alive at the end.
+    pub population: usize,
+}
+
+/// Runs `runs` soups in worlds of `m` by `n`, seeded from `seed` on, for at
+/// most `longest` generations each.
+pub fn run(
+    rules: &Rulestring,
+    (m, n): (usize, usize),
+    soup: &Soup,
+    seed: u64,
+    runs: u64,
+    longest: u64,
+) -> Vec<Outcome> {
+    (0..runs)
+        .into_par_iter()
+        .map(|i| {
+            let mut matrix = Matrix::new(m, n, rules.clone());
+            // As the run seeds its first soup; seeding from a `StdRng`
+            // can't fail.
+            let mut rng = StdRng::from_rng(StdRng::seed_from_u64(seed.wrapping_add(i))).unwrap();
+            soup.seed(&mut matrix, &mut rng);
+            let settled = search::settle(&mut matrix, longest);
+
+            let population = matrix.rows.iter().filter(|&&c| c != 0).count();
+            Outcome {
+                settled,
+                died: population == 0,
+                population,
+            }
+        })
+        .collect()
+}
+
+/// The mean of some numbers and their standard deviation, or `None` if
+/// there aren't any.
+pub fn spread(values: &[f64]) -> Option<(f64, f64)> {
+    if values.is_empty() {
+        return None;
+    }
+    let count = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / count;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count;
+    Some((mean, variance.sqrt()))
+}
@@ -0,0 +1,180 @@
+//! The event log: everything worth knowing about that happened in a run,
+//! and when, so a long run left going overnight can be gone through
+//! afterwards.
+//!
+//! Reseeds, changes of rule, the world settling down or starting up again,
+//! the population peaking or bottoming out, and everything else said under
+//! the grid are kept, `E` lists the latest of them, and `--event-log
+//! events.jsonl` writes every one as a line of JSON as it happens:
+//!
+//! ```text
+//! {"time": "2026-10-16T12:00:00Z", "generation": 1103, "kind": "period", "population": 116, "message": "Settled into period 2"}
+//! ```
+//!
+//! The first line is the provenance instead, as `{"provenance": {...}}`.
+//!
+//! The population has peaked once it's fallen a third, or ten cells, from
+//! the highest it reached since it last bottomed out, and the other way
+//! round, so the wobbles of a busy world don't count. Nothing is said about
+//! it once the world has settled.
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::time::SystemTime;
+
+use crate::json;
+use crate::provenance::{self, Provenance};
+
+/// How many events to keep for `E`.
+const KEPT: usize = 256;
+
+/// What sort of thing happened.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Reseed,
+    Rule,
+    Period,
+    Peak,
+    Trough,
+    /// Anything else the session said.
+    Note,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Reseed => "reseed",
+            Kind::Rule => "rule",
+            Kind::Period => "period",
+            Kind::Peak => "peak",
+            Kind::Trough => "trough",
+            Kind::Note => "note",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Event {
+    pub time: SystemTime,
+    pub generation: u64,
+    pub kind: Kind,
+    pub population: usize,
+    pub message: String,
+}
+
+impl Event {
+    fn json(&self) -> String {
+        format!(
+            "{{\"time\": {}, \"generation\": {}, \"kind\": {}, \"population\": {}, \"message\": {}}}",
+            json::string(&provenance::timestamp(self.time)),
+            self.generation,
+            json::string(self.kind.name()),
+            self.population,
+            json::string(&self.message)
+        )
+    }
+
+    /// The event as a line for `E`, with the time of day it happened.
+    pub fn line(&self) -> String {
+        let time = provenance::timestamp(self.time);
+        format!(
+            "{} {:>7} {:<7} {}",
+            &time[11..19],
+            self.generation,
+            self.kind.name(),
+            self.message
+        )
+    }
+}
+
+/// The latest events, for `E`.
+#[derive(Debug, Default)]
+pub struct Events {
+    kept: VecDeque<Event>,
+}
+
+impl Events {
+    pub fn keep(&mut self, event: Event) {
+        if self.kept.len() == KEPT {
+            self.kept.pop_front();
+        }
+        self.kept.push_back(event);
+    }
+
+    /// The last `count` events as lines, oldest first.
+    pub fn lines(&self, count: usize) -> Vec<String> {
+        let skip = self.kept.len().saturating_sub(count);
+        self.kept.iter().skip(skip).map(Event::line).collect()
+    }
+}
+
+/// Writes every event as a line of JSON.
+pub struct EventLog {
+    out: Box<dyn Write>,
+}
+
+impl EventLog {
+    pub fn new(mut out: Box<dyn Write>, provenance: &Provenance) -> io::Result<EventLog> {
+        writeln!(out, "{{\"provenance\": {}}}", provenance.json())?;
+        Ok(EventLog { out })
+    }
+
+    pub fn write(&mut self, event: &Event) -> io::Result<()> {
+        writeln!(self.out, "{}", event.json())
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Watches the population for it peaking and bottoming out.
+#[derive(Debug, Default)]
+pub struct Extrema {
+    /// The generation last seen, to start over when the run goes back.
+    last: Option<u64>,
+    /// The highest or lowest population since the last turn, and when.
+    extreme: Option<(u64, usize)>,
+    /// Whether the population was last seen going up.
+    rising: bool,
+}
+
+impl Extrema {
+    /// Sees a generation's population, returning the peak or trough it
+    /// has turned from, if it has. A settled world starts over.
+    pub fn see(
+        &mut self,
+        generation: u64,
+        population: usize,
+        settled: bool,
+    ) -> Option<(Kind, u64, usize)> {
+        if settled || self.last.is_none_or(|last| generation <= last) {
+            *self = Extrema {
+                last: Some(generation),
+                extreme: Some((generation, population)),
+                rising: true,
+            };
+            return None;
+        }
+        self.last = Some(generation);
+
+        let (at, extreme) = self.extreme.unwrap_or((generation, population));
+        let turn = (extreme / 3).max(10);
+        if self.rising {
+            if population >= extreme {
+                self.extreme = Some((generation, population));
+            } else if extreme - population >= turn {
+                self.rising = false;
+                self.extreme = Some((generation, population));
+                return Some((Kind::Peak, at, extreme));
+            }
+        } else if population <= extreme {
+            self.extreme = Some((generation, population));
+        } else if population - extreme >= turn {
+            self.rising = true;
+            self.extreme = Some((generation, population));
+            return Some((Kind::Trough, at, extreme));
+        }
+        None
+    }
+}
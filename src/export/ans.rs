@@ -0,0 +1,58 @@
+//! ANSI art files: the grid in the theme's glyphs and colours, with no
+//! cursor movement, so it can be `cat`ed into any terminal.
+//!
+//! The provenance goes first, in a privacy message (`ESC ^ ... ESC \\`),
+//! which terminals read and then throw away without showing.
+
+use std::fs;
+use std::io;
+
+use crate::provenance::Provenance;
+use crate::record::Recorder;
+use crate::theme::Theme;
+use crate::Matrix;
+
+/// Renders the whole grid, one line per row. `render_row` resets the colours
+/// at the end of every row that uses any, so nothing leaks past the file.
+pub fn ans(theme: &Theme, matrix: &Matrix, provenance: &Provenance) -> String {
+    let mut out = format!("\x1B^{}\x1B\\", provenance.lines().join("\n"));
+
+    for (states, ages) in matrix
+        .rows
+        .chunks(matrix.n)
+        .zip(matrix.ages.chunks(matrix.n))
+    {
+        out.push_str(&theme.render_row(states, ages));
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Keeps the latest generation and writes it as ANSI art when the run ends.
+pub struct AnsRecorder {
+    path: String,
+    provenance: Provenance,
+    last: String,
+}
+
+impl AnsRecorder {
+    pub fn new(path: &str, provenance: &Provenance) -> AnsRecorder {
+        AnsRecorder {
+            path: path.to_string(),
+            provenance: provenance.clone(),
+            last: String::new(),
+        }
+    }
+}
+
+impl Recorder for AnsRecorder {
+    fn record(&mut self, theme: &Theme, matrix: &Matrix, _frame: &str) -> io::Result<()> {
+        self.last = ans(theme, matrix, &self.provenance);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        fs::write(&self.path, &self.last)
+    }
+}
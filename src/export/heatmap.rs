@@ -0,0 +1,165 @@
+//! Heatmaps of where things happened: how many times every cell came to
+//! life or died over the whole run, written when it ends. Guns light up
+//! along with the lanes their gliders fly down, oscillators glow where
+//! they sit and still lifes leave nothing but the mark of where they
+//! settled.
+//!
+//! The file's extension says what it's written as: a PGM with the busiest
+//! cells darkest, ANSI art for `cat`ing into a terminal, or else a PNG.
+//! Both of those colour the counts from black through purple, red and
+//! orange to pale yellow, on a log scale so that a gun doesn't wash out
+//! everything else.
+
+use std::fs;
+use std::io;
+
+use crate::provenance::Provenance;
+use crate::render::Canvas;
+use crate::theme::Color;
+use crate::Matrix;
+
+/// The colours the counts run through, from none to the most there are.
+const RAMP: &[Color] = &[
+    Color { r: 0, g: 0, b: 4 },
+    Color {
+        r: 87,
+        g: 16,
+        b: 110,
+    },
+    Color {
+        r: 188,
+        g: 55,
+        b: 84,
+    },
+    Color {
+        r: 249,
+        g: 142,
+        b: 9,
+    },
+    Color {
+        r: 252,
+        g: 255,
+        b: 164,
+    },
+];
+
+/// How many times every cell has changed, and where to write it all.
+pub struct Heatmap {
+    path: String,
+    scale: usize,
+    n: usize,
+    changes: Vec<u32>,
+    last: Vec<bool>,
+}
+
+impl Heatmap {
+    /// Starts counting from the world as it is, `scale` pixels per cell in
+    /// the PNG.
+    pub fn new(path: &str, scale: usize, matrix: &Matrix) -> Heatmap {
+        Heatmap {
+            path: path.to_string(),
+            scale: scale.max(1),
+            n: matrix.n,
+            changes: vec![0; matrix.rows.len()],
+            last: matrix.rows.iter().map(|&c| c != 0).collect(),
+        }
+    }
+
+    /// Counts the cells that have come to life or died since the last
+    /// generation it saw. A world that has changed size starts afresh.
+    pub fn add(&mut self, matrix: &Matrix) {
+        if matrix.rows.len() != self.last.len() || matrix.n != self.n {
+            *self = Heatmap::new(&self.path, self.scale, matrix);
+            return;
+        }
+        for ((changes, last), &c) in self
+            .changes
+            .iter_mut()
+            .zip(&mut self.last)
+            .zip(&matrix.rows)
+        {
+            if *last != (c != 0) {
+                *changes = changes.saturating_add(1);
+                *last = c != 0;
+            }
+        }
+    }
+
+    /// How hot every cell is, from 0 for never changing to 1 for the
+    /// cells that changed the most.
+    fn heat(&self) -> Vec<f32> {
+        let most = self.changes.iter().max().copied().unwrap_or(0);
+        let top = (most as f32).ln_1p().max(f32::MIN_POSITIVE);
+        self.changes
+            .iter()
+            .map(|&c| (c as f32).ln_1p() / top)
+            .collect()
+    }
+
+    /// Writes the heatmap out in whichever form the path asks for.
+    pub fn write(&self, provenance: &Provenance) -> io::Result<()> {
+        let extension = self.path.rsplit('.').next().unwrap_or_default();
+        let out = match extension.to_ascii_lowercase().as_str() {
+            "pgm" => self.pgm(provenance),
+            "ans" => self.ans(provenance).into_bytes(),
+            _ => self.canvas().png(&provenance.lines()),
+        };
+        fs::write(&self.path, out)
+    }
+
+    fn canvas(&self) -> Canvas {
+        let heat = self.heat();
+        let (width, height) = (self.n * self.scale, heat.len() / self.n.max(1) * self.scale);
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(color(heat[y / self.scale * self.n + x / self.scale]));
+            }
+        }
+        Canvas {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// A raw PGM, one byte per cell, white where nothing happened and
+    /// darker where more did.
+    fn pgm(&self, provenance: &Provenance) -> Vec<u8> {
+        let comments: String = provenance
+            .lines()
+            .iter()
+            .map(|line| format!("# {}\n", line))
+            .collect();
+        let mut out = format!(
+            "P5\n{}{} {}\n255\n",
+            comments,
+            self.n,
+            self.changes.len() / self.n.max(1)
+        )
+        .into_bytes();
+        out.extend(self.heat().iter().map(|&h| 255 - (h * 255.0).round() as u8));
+        out
+    }
+
+    /// Two spaces per cell on a background of its colour, one line per
+    /// row, with the provenance hidden in a privacy message as `ans` does.
+    fn ans(&self, provenance: &Provenance) -> String {
+        let mut out = format!("\x1B^{}\x1B\\", provenance.lines().join("\n"));
+        for row in self.heat().chunks(self.n.max(1)) {
+            for &h in row {
+                let c = color(h);
+                out.push_str(&format!("\x1B[48;2;{};{};{}m  ", c.r, c.g, c.b));
+            }
+            out.push_str("\x1B[0m\n");
+        }
+        out
+    }
+}
+
+/// The colour of a cell that's `heat` of the way to being the hottest.
+fn color(heat: f32) -> Color {
+    let at = heat.clamp(0.0, 1.0) * (RAMP.len() - 1) as f32;
+    let i = (at as usize).min(RAMP.len() - 2);
+    RAMP[i].lerp(RAMP[i + 1], at - i as f32)
+}
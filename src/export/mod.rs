@@ -0,0 +1,7 @@
+//! Exporters write out a single generation, as opposed to the recorders
+//! which follow a whole run.
+
+pub mod ans;
+pub mod heatmap;
+pub mod plot;
+pub mod svg;
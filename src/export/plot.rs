@@ -0,0 +1,237 @@
+//! The population of every generation as a chart, for `--plot`, drawn when
+//! the run ends so there's no need to take `--stats-out` through a
+//! plotting program just to see how it went.
+//!
+//! `--plot population.png` draws the curve as a PNG, with the axes labelled
+//! in the `--banner` font, and `--plot -` draws it in the terminal instead,
+//! in block characters like the sparkline's, after the run.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write};
+
+use crate::pattern::banner;
+use crate::provenance::Provenance;
+use crate::render::Canvas;
+use crate::stats::Stats;
+use crate::theme::Color;
+
+const WIDTH: usize = 800;
+const HEIGHT: usize = 400;
+
+/// How many pixels across every cell of the font is.
+const FONT: usize = 2;
+
+/// Room left around the chart, in pixels.
+const MARGIN: usize = 16;
+
+const PAPER: Color = Color {
+    r: 255,
+    g: 255,
+    b: 255,
+};
+const INK: Color = Color {
+    r: 64,
+    g: 64,
+    b: 64,
+};
+const LINE: Color = Color {
+    r: 31,
+    g: 119,
+    b: 180,
+};
+
+/// The size of the chart in the terminal, in characters.
+const COLUMNS: usize = 64;
+const ROWS: usize = 12;
+
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// The population of every generation the run went through, and where to
+/// draw them.
+pub struct Plot {
+    path: String,
+    points: Vec<(u64, usize)>,
+}
+
+impl Plot {
+    pub fn new(path: &str) -> Plot {
+        Plot {
+            path: path.to_string(),
+            points: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, stats: &Stats) {
+        self.points.push((stats.generation, stats.population));
+    }
+
+    /// Draws the chart as a PNG, or in the terminal if the path is `-`.
+    pub fn write(&self, provenance: &Provenance) -> io::Result<()> {
+        if self.path == "-" {
+            io::stdout().write_all(self.text().as_bytes())
+        } else {
+            fs::write(&self.path, self.canvas().png(&provenance.lines()))
+        }
+    }
+
+    /// The first and last generations, never the same, and the highest
+    /// population, never none.
+    fn bounds(&self) -> (u64, u64, usize) {
+        let first = self.points.iter().map(|p| p.0).min().unwrap_or(0);
+        let last = self.points.iter().map(|p| p.0).max().unwrap_or(0);
+        let peak = self.points.iter().map(|p| p.1).max().unwrap_or(0);
+        (first, last.max(first + 1), peak.max(1))
+    }
+
+    /// The lowest, highest and last populations in each of `columns`
+    /// columns the generations are shared out between, if any fall in it.
+    fn columns(&self, columns: usize) -> Vec<Option<(usize, usize, usize)>> {
+        let (first, last, _) = self.bounds();
+        let mut spans = vec![None; columns];
+        for &(generation, population) in &self.points {
+            let x = ((generation - first) as f64 / (last - first) as f64 * (columns - 1) as f64)
+                .round() as usize;
+            spans[x] = Some(match spans[x] {
+                Some((lo, hi, _)) => (population.min(lo), population.max(hi), population),
+                None => (population, population, population),
+            });
+        }
+        spans
+    }
+
+    fn canvas(&self) -> Canvas {
+        let mut canvas = Canvas {
+            width: WIDTH,
+            height: HEIGHT,
+            pixels: vec![PAPER; WIDTH * HEIGHT],
+        };
+        let (first, last, peak) = self.bounds();
+
+        // The numbers on the axes decide how much room they need.
+        let glyph = |chars: usize| chars * 6 * FONT;
+        let left = MARGIN + glyph(peak.to_string().len()) + MARGIN / 2;
+        let top = MARGIN + 8 * FONT + MARGIN / 2;
+        let bottom = HEIGHT - MARGIN - 8 * FONT - MARGIN / 2;
+        let right = WIDTH - MARGIN;
+
+        label(&mut canvas, "POPULATION", (MARGIN, MARGIN));
+        label(&mut canvas, &peak.to_string(), (MARGIN, top));
+        label(
+            &mut canvas,
+            "0",
+            (left - MARGIN / 2 - glyph(1), bottom - 7 * FONT),
+        );
+        label(&mut canvas, &first.to_string(), (left, bottom + MARGIN / 2));
+        let end = last.to_string();
+        label(
+            &mut canvas,
+            &end,
+            (right - glyph(end.len()), bottom + MARGIN / 2),
+        );
+
+        for y in top..=bottom {
+            canvas.pixels[y * WIDTH + left - 1] = INK;
+        }
+        for x in left - 1..=right {
+            canvas.pixels[bottom * WIDTH + x] = INK;
+        }
+
+        // Every column runs from the lowest population in it to the highest,
+        // and on from wherever the last column stopped, so the curve has no
+        // gaps in it however steep it gets. Columns no generation fell in,
+        // when there are fewer generations than columns, go straight across
+        // from one to the next.
+        let y = |population: usize| bottom - 1 - population * (bottom - 1 - top) / peak;
+        let spans = self.columns(right - left);
+        let filled: Vec<usize> = (0..spans.len()).filter(|&x| spans[x].is_some()).collect();
+        let mut previous: Option<(usize, usize)> = None;
+        for &x in &filled {
+            let (lo, hi, end) = spans[x].unwrap();
+            let (lo, hi) = match previous {
+                Some((from, p)) => {
+                    for between in from + 1..x {
+                        let t = (between - from) as f64 / (x - from) as f64;
+                        let population = p as f64 + (end as f64 - p as f64) * t;
+                        canvas.pixels[y(population.round() as usize) * WIDTH + left + between] =
+                            LINE;
+                    }
+                    (lo.min(p), hi.max(p))
+                }
+                None => (lo, hi),
+            };
+            for row in y(hi)..=y(lo) {
+                canvas.pixels[row * WIDTH + left + x] = LINE;
+            }
+            previous = Some((x, end));
+        }
+        canvas
+    }
+
+    /// The chart as rows of block characters, the highest population in
+    /// every column making its bar, with the axes numbered.
+    fn text(&self) -> String {
+        let (first, last, peak) = self.bounds();
+        let columns = self.columns(COLUMNS);
+        let gutter = peak.to_string().len();
+        let mut out = String::new();
+
+        for row in (0..ROWS).rev() {
+            let axis = match row {
+                r if r == ROWS - 1 => peak.to_string(),
+                0 => "0".to_string(),
+                _ => String::new(),
+            };
+            write!(out, "{:>width$} │", axis, width = gutter).unwrap();
+            for span in &columns {
+                // In eighths of a row, so the bars can end part way up.
+                let height = span.map_or(0, |(_, hi, _)| hi * ROWS * 8 / peak);
+                out.push(match height.saturating_sub(row * 8) {
+                    0 => ' ',
+                    h if h >= 8 => BARS[7],
+                    h => BARS[h - 1],
+                });
+            }
+            out.push('\n');
+        }
+        writeln!(
+            out,
+            "{:>width$} └{}",
+            "",
+            "─".repeat(COLUMNS),
+            width = gutter
+        )
+        .unwrap();
+        let end = last.to_string();
+        writeln!(
+            out,
+            "{:>width$}  {}{:>pad$}",
+            "",
+            first,
+            end,
+            width = gutter,
+            pad = COLUMNS - first.to_string().len()
+        )
+        .unwrap();
+        out
+    }
+}
+
+/// Writes text in the `--banner` font with its top left corner at `(x, y)`.
+fn label(canvas: &mut Canvas, text: &str, (x, y): (usize, usize)) {
+    // The font has every digit and capital letter.
+    let Ok(pattern) = banner::banner(text) else {
+        return;
+    };
+    for r in 0..pattern.height * FONT {
+        for c in 0..pattern.width * FONT {
+            let (px, py) = (x + c, y + r);
+            if pattern.cells[r / FONT * pattern.width + c / FONT] != 0
+                && px < canvas.width
+                && py < canvas.height
+            {
+                canvas.pixels[py * canvas.width + px] = INK;
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! SVG snapshots.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::provenance::{self, Provenance};
+use crate::theme::{Color, Theme};
+use crate::Matrix;
+
+/// Draws the matrix as a background plus one square per live cell.
+///
+/// Cells are one unit in the `viewBox`, and `scale` only sets the default
+/// display size, so the result can be scaled freely. Cells of the same
+/// colour are grouped together to keep the file small. With `ages` set, the
+/// theme's gradient is applied; otherwise every cell is drawn as if it had
+/// just been born. The provenance goes in the `metadata`.
+pub fn svg(
+    theme: &Theme,
+    matrix: &Matrix,
+    scale: usize,
+    ages: bool,
+    provenance: &Provenance,
+) -> String {
+    let mut groups: BTreeMap<(u8, u8, u8), Vec<usize>> = BTreeMap::new();
+
+    for (i, &state) in matrix.rows.iter().enumerate() {
+        if state != 0 {
+            let age = if ages { matrix.ages[i] } else { 0 };
+            let c = theme.pixel(state, age);
+            groups.entry((c.r, c.g, c.b)).or_default().push(i);
+        }
+    }
+
+    let mut out = String::new();
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").unwrap();
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" shape-rendering=\"crispEdges\">",
+        matrix.n * scale,
+        matrix.m * scale,
+        matrix.n,
+        matrix.m
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "<metadata>\n{}\n</metadata>",
+        provenance::escape(&provenance.lines().join("\n"))
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+        matrix.n,
+        matrix.m,
+        theme.pixel(0, 0)
+    )
+    .unwrap();
+
+    for ((r, g, b), cells) in groups {
+        writeln!(out, "<g fill=\"{}\">", Color { r, g, b }).unwrap();
+        for i in cells {
+            writeln!(
+                out,
+                "<rect x=\"{}\" y=\"{}\" width=\"1\" height=\"1\"/>",
+                i % matrix.n,
+                i / matrix.n
+            )
+            .unwrap();
+        }
+        writeln!(out, "</g>").unwrap();
+    }
+
+    writeln!(out, "</svg>").unwrap();
+    out
+}
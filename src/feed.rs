@@ -0,0 +1,89 @@
+//! Frames fed in from outside while the world runs, blending a camera or
+//! another program into the automaton for installations.
+//!
+//! `--feed FILE` reads raw PBM, PGM or PPM images one after another from a
+//! file, a named pipe or, given `-`, standard input, such as a webcam by way
+//! of:
+//!
+//! ```text
+//! ffmpeg -f v4l2 -i /dev/video0 -f image2pipe -c:v pbm - | iron-cat --feed -
+//! ```
+//!
+//! Every frame is stretched over the world and its dark pixels brought to
+//! life, on top of whatever is alive already. Frames are read on a thread of
+//! their own, and one that comes in before the last was used replaces it, so
+//! a camera quicker than the generations never leaves the world behind.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::image::{self, ImageError, Raster};
+
+/// What has come in from the feed.
+pub enum Fed {
+    Frame(Raster),
+    Ended,
+    Failed(ImageError),
+}
+
+/// The newest frame not yet used, and how the feed ended, if it has.
+#[derive(Default)]
+struct Inbox {
+    frame: Option<Raster>,
+    end: Option<Fed>,
+}
+
+pub struct Feed {
+    inbox: Arc<Mutex<Inbox>>,
+}
+
+impl Feed {
+    /// Starts reading frames. The file is opened on the reading thread, as
+    /// a named pipe only opens once something is writing to it.
+    pub fn open(source: &str) -> Feed {
+        let inbox = Arc::new(Mutex::new(Inbox::default()));
+        let shared = Arc::clone(&inbox);
+        let source = source.to_string();
+
+        thread::spawn(move || {
+            let end = match read(&source, &shared) {
+                Ok(()) => Fed::Ended,
+                Err(e) => Fed::Failed(e),
+            };
+            if let Ok(mut inbox) = shared.lock() {
+                inbox.end = Some(end);
+            }
+        });
+
+        Feed { inbox }
+    }
+
+    /// The newest frame since the last one taken or, once they've all been
+    /// taken, how the feed ended.
+    pub fn take(&mut self) -> Option<Fed> {
+        let mut inbox = self.inbox.lock().ok()?;
+        match inbox.frame.take() {
+            Some(frame) => Some(Fed::Frame(frame)),
+            None => inbox.end.take(),
+        }
+    }
+}
+
+/// Reads frames until the stream ends, leaving each for the world to take.
+fn read(source: &str, inbox: &Mutex<Inbox>) -> Result<(), ImageError> {
+    let input: Box<dyn Read> = match source {
+        "-" => Box::new(io::stdin()),
+        path => Box::new(File::open(path).map_err(ImageError::Io)?),
+    };
+    let mut reader = BufReader::new(input);
+
+    while let Some(frame) = image::next_netpbm(&mut reader)? {
+        match inbox.lock() {
+            Ok(mut inbox) => inbox.frame = Some(frame),
+            Err(_) => return Ok(()),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,430 @@
+//! Reading and writing the grid as an image, so patterns can be drawn in or
+//! touched up with an image editor.
+//!
+//! PNG and all six Netpbm formats (PBM, PGM and PPM, both plain and raw)
+//! can be read. PBM and PGM are written in the "ink" convention where live
+//! cells are dark on a light background, while PNG is written with the
+//! theme's colours just like the pixel renderers.
+
+use std::io::Read;
+use std::{error, fmt, fs, io};
+
+use crate::provenance::Provenance;
+use crate::render::Canvas;
+use crate::theme::{Color, Theme};
+use crate::Matrix;
+
+/// Why an image couldn't be read.
+#[derive(Debug)]
+pub enum ImageError {
+    Io(io::Error),
+    Png(png::DecodingError),
+    Format(String),
+}
+
+impl error::Error for ImageError {}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::Io(e) => write!(f, "Could not read image: {}", e),
+            ImageError::Png(e) => write!(f, "Could not decode PNG: {}", e),
+            ImageError::Format(s) => write!(f, "Invalid image: {}", s),
+        }
+    }
+}
+
+/// How pixels are turned into cell states.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mapping {
+    /// Pixels darker than the threshold (0 to 255) are alive.
+    Threshold(u8),
+    /// Every pixel becomes the state whose theme colour is closest to it,
+    /// which round-trips anything written with the same theme.
+    Palette,
+}
+
+/// A decoded image.
+#[derive(Debug)]
+pub struct Raster {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>,
+}
+
+impl Raster {
+    /// Reads a PNG or Netpbm file, telling them apart by their magic bytes.
+    pub fn load(path: &str) -> Result<Raster, ImageError> {
+        let data = fs::read(path).map_err(ImageError::Io)?;
+
+        if data.starts_with(b"\x89PNG") {
+            decode_png(&data)
+        } else if data.len() > 1 && data[0] == b'P' && (b'1'..=b'6').contains(&data[1]) {
+            decode_netpbm(&data)
+        } else {
+            Err(ImageError::Format(String::from(
+                "expected a PNG, PBM, PGM or PPM file",
+            )))
+        }
+    }
+
+    /// Turns the image into cell states, reading one cell from the centre
+    /// of every `scale` by `scale` block of pixels.
+    pub fn cells(
+        &self,
+        scale: usize,
+        mapping: Mapping,
+        theme: &Theme,
+    ) -> (usize, usize, Vec<usize>) {
+        let scale = scale.max(1);
+        let m = self.height / scale;
+        let n = self.width / scale;
+        let palette: Vec<Color> = (0..theme.states()).map(|s| theme.pixel(s, 0)).collect();
+
+        let cells = (0..m * n)
+            .map(|i| {
+                let y = (i / n) * scale + scale / 2;
+                let x = (i % n) * scale + scale / 2;
+                let c = self.pixels[y * self.width + x];
+
+                match mapping {
+                    Mapping::Threshold(t) => (luminance(c) < t as f32) as usize,
+                    Mapping::Palette => nearest(&palette, c),
+                }
+            })
+            .collect();
+
+        (m, n, cells)
+    }
+}
+
+impl Raster {
+    /// Whether each of `m` by `n` cells is darker than the threshold,
+    /// stretching the image over all of them whatever its size.
+    pub fn ink(&self, m: usize, n: usize, threshold: u8) -> Vec<bool> {
+        (0..m * n)
+            .map(|i| {
+                let y = (i / n) * self.height / m;
+                let x = (i % n) * self.width / n;
+                self.pixels
+                    .get(y * self.width + x)
+                    .is_some_and(|&c| luminance(c) < threshold as f32)
+            })
+            .collect()
+    }
+}
+
+fn luminance(c: Color) -> f32 {
+    0.299 * c.r as f32 + 0.587 * c.g as f32 + 0.114 * c.b as f32
+}
+
+fn nearest(palette: &[Color], c: Color) -> usize {
+    let distance = |p: &Color| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(p.r, c.r) + d(p.g, c.g) + d(p.b, c.b)
+    };
+
+    (0..palette.len())
+        .min_by_key(|&i| distance(&palette[i]))
+        .unwrap_or(0)
+}
+
+fn decode_png(data: &[u8]) -> Result<Raster, ImageError> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+
+    let mut reader = decoder.read_info().map_err(ImageError::Png)?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).map_err(ImageError::Png)?;
+
+    let channels = info.color_type.samples();
+    let pixels = buf[..info.buffer_size()]
+        .chunks(channels)
+        .map(|p| match channels {
+            1 | 2 => Color {
+                r: p[0],
+                g: p[0],
+                b: p[0],
+            },
+            _ => Color {
+                r: p[0],
+                g: p[1],
+                b: p[2],
+            },
+        })
+        .collect();
+
+    Ok(Raster {
+        width: info.width as usize,
+        height: info.height as usize,
+        pixels,
+    })
+}
+
+/// Reads the next of a stream of raw Netpbm images written one straight
+/// after another, as `ffmpeg -f image2pipe -c:v pbm` does, or `None` once
+/// the stream has ended between images. Plain images can't be told apart
+/// from the one after, so only raw ones are read.
+pub fn next_netpbm(reader: &mut impl Read) -> Result<Option<Raster>, ImageError> {
+    let mut byte = [0];
+    let mut next = |reader: &mut dyn Read| -> Result<Option<u8>, ImageError> {
+        match reader.read(&mut byte).map_err(ImageError::Io)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    };
+    let bad = || ImageError::Format(String::from("bad Netpbm header"));
+
+    let mut data = Vec::new();
+    loop {
+        match next(reader)? {
+            None => return Ok(None),
+            Some(c) if c.is_ascii_whitespace() => {}
+            Some(c) => {
+                data.push(c);
+                break;
+            }
+        }
+    }
+    data.extend(next(reader)?);
+    let kind = match data[..] {
+        [b'P', kind @ (b'4' | b'5' | b'6')] => kind,
+        _ => {
+            return Err(ImageError::Format(String::from(
+                "expected raw PBM, PGM or PPM images, one after another",
+            )))
+        }
+    };
+
+    // The header ends with exactly one whitespace character after its
+    // last number, which is read along with it.
+    let fields = if kind == b'4' { 2 } else { 3 };
+    let mut numbers: Vec<usize> = Vec::new();
+    let mut digits = String::new();
+    let mut comment = false;
+    while numbers.len() < fields {
+        let c = next(reader)?.ok_or_else(bad)?;
+        data.push(c);
+        match c {
+            b'\n' if comment => comment = false,
+            _ if comment => {}
+            b'#' => comment = true,
+            b'0'..=b'9' => digits.push(c as char),
+            c if c.is_ascii_whitespace() => {
+                if !digits.is_empty() {
+                    numbers.push(digits.parse().map_err(|_| bad())?);
+                    digits.clear();
+                }
+            }
+            _ => return Err(bad()),
+        }
+    }
+
+    let (width, height) = (numbers[0], numbers[1]);
+    let samples = match kind {
+        b'4' => width.div_ceil(8).checked_mul(height),
+        b'5' => width.checked_mul(height),
+        _ => width.checked_mul(height).and_then(|p| p.checked_mul(3)),
+    };
+    let wide = numbers.get(2).is_some_and(|&maxval| maxval > 255);
+    let length = samples
+        .and_then(|s| s.checked_mul(if wide { 2 } else { 1 }))
+        .ok_or_else(bad)?;
+
+    let start = data.len();
+    data.resize(start + length, 0);
+    reader
+        .read_exact(&mut data[start..])
+        .map_err(ImageError::Io)?;
+    decode_netpbm(&data).map(Some)
+}
+
+/// Netpbm files start with a whitespace separated header, where `#` starts
+/// a comment, followed by either more ASCII numbers or raw bytes.
+struct Header<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Header<'a> {
+    fn skip(&mut self) {
+        while self.pos < self.data.len() {
+            match self.data[self.pos] {
+                b'#' => {
+                    while self.pos < self.data.len() && self.data[self.pos] != b'\n' {
+                        self.pos += 1;
+                    }
+                }
+                c if c.is_ascii_whitespace() => self.pos += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, ImageError> {
+        self.skip();
+        let start = self.pos;
+        while self.pos < self.data.len() && self.data[self.pos].is_ascii_digit() {
+            self.pos += 1;
+        }
+
+        std::str::from_utf8(&self.data[start..self.pos])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| ImageError::Format(String::from("bad Netpbm header")))
+    }
+
+    /// Plain PBM allows bits without any whitespace between them.
+    fn bit(&mut self) -> Result<bool, ImageError> {
+        self.skip();
+        match self.data.get(self.pos) {
+            Some(b'0') => {
+                self.pos += 1;
+                Ok(false)
+            }
+            Some(b'1') => {
+                self.pos += 1;
+                Ok(true)
+            }
+            _ => Err(ImageError::Format(String::from("bad PBM data"))),
+        }
+    }
+}
+
+fn decode_netpbm(data: &[u8]) -> Result<Raster, ImageError> {
+    let kind = data[1];
+    let mut header = Header { data, pos: 2 };
+    let width = header.number()?;
+    let height = header.number()?;
+    let maxval = if kind == b'1' || kind == b'4' {
+        1
+    } else {
+        header.number()?
+    };
+
+    if maxval == 0 || maxval > 65535 {
+        return Err(ImageError::Format(String::from("bad maximum value")));
+    }
+
+    let count = width
+        .checked_mul(height)
+        .ok_or_else(|| ImageError::Format(String::from("image is too big")))?;
+    let grey = |v: usize| {
+        let v = (v.min(maxval) * 255 / maxval) as u8;
+        Color { r: v, g: v, b: v }
+    };
+    let ink = |bit: bool| grey(if bit { 0 } else { 1 });
+
+    // Raw formats have exactly one whitespace character before the data.
+    let raw = &data[(header.pos + 1).min(data.len())..];
+    let wide = maxval > 255;
+    let sample = |i: usize| -> Option<usize> {
+        if wide {
+            Some((*raw.get(i * 2)? as usize) << 8 | *raw.get(i * 2 + 1)? as usize)
+        } else {
+            raw.get(i).map(|&b| b as usize)
+        }
+    };
+    let short = || ImageError::Format(String::from("image data is too short"));
+
+    let pixels = match kind {
+        b'1' => (0..count)
+            .map(|_| header.bit().map(ink))
+            .collect::<Result<_, _>>()?,
+        b'2' => (0..count)
+            .map(|_| header.number().map(grey))
+            .collect::<Result<_, _>>()?,
+        b'3' => (0..count)
+            .map(|_| {
+                let (r, g, b) = (header.number()?, header.number()?, header.number()?);
+                Ok(Color {
+                    r: grey(r).r,
+                    g: grey(g).r,
+                    b: grey(b).r,
+                })
+            })
+            .collect::<Result<_, _>>()?,
+        b'4' => {
+            let stride = width.div_ceil(8);
+            (0..count)
+                .map(|i| {
+                    let (y, x) = (i / width, i % width);
+                    let byte = raw.get(y * stride + x / 8).ok_or_else(short)?;
+                    Ok(ink(byte & (0x80 >> (x % 8)) != 0))
+                })
+                .collect::<Result<_, _>>()?
+        }
+        b'5' => (0..count)
+            .map(|i| sample(i).map(grey).ok_or_else(short))
+            .collect::<Result<_, _>>()?,
+        _ => (0..count)
+            .map(|i| {
+                let (r, g, b) = (sample(i * 3), sample(i * 3 + 1), sample(i * 3 + 2));
+                Ok(Color {
+                    r: grey(r.ok_or_else(short)?).r,
+                    g: grey(g.ok_or_else(short)?).r,
+                    b: grey(b.ok_or_else(short)?).r,
+                })
+            })
+            .collect::<Result<_, _>>()?,
+    };
+
+    Ok(Raster {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// The provenance as Netpbm comments, to go after the magic number.
+fn comments(provenance: &Provenance) -> String {
+    provenance
+        .lines()
+        .iter()
+        .map(|line| format!("# {}\n", line))
+        .collect()
+}
+
+/// A raw PBM, one bit per cell with live cells set.
+pub fn pbm(matrix: &Matrix, provenance: &Provenance) -> Vec<u8> {
+    let mut out = format!("P4\n{}{} {}\n", comments(provenance), matrix.n, matrix.m).into_bytes();
+
+    for row in matrix.rows.chunks(matrix.n) {
+        for byte in row.chunks(8) {
+            out.push(
+                byte.iter()
+                    .enumerate()
+                    .fold(0, |b, (i, &c)| if c != 0 { b | 0x80 >> i } else { b }),
+            );
+        }
+    }
+
+    out
+}
+
+/// A raw PGM, one byte per cell. Dead cells are white and higher states get
+/// darker, so a two-state grid looks the same as its PBM.
+pub fn pgm(matrix: &Matrix, provenance: &Provenance) -> Vec<u8> {
+    let mut out = format!(
+        "P5\n{}{} {}\n255\n",
+        comments(provenance),
+        matrix.n,
+        matrix.m
+    )
+    .into_bytes();
+    let top = matrix.rows.iter().max().copied().unwrap_or(0);
+    let step = 255 / top.max(1);
+
+    out.extend(
+        matrix
+            .rows
+            .iter()
+            .map(|&c| 255usize.saturating_sub(c * step) as u8),
+    );
+    out
+}
+
+/// A PNG in the theme's colours, `scale` pixels per cell.
+pub fn png(theme: &Theme, matrix: &Matrix, scale: usize, provenance: &Provenance) -> Vec<u8> {
+    Canvas::new(theme, matrix, scale).png(&provenance.lines())
+}
@@ -0,0 +1,268 @@
+//! Which keys do what.
+//!
+//! Every action has a name and some keys by default, and the `[keys]`
+//! section of the config file can give any of them other keys instead:
+//!
+//! ```toml
+//! [keys]
+//! up = ["k", "up"]
+//! down = ["j", "down"]
+//! left = ["h", "left"]
+//! right = ["l", "right"]
+//! library = "L"
+//! ```
+//!
+//! Keys are single characters, which are case sensitive, or one of
+//! `space`, `enter`, `esc`, `tab`, `backspace`, `delete`, `up`, `down`,
+//! `left`, `right`, `home`, `end`, `pageup` and `pagedown`.
+//!
+//! The keys that move the cursor, toggle cells and act on the selection
+//! only mean that while editing, and then they come before everything
+//! else, so space can toggle cells there and pause the run elsewhere. Two
+//! actions that could both be meant by the same key are refused.
+
+use std::collections::BTreeMap;
+use std::{error, fmt};
+
+use crossterm::event::KeyCode;
+
+/// Why the keys asked for couldn't be used.
+#[derive(Debug)]
+pub enum KeyError {
+    Action(String),
+    Key(String),
+    Clash(String, &'static str, &'static str),
+}
+
+impl error::Error for KeyError {}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyError::Action(s) => write!(f, "Invalid action {:?} in [keys]", s),
+            KeyError::Key(s) => write!(f, "Invalid key {:?} in [keys]", s),
+            KeyError::Clash(key, a, b) => {
+                write!(f, "Invalid [keys]: {} would mean both {} and {}", key, a, b)
+            }
+        }
+    }
+}
+
+/// Everything a key can do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    Pause,
+    Step,
+    Back,
+    Forward,
+    Rewind,
+    Skip,
+    Reseed,
+    Density,
+    Clear,
+    Rule,
+    Resize,
+    Break,
+    Edit,
+    Inspect,
+    Paste,
+    SystemPaste,
+    Stamp,
+    NextStamp,
+    Library,
+    Rotate,
+    Flip,
+    Export,
+    Save,
+    Dashboard,
+    Mark,
+    Jump,
+    Diff,
+    Events,
+    Help,
+    Quit,
+    Up,
+    Down,
+    Left,
+    Right,
+    Toggle,
+    Copy,
+    Cut,
+    Erase,
+    SystemCopy,
+}
+
+/// Every action's name in the config file and its keys by default.
+const ACTIONS: &[(Action, &str, &[&str])] = &[
+    (Action::Pause, "pause", &["space"]),
+    (Action::Step, "step", &["n"]),
+    (Action::Back, "back", &["u", ","]),
+    (Action::Forward, "forward", &["."]),
+    (Action::Rewind, "rewind", &["["]),
+    (Action::Skip, "skip", &["]"]),
+    (Action::Reseed, "reseed", &["r"]),
+    (Action::Density, "reseed_density", &["d"]),
+    (Action::Clear, "clear", &["X"]),
+    (Action::Rule, "rule", &["R"]),
+    (Action::Resize, "resize", &["S"]),
+    (Action::Break, "break", &["b"]),
+    (Action::Edit, "edit", &["e"]),
+    (Action::Inspect, "inspect", &["i"]),
+    (Action::Paste, "paste", &["p"]),
+    (Action::SystemPaste, "system_paste", &["V"]),
+    (Action::Stamp, "stamp", &["g"]),
+    (Action::NextStamp, "next_stamp", &["G"]),
+    (Action::Library, "library", &["l"]),
+    (Action::Rotate, "rotate", &["o"]),
+    (Action::Flip, "flip", &["f"]),
+    (Action::Export, "export", &["w"]),
+    (Action::Save, "save", &["W"]),
+    (Action::Dashboard, "dashboard", &["D"]),
+    (Action::Mark, "mark", &["m"]),
+    (Action::Jump, "jump", &["'"]),
+    (Action::Diff, "diff", &["="]),
+    (Action::Events, "events", &["E"]),
+    (Action::Help, "help", &["?"]),
+    (Action::Quit, "quit", &["q"]),
+    (Action::Up, "up", &["up"]),
+    (Action::Down, "down", &["down"]),
+    (Action::Left, "left", &["left"]),
+    (Action::Right, "right", &["right"]),
+    (Action::Toggle, "toggle", &["space"]),
+    (Action::Copy, "copy", &["c"]),
+    (Action::Cut, "cut", &["x"]),
+    (Action::Erase, "erase", &["delete", "backspace"]),
+    (Action::SystemCopy, "system_copy", &["C"]),
+];
+
+/// The keys with names of their own.
+const NAMED: &[(&str, KeyCode)] = &[
+    ("space", KeyCode::Char(' ')),
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+];
+
+impl Action {
+    /// Whether the action only means anything while editing.
+    pub fn editing(self) -> bool {
+        self >= Action::Up
+    }
+
+    fn name(self) -> &'static str {
+        ACTIONS.iter().find(|(a, _, _)| *a == self).unwrap().1
+    }
+}
+
+/// Reads a key as written in the config file.
+pub fn parse(key: &str) -> Option<KeyCode> {
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(KeyCode::Char(c)),
+        _ => NAMED
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|&(_, code)| code),
+    }
+}
+
+/// Writes a key as it would be in the config file.
+pub fn name(code: KeyCode) -> String {
+    match NAMED.iter().find(|&&(_, c)| c == code) {
+        Some((name, _)) => name.to_string(),
+        None => match code {
+            KeyCode::Char(c) => c.to_string(),
+            other => format!("{:?}", other).to_lowercase(),
+        },
+    }
+}
+
+/// Every action and the keys that do it.
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    keys: Vec<(Action, Vec<KeyCode>)>,
+}
+
+/// The keys every action has unless told otherwise.
+impl Default for Keymap {
+    fn default() -> Keymap {
+        Keymap {
+            keys: ACTIONS
+                .iter()
+                .map(|&(action, _, keys)| (action, keys.iter().filter_map(|k| parse(k)).collect()))
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    /// The default keys with some actions given others instead, as in the
+    /// `[keys]` section of the config file.
+    pub fn new(keys: &BTreeMap<String, Vec<String>>) -> Result<Keymap, KeyError> {
+        let mut keymap = Keymap::default();
+
+        for (action, keys) in keys {
+            let slot = keymap
+                .keys
+                .iter_mut()
+                .find(|(a, _)| a.name() == action)
+                .ok_or_else(|| KeyError::Action(action.clone()))?;
+            slot.1 = keys
+                .iter()
+                .map(|k| parse(k).ok_or_else(|| KeyError::Key(k.clone())))
+                .collect::<Result<_, _>>()?;
+        }
+
+        for (i, (a, keys)) in keymap.keys.iter().enumerate() {
+            for (b, others) in &keymap.keys[i + 1..] {
+                if a.editing() != b.editing() {
+                    continue;
+                }
+                if let Some(&key) = keys.iter().find(|k| others.contains(k)) {
+                    return Err(KeyError::Clash(name(key), a.name(), b.name()));
+                }
+            }
+        }
+
+        Ok(keymap)
+    }
+
+    /// What a key does, if anything. While editing the editing actions
+    /// come first.
+    pub fn action(&self, code: KeyCode, editing: bool) -> Option<Action> {
+        let find = |edit: bool| {
+            self.keys
+                .iter()
+                .find(|(a, keys)| a.editing() == edit && keys.contains(&code))
+                .map(|&(a, _)| a)
+        };
+
+        if editing {
+            find(true).or_else(|| find(false))
+        } else {
+            find(false)
+        }
+    }
+
+    /// The keys that do an action, as they'd be written in the config
+    /// file, for telling the user about them.
+    pub fn names(&self, action: Action) -> String {
+        self.keys
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map_or(Vec::new(), |(_, keys)| {
+                keys.iter().map(|&k| name(k)).collect()
+            })
+            .join(" ")
+    }
+}
@@ -0,0 +1,177 @@
+//! Sending frames over a slow connection.
+//!
+//! Over a laggy SSH session a full frame every generation can take longer
+//! to write than the generation itself, and then the whole simulation slows
+//! down to the speed of the link. With `--render-budget` or
+//! `--max-bandwidth` a `Link` watches how long writes take and how many
+//! bytes they cost, and steps down through cheaper ways of drawing:
+//!
+//! 1. only rewriting the lines that changed since the last frame,
+//! 2. using the 256-colour palette instead of 24-bit colour,
+//! 3. and then skipping frames, twice as many at every further step.
+//!
+//! It steps back up once frames have gone through comfortably for a while.
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// Frames that must go through comfortably before stepping back up.
+const CALM: usize = 5;
+
+/// The cheapest way of drawing: diffs, fewer colours and every eighth frame.
+const LOWEST: usize = 5;
+
+pub struct Link {
+    /// The longest a frame may take to write.
+    budget: Option<Duration>,
+    /// The most bytes per second we may send.
+    bandwidth: Option<usize>,
+    /// How far we have stepped down, from 0 for full frames to `LOWEST`.
+    level: usize,
+    calm: usize,
+    /// Frames not sent since the last one that was.
+    skipped: usize,
+    /// The lines on screen, to diff the next frame against.
+    shown: Vec<String>,
+    last: Option<Instant>,
+}
+
+impl Link {
+    pub fn new(budget: Option<Duration>, bandwidth: Option<usize>) -> Link {
+        Link {
+            budget,
+            bandwidth,
+            level: 0,
+            calm: 0,
+            skipped: 0,
+            shown: Vec::new(),
+            last: None,
+        }
+    }
+
+    /// Forgets what is on screen, after it has been cleared or redrawn
+    /// behind our back, so the next frame is sent in full.
+    pub fn reset(&mut self) {
+        self.shown.clear();
+    }
+
+    /// Draws a frame at the top of the screen, or not, if we're skipping.
+    pub fn send(&mut self, frame: &str) -> io::Result<()> {
+        if self.level >= 3 && self.skipped + 1 < 1 << (self.level - 2) {
+            self.skipped += 1;
+            return Ok(());
+        }
+        self.skipped = 0;
+
+        let frame = if self.level >= 2 {
+            fewer_colors(frame)
+        } else {
+            frame.to_string()
+        };
+        let lines: Vec<String> = frame.lines().map(String::from).collect();
+
+        let mut out = String::new();
+        if self.level >= 1 && self.shown.len() == lines.len() {
+            for (i, (line, old)) in lines.iter().zip(&self.shown).enumerate() {
+                if line != old {
+                    out.push_str(&format!("\x1B[{};1H{}", i + 1, line));
+                }
+            }
+            out.push_str(&format!("\x1B[{};1H", lines.len() + 1));
+        } else {
+            // Frames can get shorter, so anything below is cleared.
+            out.push_str("\x1B[H");
+            out.push_str(&frame);
+            out.push_str("\x1B[J");
+        }
+
+        let start = Instant::now();
+        let mut stdout = io::stdout();
+        stdout.write_all(out.as_bytes())?;
+        stdout.flush()?;
+        let elapsed = start.elapsed();
+
+        self.shown = lines;
+        self.adapt(out.len(), elapsed);
+
+        Ok(())
+    }
+
+    /// Steps down if the last frame was too slow or too big, and back up
+    /// after enough frames with room to spare.
+    fn adapt(&mut self, bytes: usize, elapsed: Duration) {
+        let now = Instant::now();
+        let interval = self
+            .last
+            .map_or(Duration::from_secs(1), |last| now.duration_since(last));
+        self.last = Some(now);
+
+        let rate = bytes as f64 / interval.as_secs_f64().max(0.001);
+        let over = self.budget.is_some_and(|b| elapsed > b)
+            || self.bandwidth.is_some_and(|b| rate > b as f64);
+        let easy = self.budget.is_none_or(|b| elapsed * 2 < b)
+            && self.bandwidth.is_none_or(|b| rate * 2.0 < b as f64);
+
+        if over {
+            self.level = (self.level + 1).min(LOWEST);
+            self.calm = 0;
+        } else if easy && self.level > 0 {
+            self.calm += 1;
+            if self.calm >= CALM {
+                self.level -= 1;
+                self.calm = 0;
+            }
+        }
+    }
+}
+
+/// Rewrites every 24-bit colour in a frame's escape codes as the nearest
+/// colour in the 256-colour palette's 6x6x6 cube.
+fn fewer_colors(frame: &str) -> String {
+    let mut out = String::with_capacity(frame.len());
+    let mut rest = frame;
+
+    while let Some(start) = rest.find("\x1B[") {
+        out.push_str(&rest[..start]);
+        let seq = &rest[start + 2..];
+        let end = match seq.find(|c: char| c.is_ascii_alphabetic()) {
+            Some(end) => end,
+            None => {
+                rest = &rest[start..];
+                break;
+            }
+        };
+
+        out.push_str("\x1B[");
+        if seq[end..].starts_with('m') {
+            out.push_str(&fewer_params(&seq[..end]));
+        } else {
+            out.push_str(&seq[..end]);
+        }
+        out.push_str(&seq[end..=end]);
+        rest = &seq[end + 1..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+fn fewer_params(params: &str) -> String {
+    let p: Vec<&str> = params.split(';').collect();
+    let mut out = Vec::with_capacity(p.len());
+    let mut i = 0;
+
+    while i < p.len() {
+        if (p[i] == "38" || p[i] == "48") && p.get(i + 1) == Some(&"2") && i + 4 < p.len() {
+            let level = |s: &str| (s.parse::<u32>().unwrap_or(0).min(255) * 5 + 127) / 255;
+            let index = 16 + 36 * level(p[i + 2]) + 6 * level(p[i + 3]) + level(p[i + 4]);
+            out.push(format!("{};5;{}", p[i], index));
+            i += 5;
+        } else {
+            out.push(p[i].to_string());
+            i += 1;
+        }
+    }
+
+    out.join(";")
+}
@@ -3,21 +3,97 @@
 //! * Animated using terminal codes.
 //! * Accepts B/S rulestrings.
 //! * Parallelism courtesy of the `rayon` library!
+//! * Custom glyphs and colours from TOML theme files.
 
-use std::{error, fmt, thread, time};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::{error, fmt, fs, time};
 extern crate clap;
 extern crate rand;
 extern crate rayon;
 
-use clap::{App, Arg};
-use rand::distributions::{Distribution, Uniform};
+mod checkpoint;
+mod clipboard;
+mod clusters;
+mod collide;
+mod condition;
+mod config;
+mod dashboard;
+mod demo;
+mod diff;
+mod ensemble;
+mod events;
+mod export;
+mod feed;
+mod image;
+mod json;
+mod keys;
+mod link;
+mod net;
+mod pattern;
+mod predecessor;
+mod project;
+mod provenance;
+mod record;
+mod render;
+mod rules;
+mod script;
+mod search;
+mod session;
+mod soup;
+mod stats;
+mod sweep;
+mod term;
+mod theme;
+mod view;
+mod watch;
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use rayon::prelude::*;
 
+use checkpoint::Checkpoint;
+use clusters::ClusterLog;
+use condition::Condition;
+use config::Config;
+use dashboard::{Dashboard, Period};
+use demo::Demo;
+use events::EventLog;
+use export::ans::AnsRecorder;
+use export::heatmap::Heatmap;
+use export::plot::Plot;
+use feed::Feed;
+use image::{Mapping, Raster};
+use keys::Keymap;
+use link::Link;
+use pattern::{apgcode, banner, library, Pattern, PatternFile, Transform};
+use provenance::Provenance;
+use record::cast::CastRecorder;
+use record::gif::GifRecorder;
+use record::html::{self, HtmlRecorder};
+use record::ndjson::{self, NdjsonRecorder};
+use record::replay::{ReplayRecorder, Tape};
+use record::video::VideoRecorder;
+use record::Recorder;
+#[cfg(feature = "notcurses")]
+use render::notcurses::Notcurses;
+use render::Output;
+use script::{Script, ScriptWriter};
+use session::{Session, Settings};
+use soup::{Distribution, Region, Soup};
+use stats::StatsLog;
+use theme::{Charset, Coloring, Theme};
+use view::View;
+use watch::Watch;
+
 /// This helps us gracefully exit the program while printing the cause.
 /// This macro will take in a string and optionally an Error and print them
-/// both.
+/// both, after putting the terminal back the way we found it.
 macro_rules! die {
     ($s:expr $(, $e: ident)?) => {
+        term::leave();
         println!("Error: {}", $s);
 
         $( println!("{}", $e); )?
@@ -26,6 +102,12 @@ macro_rules! die {
     }
 }
 
+/// Everything `--output` accepts.
+#[cfg(not(feature = "notcurses"))]
+const OUTPUTS: &[&str] = &["text", "sixel", "kitty", "iterm"];
+#[cfg(feature = "notcurses")]
+const OUTPUTS: &[&str] = &["text", "sixel", "kitty", "iterm", "notcurses"];
+
 /// Represents one of two main errors with rulestrings.
 #[derive(Debug)]
 enum RuleError {
@@ -59,7 +141,7 @@ impl fmt::Display for RuleError {
 /// let neighbours = 3;
 /// assert_eq!(rules.b[neighbours], true);
 /// ```
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct Rulestring {
     b: [bool; 9],
     s: [bool; 9],
@@ -99,7 +181,11 @@ impl fmt::Display for Rulestring {
 impl std::str::FromStr for Rulestring {
     type Err = RuleError;
 
+    #[allow(clippy::collapsible_match)]
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(named) = rules::by_name(s.trim()) {
+            return named.rule.parse();
+        }
         let mut rules = Rulestring::new();
 
         let bs: Vec<&str> = s.trim().split('/').collect();
@@ -155,11 +241,18 @@ impl std::str::FromStr for Rulestring {
 }
 
 /// The main struct used to represent the state of the automata.
+#[derive(Clone)]
 struct Matrix {
     m: usize,
     n: usize,
     rules: Rulestring,
     rows: Vec<usize>,
+    /// How many generations each cell has been alive, or dead, for. Cells
+    /// that have never been alive count as having been dead forever.
+    ages: Vec<u32>,
+    generation: u64,
+    births: usize,
+    deaths: usize,
 }
 
 impl Matrix {
@@ -172,23 +265,117 @@ impl Matrix {
             n,
             rules,
             rows: vec![0; m * n],
+            ages: vec![u32::MAX; m * n],
+            generation: 0,
+            births: 0,
+            deaths: 0,
+        }
+    }
+
+    /// `place` copies a block of `height` by `width` cells into the matrix
+    /// with its top left corner at `row`, `col`. Like everything else the
+    /// block wraps around the edges.
+    fn place(&mut self, row: usize, col: usize, height: usize, width: usize, cells: &[usize]) {
+        for (i, &state) in cells.iter().enumerate().take(height * width) {
+            let r = (row + i / width) % self.m;
+            let c = (col + i % width) % self.n;
+            self.rows[r * self.n + c] = state;
+            self.ages[r * self.n + c] = if state != 0 { 0 } else { u32::MAX };
         }
     }
 
-    /// `seed` randomly sets a cell a certain number of times. If the number
-    /// isn't provided, then the default is to do this for half the size of
-    /// the matrix.
-    fn seed(&mut self, cells: Option<usize>) {
-        let mut rng = rand::thread_rng();
-        let indices = Uniform::from(0..self.rows.len());
-        let iterations: usize = match cells {
-            Some(n) => n,
-            None => self.rows.len() / 2,
+    /// Brings a dead cell to life or kills a live one, by hand.
+    fn flip(&mut self, row: usize, col: usize) {
+        let alive = self.rows[row * self.n + col] != 0;
+        self.set(row, col, if alive { 0 } else { 1 });
+    }
+
+    /// `set` puts a cell in the given state by hand. A cell that comes to
+    /// life or dies this way is as young as one that did so on its own.
+    fn set(&mut self, row: usize, col: usize, state: usize) {
+        let i = row * self.n + col;
+        if (self.rows[i] != 0) != (state != 0) {
+            self.ages[i] = 0;
+        }
+        self.rows[i] = state;
+    }
+
+    /// `resize` changes the size of the matrix, keeping the cells that still
+    /// fit where they were. Any new cells are dead.
+    fn resize(&mut self, m: usize, n: usize) {
+        self.reframe(m, n, (0, 0));
+    }
+
+    /// `grow` changes the size of the matrix like `resize`, but keeps what
+    /// was there in the middle if `anchor` says so, growing or shrinking
+    /// evenly on every side.
+    fn grow(&mut self, m: usize, n: usize, anchor: Anchor) {
+        let offset = match anchor {
+            Anchor::Centre => (
+                (m as isize - self.m as isize) / 2,
+                (n as isize - self.n as isize) / 2,
+            ),
+            Anchor::Corner => (0, 0),
         };
+        self.reframe(m, n, offset);
+    }
 
-        for _ in 0..iterations {
-            let i = indices.sample(&mut rng);
-            self.rows[i] = 1;
+    /// `reframe` changes the size of the matrix, moving every cell down and
+    /// right by `offset` on the way. Cells that end up outside are lost and
+    /// any new cells are dead.
+    fn reframe(&mut self, m: usize, n: usize, (dr, dc): (isize, isize)) {
+        let mut rows = vec![0; m * n];
+        let mut ages = vec![u32::MAX; m * n];
+
+        for r in 0..self.m {
+            for c in 0..self.n {
+                let (nr, nc) = (r as isize + dr, c as isize + dc);
+                if nr < 0 || nc < 0 || nr >= m as isize || nc >= n as isize {
+                    continue;
+                }
+                let i = nr as usize * n + nc as usize;
+                rows[i] = self.rows[r * self.n + c];
+                ages[i] = self.ages[r * self.n + c];
+            }
+        }
+
+        self.m = m;
+        self.n = n;
+        self.rows = rows;
+        self.ages = ages;
+    }
+
+    /// `neighbours` adds up the states of the eight cells around a cell.
+    fn neighbours(&self, row: usize, col: usize) -> usize {
+        let c = self.n;
+        let r = self.m;
+
+        let col_next = (col + 1) % c;
+        let col_prev = (col + (c - 1)) % c;
+
+        let row_next = (row + 1) % r;
+        let row_prev = (row + (r - 1)) % r;
+
+        self.rows[(c * row) + col_prev]
+            + self.rows[(c * row) + col_next]
+            + self.rows[(c * row_prev) + col]
+            + self.rows[(c * row_prev) + col_prev]
+            + self.rows[(c * row_prev) + col_next]
+            + self.rows[(c * row_next) + col]
+            + self.rows[(c * row_next) + col_prev]
+            + self.rows[(c * row_next) + col_next]
+    }
+
+    /// `next` is the state a cell will be in next generation.
+    fn next(&self, row: usize, col: usize) -> usize {
+        let sum = self.neighbours(row, col);
+
+        if self.rules.b[sum] {
+            1
+        } else if self.rules.s[sum] {
+            self.rows[row * self.n + col]
+        } else {
+            0
         }
     }
 
@@ -197,59 +384,62 @@ impl Matrix {
     /// To make things easier, we have a 1D array and calculate our indices
     /// mathematically. We also use modular arithmetic to wrap our rows and
     /// columns.
+    ///
+    /// Alongside the states we keep the age of every cell, i.e. how many
+    /// generations it has been alive or dead, which themes use for gradients
+    /// and for colouring changes, and count how many cells were born and how
+    /// many died.
     fn pulse(&mut self) {
-        self.rows = self
-            .rows
+        let next: Vec<usize> = (0..self.rows.len())
+            .into_par_iter()
+            .map(|i| self.next(i / self.n, i % self.n))
+            .collect();
+
+        self.ages = next
             .par_iter()
-            .enumerate()
-            .map(|(i, n)| {
-                let c = self.n;
-                let r = self.m;
-
-                let col = i % c;
-                let row = i / c;
-
-                let col_next = (i + 1) % c;
-                let col_prev = (i + (c - 1)) % c;
-
-                let row_next = (row + 1) % r;
-                let row_prev = (row + (r - 1)) % r;
-
-                let sum = self.rows[(c * row) + col_prev]
-                    + self.rows[(c * row) + col_next]
-                    + self.rows[(c * row_prev) + col]
-                    + self.rows[(c * row_prev) + col_prev]
-                    + self.rows[(c * row_prev) + col_next]
-                    + self.rows[(c * row_next) + col]
-                    + self.rows[(c * row_next) + col_prev]
-                    + self.rows[(c * row_next) + col_next];
-
-                if self.rules.b[sum] {
-                    1
-                } else if self.rules.s[sum] {
-                    *n
+            .zip(self.rows.par_iter())
+            .zip(self.ages.par_iter())
+            .map(|((new, old), age)| {
+                if (*new != 0) == (*old != 0) {
+                    age.saturating_add(1)
                 } else {
                     0
                 }
             })
             .collect();
+
+        let (births, deaths) = next
+            .par_iter()
+            .zip(self.rows.par_iter())
+            .map(|(new, old)| {
+                (
+                    (*new != 0 && *old == 0) as usize,
+                    (*new == 0 && *old != 0) as usize,
+                )
+            })
+            .reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+
+        self.rows = next;
+        self.births = births;
+        self.deaths = deaths;
+        self.generation += 1;
     }
 }
 
-/// The output for the program is generated here. Since this is just a mapping
-/// over `Matrix.rows`, it is simple enough to replace this with a different
-/// one or even pass the array as output to a different program.
+/// The plain output for the program is generated here. Since this is just a
+/// mapping over `Matrix.rows`, it is simple enough to pass the array as
+/// output to a different program: every cell is written as its state, one
+/// digit per cell and one line per row, without any escape codes. The
+/// terminal output lives in `Theme` and `View`.
 impl fmt::Display for Matrix {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut out = String::new();
 
         for chunk in self.rows.chunks(self.n) {
-            out.push_str(
-                &chunk
+            out.extend(
+                chunk
                     .iter()
-                    .map(|x| if *x == 0 { "░░" } else { "▓▓" })
-                    .collect::<Vec<&str>>()
-                    .join(""),
+                    .map(|&x| std::char::from_digit(x as u32, 36).unwrap_or('?')),
             );
             out.push('\n');
         }
@@ -258,105 +448,2941 @@ impl fmt::Display for Matrix {
     }
 }
 
-/// `main` is where our `Matrix` is instantiated and where the output loop is.
-///
-/// Note that this loop will have to be terminated using ^c or an equivalent.
-///
-/// `main` also takes care of our arguments using the `clap` library.
-fn main() {
-    let matches = App::new("Iron Cellular Automata for Terminals")
-        .version("1.0")
-        .author("Joe Peterson")
-        .about("Runs an animated cellular automata simulation in the terminal.")
-        .arg(
-            Arg::with_name("rows")
-                .short("m")
-                .long("rows")
-                .value_name("ROWS")
-                .help("Number of rows")
-                .takes_value(true),
-        )
-        .arg(
-            Arg::with_name("columns")
-                .short("n")
-                .long("columns")
-                .value_name("COLUMNS")
-                .help("Number of columns")
-                .takes_value(true),
-        )
-        .arg(
-            Arg::with_name("rulestring")
-                .short("r")
-                .long("rulestring")
-                .value_name("RULESTRING")
-                .help("Rulestring for the automata in B/S notation")
-                .required(true)
-                .takes_value(true),
-        )
-        .arg(
-            Arg::with_name("seed_iter")
-                .short("s")
-                .long("seed")
-                .value_name("SEED")
-                .help("Set random cells SEED times")
-                .takes_value(true),
-        )
-        .get_matches();
+/// Parses an optional numeric argument, exiting with an error if it is
+/// malformed.
+fn number<T>(matches: &ArgMatches, name: &str) -> Option<T>
+where
+    T: std::str::FromStr,
+    T::Err: fmt::Display,
+{
+    match matches.value_of(name).map(str::parse::<T>) {
+        Some(Ok(x)) => Some(x),
+        Some(Err(e)) => {
+            die!(format!("Invalid value for '{}'.", name), e);
+        }
+        None => None,
+    }
+}
 
-    let m = match matches.value_of("rows").unwrap_or("23").parse::<usize>() {
-        Ok(x) => {
-            if x > 0 {
-                x
-            } else {
-                die!("Can't have zero rows.");
+/// Where the old world ends up in a bigger or smaller one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Anchor {
+    Centre,
+    Corner,
+}
+
+impl std::str::FromStr for Anchor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Anchor, String> {
+        match s {
+            "centre" | "center" => Ok(Anchor::Centre),
+            "corner" => Ok(Anchor::Corner),
+            _ => Err(format!("Invalid anchor {}: expected centre or corner", s)),
+        }
+    }
+}
+
+/// Parses a size written as `WIDTHxHEIGHT`, or `ROWSxCOLUMNS` for worlds,
+/// neither of which may be zero.
+fn dimensions(s: &str) -> Option<(usize, usize)> {
+    let mut parts = s.splitn(2, 'x');
+    let w = parts.next()?.trim().parse::<usize>().ok()?;
+    let h = parts.next()?.trim().parse::<usize>().ok()?;
+
+    if w > 0 && h > 0 {
+        Some((w, h))
+    } else {
+        None
+    }
+}
+
+/// Reads the rule in a rule file: the first line that isn't empty or a
+/// `#` comment.
+fn rule_file(path: &Path) -> Result<Rulestring, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let line = text
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with('#'))
+        .ok_or("there's no rule in it")?;
+    line.parse::<Rulestring>().map_err(|e| e.to_string())
+}
+
+/// Parses a point written as `X,Y`.
+fn coordinates(s: &str) -> Option<(usize, usize)> {
+    let (x, y) = s.split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+/// Reads a length of time as a number and a unit, `ms`, `s`, `m` or `h`,
+/// or seconds with no unit.
+fn duration(s: &str) -> Option<time::Duration> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let seconds = match unit.trim() {
+        "ms" => 0.001,
+        "" | "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3600.0,
+        _ => return None,
+    };
+    let number: f64 = number.parse().ok()?;
+    time::Duration::try_from_secs_f64(number * seconds).ok()
+}
+
+/// Reads a rectangle as `X,Y,W,H`: the column and row of its top left
+/// corner, then its width and height.
+fn region(s: &str) -> Option<Region> {
+    let parts: Vec<usize> = s
+        .split(',')
+        .map(|p| p.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    match parts[..] {
+        [col, row, width, height] if width > 0 && height > 0 => Some(Region {
+            row,
+            col,
+            height,
+            width,
+        }),
+        _ => None,
+    }
+}
+
+/// A pattern to put down at the start, as `--place` gives it: a built-in
+/// pattern, a pattern file or an apgcode, `@` and where its top left corner
+/// goes, then any transforms, as in `glider@10,5:rot90`.
+#[derive(Debug)]
+struct Placement {
+    source: String,
+    at: (usize, usize),
+    transforms: Vec<Transform>,
+}
+
+impl std::str::FromStr for Placement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Placement, String> {
+        let bad = || {
+            format!(
+                "Invalid placement {}: expected PATTERN@X,Y and any of :rot90, :rot180, \
+                 :rot270, :flipX, :flipY or :scale2",
+                s
+            )
+        };
+        let (source, rest) = s.rsplit_once('@').ok_or_else(bad)?;
+        let mut parts = rest.split(':');
+        let at = parts.next().and_then(coordinates).ok_or_else(bad)?;
+        let transforms = parts.map(str::parse).collect::<Result<_, _>>()?;
+
+        Ok(Placement {
+            source: source.to_string(),
+            at,
+            transforms,
+        })
+    }
+}
+
+/// Loads a pattern as `open` does, then turns it around as any transforms
+/// on the end of its name say, as in `gun.rle:rot90:scale2`.
+fn load(spec: &str) -> PatternFile {
+    let (source, transforms) = pattern::suffixes(spec);
+    let mut file = open(source);
+    for transform in transforms {
+        file.pattern = file.pattern.transform(transform);
+    }
+    file
+}
+
+/// The files another run leaves behind to carry on from: checkpoints, and
+/// patterns such as `--emit-final` writes or `W` saves.
+const CHAINED: &[&str] = &["icat", "rle", "cells", "lif", "life", "mc"];
+
+/// The most recently changed checkpoint or pattern file in a directory.
+fn latest(dir: &Path) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let path = entry.path();
+            path.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| CHAINED.contains(&e))
+        })
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max()
+        .map(|(_, path)| path)
+}
+
+/// Loads a built-in pattern written as `name:glider`, a pattern file or a
+/// URL, or the object an apgcode stands for unless there's a file by that
+/// name, exiting with an error if it can't.
+fn open(source: &str) -> PatternFile {
+    if let Some(name) = source.strip_prefix("name:") {
+        return match library::get(name) {
+            Some(pattern) => PatternFile {
+                pattern,
+                rule: None,
+                comments: Vec::new(),
+            },
+            None => {
+                die!(format!(
+                    "There's no built-in pattern called {}; `iron-cat patterns` lists them.",
+                    name
+                ));
+            }
+        };
+    }
+    if apgcode::is_apgcode(source) && !Path::new(source).exists() {
+        return match apgcode::decode(source) {
+            Ok(pattern) => PatternFile {
+                pattern,
+                rule: None,
+                comments: Vec::new(),
+            },
+            Err(e) => {
+                die!(format!("Could not load the pattern {}.", source), e);
             }
+        };
+    }
+    let text = if net::is_url(source) {
+        net::fetch(source).map_err(|e| e.to_string())
+    } else {
+        fs::read_to_string(source).map_err(|e| e.to_string())
+    };
+    let text = match text {
+        Ok(text) => text,
+        Err(e) => {
+            die!(format!("Could not load the pattern {}.", source), e);
         }
+    };
+    match pattern::read(&text) {
+        Ok(file) => file,
         Err(e) => {
-            die!("Invalid value for 'rows'.", e);
+            die!(format!("Could not load the pattern {}.", source), e);
+        }
+    }
+}
+
+/// The `render` subcommand: runs the simulation silently up to the requested
+/// generation and writes it out as a single image.
+fn render(
+    matches: &ArgMatches,
+    theme: &Theme,
+    matrix: &mut Matrix,
+    scale: usize,
+    provenance: &Provenance,
+) {
+    let generation = number::<u64>(matches, "generation").unwrap_or(0);
+
+    while matrix.generation < generation {
+        matrix.pulse();
+    }
+
+    let out = match matches.value_of("format") {
+        Some("svg") => {
+            let ages = matches.is_present("ages");
+            export::svg::svg(theme, matrix, scale, ages, provenance).into_bytes()
         }
+        Some("png") => image::png(theme, matrix, scale, provenance),
+        Some("pbm") => image::pbm(matrix, provenance),
+        Some("pgm") => image::pgm(matrix, provenance),
+        Some("ans") => export::ans::ans(theme, matrix, provenance).into_bytes(),
+        _ => unreachable!(),
     };
 
-    let n = match matches.value_of("columns").unwrap_or("38").parse::<usize>() {
-        Ok(x) => {
-            if x > 0 {
-                x
-            } else {
-                die!("Can't have zero columns.");
+    let result = match matches.value_of("out") {
+        Some(path) => std::fs::write(path, out),
+        None => std::io::stdout().write_all(&out),
+    };
+
+    if let Err(e) = result {
+        die!("Could not write snapshot.", e);
+    }
+}
+
+/// The `export` subcommand: runs the simulation silently up to the
+/// requested generation and writes its live cells out as a pattern.
+fn export(matches: &ArgMatches, matrix: &mut Matrix, provenance: &Provenance) {
+    let generation = number::<u64>(matches, "generation").unwrap_or(0);
+
+    while matrix.generation < generation {
+        matrix.pulse();
+    }
+
+    let live = Pattern::live(matrix);
+    let format = number::<pattern::Format>(matches, "format").unwrap_or(pattern::Format::Rle);
+    let out = format.write(&live, &matrix.rules, &provenance.lines());
+
+    let result = match matches.value_of("out") {
+        Some(path) => std::fs::write(path, out),
+        None => std::io::stdout().write_all(out.as_bytes()),
+    };
+
+    if let Err(e) = result {
+        die!("Could not write pattern.", e);
+    }
+}
+
+/// The names of the built-in patterns by their apgcodes under a rule, for
+/// naming what's found.
+fn names(rules: &Rulestring) -> HashMap<String, String> {
+    library::all()
+        .into_iter()
+        .filter_map(|(name, pattern)| Some((apgcode::identify(&pattern, rules)?, name)))
+        .collect()
+}
+
+/// The most generations `census` runs for, waiting for the world to settle.
+const SETTLE: u64 = 10_000;
+
+/// The `census` subcommand: runs the world to the generation asked for, or
+/// else until it settles, and counts the objects in it by apgcode, most
+/// first, naming those that are built in.
+fn census(matches: &ArgMatches, matrix: &mut Matrix) {
+    let mut dashboard = Dashboard::new(false);
+    dashboard.watch(matrix);
+    match number::<u64>(matches, "generation") {
+        Some(generation) => {
+            while matrix.generation < generation {
+                matrix.pulse();
             }
         }
+        None => {
+            while dashboard.period == Period::Changing && matrix.generation < SETTLE {
+                matrix.pulse();
+                dashboard.watch(matrix);
+            }
+        }
+    }
+
+    if matches.is_present("clusters") {
+        let sizes = clusters::sizes(matrix);
+        println!(
+            "generation {}, {} clusters, the largest {} cells",
+            matrix.generation,
+            sizes.len(),
+            sizes.first().unwrap_or(&0)
+        );
+        for (size, count) in clusters::histogram(&sizes) {
+            println!("{:>7}  of {}", count, size);
+        }
+        return;
+    }
+
+    let names = names(&matrix.rules);
+    let population = matrix.rows.iter().filter(|&&c| c != 0).count();
+
+    println!(
+        "generation {}, population {}",
+        matrix.generation, population
+    );
+    for (code, count) in apgcode::census(matrix) {
+        let name = code.as_ref().and_then(|c| names.get(c));
+        println!(
+            "{:>7}  {}{}",
+            count,
+            code.as_deref().unwrap_or("unidentified"),
+            name.map_or(String::new(), |n| format!(" ({})", n))
+        );
+    }
+}
+
+/// The `search` subcommand: runs many soups of the given size, each in the
+/// middle of a world three times as big unless told the world's size, and
+/// adds up their censuses. Objects not in the results of an earlier search
+/// are marked new, and can be written out as pattern files.
+fn search(
+    matches: &ArgMatches,
+    top: &ArgMatches,
+    rules: &Rulestring,
+    soup: &Soup,
+    seed: u64,
+    provenance: &Provenance,
+) {
+    let soups = number::<u64>(matches, "soups").unwrap_or(1000);
+    let (w, h) = match dimensions(matches.value_of("size").unwrap_or("16x16")) {
+        Some(size) => size,
+        None => {
+            die!("Invalid value for 'size', expected WxH.");
+        }
+    };
+    let m = number(top, "rows").unwrap_or(h * 3);
+    let n = number(top, "columns").unwrap_or(w * 3);
+    if h > m || w > n {
+        die!("Invalid value for 'size', expected WxH no bigger than the world.");
+    }
+    let soup = Soup {
+        region: Some(Region::centred(h, w, (m, n))),
+        ..soup.clone()
+    };
+
+    let known: Option<Vec<String>> =
+        matches
+            .value_of("known")
+            .map(|path| match fs::read_to_string(path) {
+                Ok(text) => text
+                    .split_whitespace()
+                    .filter(|word| apgcode::is_apgcode(word))
+                    .map(String::from)
+                    .collect(),
+                Err(e) => {
+                    die!("Could not read the earlier search.", e);
+                }
+            });
+    let names = names(rules);
+
+    let tallies = search::search(rules, (m, n), &soup, seed, soups);
+
+    println!(
+        "{} soups of {}x{} in {}x{} worlds under {}, from --rng-seed {}",
+        soups, w, h, m, n, rules, seed
+    );
+    let mut new = Vec::new();
+    for (code, tally) in &tallies {
+        let fresh = code
+            .as_ref()
+            .filter(|c| known.as_ref().is_some_and(|k| !k.contains(c)));
+        new.extend(fresh.map(|c| (c, tally.first)));
+        println!(
+            "{:>9}  {:<24} first in soup {}{}{}",
+            tally.count,
+            code.as_deref().unwrap_or("unidentified"),
+            tally.first,
+            code.as_ref()
+                .and_then(|c| names.get(c))
+                .map_or(String::new(), |name| format!(" ({})", name)),
+            if fresh.is_some() { "  new" } else { "" }
+        );
+    }
+
+    let Some(dir) = matches.value_of("export_new") else {
+        return;
+    };
+    if let Err(e) = fs::create_dir_all(dir) {
+        die!("Could not create the directory for new objects.", e);
+    }
+    // Without an earlier search to go by, everything is new.
+    if known.is_none() {
+        new = tallies
+            .iter()
+            .filter_map(|(code, tally)| Some((code.as_ref()?, tally.first)))
+            .collect();
+    }
+    for (code, first) in new {
+        let pattern = match apgcode::decode(code) {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                die!("Could not write out a new object.", e);
+            }
+        };
+        let mut comments = provenance.lines();
+        comments.push(format!("found in soup {}", first));
+        let path = Path::new(dir).join(format!("{}.rle", code));
+        let out = pattern::Format::Rle.write(&pattern, rules, &comments);
+        if let Err(e) = fs::write(&path, out) {
+            die!(format!("Could not write {}.", path.display()), e);
+        }
+    }
+}
+
+/// `--ensemble N`: runs that many soups instead of drawing one, and says
+/// how they went on the whole, as text or as JSON with `--summary json`.
+fn ensemble(matches: &ArgMatches, matrix: &Matrix, soup: &Soup, seed: u64, runs: u64) {
+    let longest = number::<u64>(matches, "generations").unwrap_or(1000);
+    let outcomes = ensemble::run(
+        &matrix.rules,
+        (matrix.m, matrix.n),
+        soup,
+        seed,
+        runs,
+        longest,
+    );
+
+    let died: Vec<f64> = outcomes
+        .iter()
+        .filter(|o| o.died)
+        .filter_map(|o| o.settled)
+        .map(|g| g as f64)
+        .collect();
+    let populations: Vec<f64> = outcomes.iter().map(|o| o.population as f64).collect();
+    let settled: Vec<f64> = outcomes
+        .iter()
+        .filter_map(|o| o.settled)
+        .map(|g| g as f64)
+        .collect();
+    let measures = [
+        ("lifespan", &died),
+        ("final population", &populations),
+        ("time to settle", &settled),
+    ];
+
+    if matches.value_of("summary") == Some("json") {
+        let fields: Vec<String> = measures
+            .iter()
+            .map(|(name, values)| {
+                let stats = match ensemble::spread(values) {
+                    Some((mean, sd)) => format!(
+                        "{{\"mean\": {:.3}, \"sd\": {:.3}, \"runs\": {}}}",
+                        mean,
+                        sd,
+                        values.len()
+                    ),
+                    None => "null".to_string(),
+                };
+                format!("{}: {}", json::string(&name.replace(' ', "_")), stats)
+            })
+            .collect();
+        println!(
+            "{{\"runs\": {}, \"rule\": {}, \"seed\": {}, {}}}",
+            runs,
+            json::string(&matrix.rules.to_string()),
+            seed,
+            fields.join(", ")
+        );
+        return;
+    }
+
+    println!(
+        "{} runs of {} in {}x{} worlds for at most {} generations, from --rng-seed {}",
+        runs, matrix.rules, matrix.m, matrix.n, longest, seed
+    );
+    for (name, values) in measures {
+        match ensemble::spread(values) {
+            Some((mean, sd)) => println!(
+                "{:<17} mean {:>9.1}  σ {:>9.1}  ({} of {} runs)",
+                name,
+                mean,
+                sd,
+                values.len(),
+                runs
+            ),
+            None => println!("{:<17} none of {} runs", name, runs),
+        }
+    }
+}
+
+/// The `sweep` subcommand: runs soups at every density asked for and
+/// writes a table of how they went.
+fn sweep(
+    matches: &ArgMatches,
+    top: &ArgMatches,
+    matrix: &Matrix,
+    soup: &Soup,
+    seed: u64,
+    provenance: &Provenance,
+) {
+    let densities = match sweep::densities(matches.value_of("density").unwrap_or_default()) {
+        Some(densities) => densities,
+        None => {
+            die!("Invalid value for 'density', expected FROM..TO:STEP or a single density, all between 0 and 1.");
+        }
+    };
+    let runs = number::<u64>(matches, "runs").unwrap_or(20);
+    let longest = number::<u64>(top, "generations").unwrap_or(1000);
+
+    let rows = sweep::sweep(
+        &matrix.rules,
+        (matrix.m, matrix.n),
+        soup,
+        &densities,
+        runs,
+        seed,
+        longest,
+    );
+    let out = match matches.value_of("format") {
+        Some("json") => sweep::json(&rows, provenance),
+        _ => sweep::csv(&rows, provenance),
+    };
+
+    let result = match matches.value_of("out") {
+        Some(path) => std::fs::write(path, out),
+        None => std::io::stdout().write_all(out.as_bytes()),
+    };
+    if let Err(e) = result {
+        die!("Could not write the table.", e);
+    }
+}
+
+/// The `convert` subcommand: reads a pattern in one format and writes it
+/// in another, going by `--format` or else the extension of the file
+/// written.
+fn convert(matches: &ArgMatches, rule: Option<&str>) {
+    let input = matches.value_of("input").unwrap_or_default();
+    let out = matches.value_of("output");
+    let file = load(input);
+
+    let format = match number::<pattern::Format>(matches, "format") {
+        Some(format) => format,
+        None => match out.map(Path::new).and_then(pattern::Format::by_extension) {
+            Some(format) => format,
+            None if out.is_none() => pattern::Format::Rle,
+            None => {
+                die!("Can't tell which format to write from the file name, so give one with --format.");
+            }
+        },
+    };
+
+    // The rule asked for comes first, then the file's own, then Life.
+    let rule = rule.or(file.rule.as_deref()).unwrap_or("B3/S23");
+    let rules = match rule.trim().parse::<Rulestring>() {
+        Ok(r) => r,
         Err(e) => {
-            die!("Invalid value for 'columns'.", e);
+            die!("Invalid Rulestring.", e);
+        }
+    };
+
+    let text = format.write(&file.pattern, &rules, &file.comments);
+    let result = match out {
+        Some(path) => fs::write(path, text),
+        None => std::io::stdout().write_all(text.as_bytes()),
+    };
+    if let Err(e) = result {
+        die!("Could not write pattern.", e);
+    }
+}
+
+/// The `info` subcommand: says what's in a pattern file, and shows a
+/// small picture of it.
+fn info(matches: &ArgMatches) {
+    let input = matches.value_of("input").unwrap_or_default();
+    let file = load(input);
+
+    // The file's size can leave room around the cells, so measure them.
+    let cells: Vec<(i64, i64)> = file
+        .pattern
+        .cells
+        .iter()
+        .enumerate()
+        .filter(|&(_, &c)| c != 0)
+        .map(|(i, _)| {
+            let width = file.pattern.width;
+            ((i % width) as i64, (i / width) as i64)
+        })
+        .collect();
+    let live = Pattern::gather(&cells);
+
+    let rules = file
+        .rule
+        .as_deref()
+        .unwrap_or("B3/S23")
+        .parse::<Rulestring>();
+    let code = rules
+        .ok()
+        .and_then(|rules| apgcode::identify(&live, &rules));
+
+    println!("{}", input);
+    println!("bounding box  {}x{}", live.width, live.height);
+    println!("population    {}", cells.len());
+    println!(
+        "rule          {}",
+        file.rule.as_deref().unwrap_or("none given")
+    );
+    if let Some(code) = code {
+        println!("apgcode       {}", code);
+    }
+    if !file.comments.is_empty() {
+        println!();
+        for comment in &file.comments {
+            println!("  {}", comment);
+        }
+    }
+    if !cells.is_empty() {
+        println!();
+        for line in live.thumbnail(16, 64) {
+            println!("  {}", line);
         }
+    }
+}
+
+/// Writes the last generation of a run to standard output, for `--emit-final`,
+/// as a pattern or as the `X Y` coordinates of every live cell.
+fn emit_final(format: &str, matrix: &Matrix, provenance: &Provenance) {
+    let notes = provenance.lines();
+    let out = match format {
+        "cells" => pattern::Format::Cells.write(&Pattern::live(matrix), &matrix.rules, &notes),
+        "coords" => {
+            let mut out = String::new();
+            for (i, &state) in matrix.rows.iter().enumerate() {
+                if state != 0 {
+                    out.push_str(&format!("{} {}\n", i % matrix.n, i / matrix.n));
+                }
+            }
+            out
+        }
+        _ => pattern::Format::Rle.write(&Pattern::live(matrix), &matrix.rules, &notes),
     };
 
-    let rulestring = match matches
+    if let Err(e) = std::io::stdout().write_all(out.as_bytes()) {
+        die!("Could not write the final generation.", e);
+    }
+}
+
+/// The `collide` subcommand: crashes the second pattern into the first
+/// every way asked for, under the rule asked for, else the first's own,
+/// else Life, and says what came out how often.
+fn collisions(matches: &ArgMatches, top: &ArgMatches) {
+    let (first, second) = (
+        matches.value_of("first").unwrap_or_default(),
+        matches.value_of("second").unwrap_or_default(),
+    );
+    let (a, b) = (load(first), load(second));
+    let rule = top
         .value_of("rulestring")
-        .unwrap()
-        .parse::<Rulestring>()
-    {
+        .or(a.rule.as_deref())
+        .unwrap_or("B3/S23");
+    let rules = match rule.trim().parse::<Rulestring>() {
         Ok(r) => r,
         Err(e) => {
             die!("Invalid Rulestring.", e);
         }
     };
+    let range = number::<usize>(matches, "range").unwrap_or(8);
+    let phases = number::<u64>(matches, "phases").unwrap_or(4);
+    let longest = number::<u64>(top, "generations").unwrap_or(1000);
 
-    let mut matrix = Matrix::new(m, n, rulestring);
+    let collisions = collide::collide(&rules, &a.pattern, &b.pattern, range, phases, longest);
+    let names = names(&rules);
+    let total: usize = collisions.outcomes.iter().map(|(_, t)| t.count).sum();
+    println!(
+        "{} into {} under {}: {} collisions, {} misses",
+        second, first, rules, total, collisions.missed
+    );
+    for (outcome, tally) in &collisions.outcomes {
+        let objects: Vec<String> = outcome
+            .iter()
+            .map(|(code, count)| {
+                format!(
+                    "{}{}{}",
+                    if *count > 1 {
+                        format!("{} ", count)
+                    } else {
+                        String::new()
+                    },
+                    code.as_deref().unwrap_or("unidentified"),
+                    code.as_ref()
+                        .and_then(|c| names.get(c))
+                        .map_or(String::new(), |name| format!(" ({})", name))
+                )
+            })
+            .collect();
+        let p = tally.first;
+        println!(
+            "{:>7}  {}  first at {},{} phase {}",
+            tally.count,
+            if objects.is_empty() {
+                "nothing".to_string()
+            } else {
+                objects.join(" + ")
+            },
+            p.dx,
+            p.dy,
+            p.phase
+        );
+    }
+}
 
-    match matches.value_of("seed") {
-        Some(s) => match s.parse::<usize>() {
-            Ok(n) => matrix.seed(Some(n)),
-            Err(e) => {
-                die!("Invalid value for 'seed'", e);
-            }
-        },
-        None => matrix.seed(None),
+/// The `predecessor` subcommand: looks for a pattern that turns into the
+/// one given, under the rule asked for, else the file's own, else Life, and
+/// writes it out, or says there's none.
+fn find_predecessor(matches: &ArgMatches, rule: Option<&str>) {
+    let input = matches.value_of("input").unwrap_or_default();
+    let file = load(input);
+    let rule = rule.or(file.rule.as_deref()).unwrap_or("B3/S23");
+    let rules = match rule.trim().parse::<Rulestring>() {
+        Ok(r) => r,
+        Err(e) => {
+            die!("Invalid Rulestring.", e);
+        }
     };
+    if rules.b[0] {
+        die!("Can't look for predecessors under a rule with B0, where the whole plane comes to life.");
+    }
+    let margin = number::<usize>(matches, "margin").unwrap_or(1);
 
-    println!("\x1B[2J{}", &matrix);
+    let cells: Vec<(i64, i64)> = file
+        .pattern
+        .cells
+        .iter()
+        .enumerate()
+        .filter(|&(_, &c)| c != 0)
+        .map(|(i, _)| {
+            let width = file.pattern.width;
+            ((i % width) as i64, (i / width) as i64)
+        })
+        .collect();
+    let target = Pattern::gather(&cells);
 
-    loop {
+    let found = predecessor::search(&target, &rules, margin);
+    let Some(before) = found.predecessor else {
+        println!(
+            "No predecessor of {} under {} within {} cells of its {}x{} bounding box, after trying {} cells: it's a Garden of Eden as far as that goes.",
+            input, rules, margin, target.width, target.height, found.tried
+        );
+        return;
+    };
+
+    let out = matches.value_of("out");
+    let format = out
+        .map(Path::new)
+        .and_then(pattern::Format::by_extension)
+        .unwrap_or(pattern::Format::Rle);
+    let comments = [format!(
+        "A predecessor of {} under {}, found after trying {} cells",
+        input, rules, found.tried
+    )];
+    let text = format.write(&before, &rules, &comments);
+    let result = match out {
+        Some(path) => fs::write(path, text),
+        None => std::io::stdout().write_all(text.as_bytes()),
+    };
+    if let Err(e) = result {
+        die!("Could not write the predecessor.", e);
+    }
+}
+
+/// The `diff` subcommand: says which cells differ between two patterns,
+/// draws them if asked, and exits with 0 if none do or 1 if some do, as
+/// `diff` does.
+fn differences(matches: &ArgMatches) -> ! {
+    let (first, second) = (
+        matches.value_of("first").unwrap_or_default(),
+        matches.value_of("second").unwrap_or_default(),
+    );
+    let diff = diff::Diff::new(&load(first).pattern, &load(second).pattern);
+    println!("{}.", diff.summary(first, second));
+
+    match matches.value_of("out") {
+        Some("-") => {
+            for line in diff.picture(32, 64) {
+                println!("  {}", line);
+            }
+        }
+        Some(path) => {
+            let scale = number::<usize>(matches, "scale").unwrap_or(4);
+            let notes = [format!("The differences between {} and {}", first, second)];
+            if let Err(e) = fs::write(path, diff.canvas(scale).png(&notes)) {
+                die!("Could not write the differences.", e);
+            }
+        }
+        None => {}
+    }
+    std::process::exit(if diff.same() { 0 } else { 1 });
+}
+
+/// The most room `verify` leaves around a pattern, in cells each way. A
+/// pattern can't spread faster than a cell a generation, so it needs no
+/// more than one more than the generations run, but a long run would make
+/// for a huge world.
+const VERIFY_ROOM: usize = 512;
+
+/// Puts a pattern in a world with `room` cells around it.
+fn world(pattern: &Pattern, rules: &Rulestring, room: usize) -> Matrix {
+    let mut matrix = Matrix::new(
+        pattern.height + 2 * room,
+        pattern.width + 2 * room,
+        rules.clone(),
+    );
+    pattern.overlay(&mut matrix, (room, room));
+    matrix
+}
+
+/// The `verify` subcommand: runs a pattern for so many generations, under
+/// the rule asked for, else its own, else Life, and exits with 0 if what's
+/// left is the pattern expected, wherever it is, or 1 if it isn't.
+fn verify(matches: &ArgMatches, rule: Option<&str>) -> ! {
+    let (input, expected) = (
+        matches.value_of("pattern").unwrap_or_default(),
+        matches.value_of("expect").unwrap_or_default(),
+    );
+    let file = load(input);
+    let rule = rule.or(file.rule.as_deref()).unwrap_or("B3/S23");
+    let rules = match rule.trim().parse::<Rulestring>() {
+        Ok(r) => r,
+        Err(e) => {
+            die!("Invalid Rulestring.", e);
+        }
+    };
+    let generations = number::<u64>(matches, "generations").unwrap_or(0);
+
+    let room = (generations as usize).saturating_add(1).min(VERIFY_ROOM);
+    let mut matrix = world(&file.pattern, &rules, room);
+    while matrix.generation < generations {
         matrix.pulse();
-        println!("\x1B[H{}", &matrix);
-        thread::sleep(time::Duration::new(1, 0));
+    }
+    let got = Pattern::live(&matrix);
+    // Trimmed down to its live cells the same way.
+    let want = Pattern::live(&world(&load(expected).pattern, &rules, 1));
+
+    let population = |p: &Pattern| p.cells.iter().filter(|&&c| c != 0).count();
+    if got == want {
+        println!(
+            "{} matches {} after {} generations under {}.",
+            input, expected, generations, rules
+        );
+        std::process::exit(0);
+    }
+    println!(
+        "{} doesn't match {} after {} generations under {}: expected {} cells in {}x{}, got {} in {}x{}.",
+        input,
+        expected,
+        generations,
+        rules,
+        population(&want),
+        want.width,
+        want.height,
+        population(&got),
+        got.width,
+        got.height
+    );
+    std::process::exit(1);
+}
+
+/// The `patterns` subcommand: lists the built-in patterns, with their
+/// sizes, populations and apgcodes under Conway's Life where they have one.
+fn patterns() {
+    let life: Rulestring = "B3/S23".parse().expect("Life's rulestring is valid");
+    for (name, pattern) in library::all() {
+        let population = pattern.cells.iter().filter(|&&c| c != 0).count();
+        let code = apgcode::identify(&pattern, &life).unwrap_or_default();
+        println!(
+            "{:<16}{:>8}{:>6}  {}",
+            name,
+            format!("{}x{}", pattern.width, pattern.height),
+            population,
+            code
+        );
+    }
+}
+
+/// `main` is where our `Matrix` is instantiated and handed to a `Session`,
+/// which runs the output loop.
+///
+/// Note that unless `--generations` is given the loop will have to be ended
+/// with `q`, or ^c or an equivalent, which we catch so that recordings are
+/// finished and the terminal is restored.
+///
+/// `main` also takes care of our arguments using the `clap` library.
+fn main() {
+    let args = match project::arguments(std::env::args_os().collect()) {
+        Ok(args) => args,
+        Err(e) => {
+            die!("Could not open the project.", e);
+        }
+    };
+
+    let matches = App::new("Iron Cellular Automata for Terminals")
+        .version("1.0")
+        .author("Joe Peterson")
+        .about("Runs an animated cellular automata simulation in the terminal.")
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .arg(
+            Arg::with_name("rows")
+                .short("m")
+                .long("rows")
+                .value_name("ROWS")
+                .help("Number of rows")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("columns")
+                .short("n")
+                .long("columns")
+                .value_name("COLUMNS")
+                .help("Number of columns")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("rulestring")
+                .short("r")
+                .long("rulestring")
+                .value_name("RULESTRING")
+                .help("Rulestring for the automata in B/S notation, or the name of a well-known rule such as life, highlife, seeds or diamoeba")
+                .required_unless_one(&["compare", "pattern", "place", "stdin", "resume", "rule_file", "init_from"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("rule_file")
+                .long("rule-file")
+                .value_name("FILE")
+                .help("Read the rulestring from a file, and change to the new rule whenever the file is saved")
+                .conflicts_with_all(&["rulestring", "compare"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("compare")
+                .long("compare")
+                .value_name("RULE,RULE")
+                .help("Run two rules side by side from the same start")
+                .conflicts_with("rulestring")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("twin_perturb")
+                .long("twin-perturb")
+                .value_name("X,Y")
+                .help("Run a twin of the world beside it with one cell flipped, the one at column X of row Y or else the middle one, and say how many cells the two differ in as the damage spreads")
+                .conflicts_with_all(&["compare", "checkpoint_file", "record"])
+                .min_values(0)
+                .max_values(1)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("seed_iter")
+                .short("s")
+                .long("seed")
+                .value_name("SEED")
+                .help("Set about SEED random cells alive")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("density")
+                .long("density")
+                .value_name("P")
+                .help("Set every cell alive with probability P, between 0 and 1 [default: what suits the rule, or 0.5]")
+                .conflicts_with("seed_iter")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("seed_exact")
+                .long("seed-exact")
+                .value_name("N")
+                .help("Set exactly N different random cells alive")
+                .conflicts_with_all(&["seed_iter", "density", "seed_dist"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("seed_region")
+                .long("seed-region")
+                .value_name("X,Y,W,H")
+                .help("Only seed the W by H rectangle with its top left corner at column X of row Y")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("seed_center")
+                .long("seed-center")
+                .value_name("WxH")
+                .help("Only seed a W by H rectangle in the middle of the world")
+                .conflicts_with("seed_region")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("seed_dist")
+                .long("seed-dist")
+                .value_name("DIST")
+                .help("Spread the soup evenly, in a cloud in the middle, in N clouds at random, in blobs of Perlin noise or spread out with no two cells touching [default: uniform]  [possible values: uniform, gaussian, clusters:N, perlin, stratified]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("seed_bytes")
+                .long("seed-bytes")
+                .value_name("FILE")
+                .help("Start from the bits of FILE laid out row by row, a live cell for every 1")
+                .conflicts_with_all(&["pattern", "stdin", "image", "banner", "place", "seed_iter", "density", "seed_exact", "seed_dist"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("sprinkle")
+                .long("sprinkle")
+                .value_name("NAME:N,...")
+                .help("Start from N copies of each built-in pattern NAME at random, turned and flipped at random, instead of random cells")
+                .conflicts_with_all(&["pattern", "stdin", "image", "banner", "place", "seed_bytes", "seed_iter", "density", "seed_exact", "seed_dist"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("seed_phrase")
+                .long("seed-phrase")
+                .value_name("WORDS")
+                .help("Seed everything random with a hash of WORDS, so the same words make the same soup")
+                .conflicts_with_all(&["rng_seed", "resume", "play_script"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("noise")
+                .long("noise")
+                .value_name("P")
+                .help("Flip every cell with probability P after each generation, as mutation or thermal noise")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("noise_births")
+                .long("noise-births")
+                .value_name("K")
+                .help("Bring K random cells to life after each generation")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("feed")
+                .long("feed")
+                .value_name("FILE")
+                .help("Bring cells to life from raw PBM, PGM or PPM frames read one after another from a file, a named pipe or - for standard input, such as a webcam through ffmpeg, where pixels are darker than --threshold")
+                .conflicts_with("stdin")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("reseed_every")
+                .long("reseed-every")
+                .value_name("N")
+                .help("Start over from a fresh soup every N generations")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("reseed_keep")
+                .long("reseed-keep")
+                .help("Add the fresh soup to what's alive instead of starting over")
+                .requires("reseed_every"),
+        )
+        .arg(
+            Arg::with_name("noise_scale")
+                .long("noise-scale")
+                .value_name("CELLS")
+                .help("How many cells across the blobs of Perlin noise are, about [default: 8]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("noise_threshold")
+                .long("noise-threshold")
+                .value_name("T")
+                .help("Cells are alive where Perlin noise is above T, between 0 and 1; higher makes fewer blobs [default: 0.5]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("rng_seed")
+                .long("rng-seed")
+                .value_name("N")
+                .help("Seed everything random with N, so the run can be made again [default: random]")
+                .conflicts_with_all(&["resume", "play_script"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("image")
+                .long("image")
+                .value_name("FILE")
+                .help("Read the initial state from a PNG, PBM, PGM or PPM image")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("pattern")
+                .long("pattern")
+                .value_name("FILE")
+                .help("Start from a pattern in an RLE, Life 1.05/1.06, plaintext .cells or Macrocell file or URL, an apgcode or a built-in pattern such as name:glider, turned by any of :rot90, :rot180, :rot270, :flipX, :flipY and :scale2 on the end, in the middle of the world, under its own rule unless given one")
+                .conflicts_with_all(&["image", "seed_iter", "density", "seed_exact", "seed_region", "seed_center", "seed_dist"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("stdin")
+                .long("stdin")
+                .help("Start from a pattern read from standard input, as plaintext rows of . and O, RLE or any other pattern format")
+                .conflicts_with_all(&["pattern", "image", "seed_iter", "density", "seed_exact", "seed_region", "seed_center", "seed_dist"]),
+        )
+        .arg(
+            Arg::with_name("init_from")
+                .long("init-from")
+                .value_name("FILE")
+                .help("Start from the world in a checkpoint or pattern file another run left, such as its --emit-final output, or `last` for the newest one here, under its rule unless given another")
+                .conflicts_with_all(&["pattern", "stdin", "banner", "image", "seed_bytes", "sprinkle", "seed_iter", "density", "seed_exact", "seed_region", "seed_center", "seed_dist", "resume"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("banner")
+                .long("banner")
+                .value_name("TEXT")
+                .help("Start from TEXT written out in live cells, in capitals, digits and a little punctuation")
+                .conflicts_with_all(&["pattern", "stdin", "image", "seed_iter", "density", "seed_exact", "seed_region", "seed_center", "seed_dist"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("at")
+                .long("at")
+                .value_name("X,Y")
+                .help("Put the pattern's top left corner at column X of row Y instead")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("place")
+                .long("place")
+                .value_name("PATTERN@X,Y")
+                .help(
+                    "Put a built-in pattern, pattern file or apgcode down with its top left corner \
+                     at column X of row Y, turned by any of :rot90, :rot180, :rot270, :flipX and \
+                     :flipY or scaled up by :scale2; can be given more than once",
+                )
+                .conflicts_with_all(&["seed_iter", "density", "seed_exact", "seed_region", "seed_center", "seed_dist"])
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("image_map")
+                .long("image-map")
+                .value_name("MAPPING")
+                .help("Turn pixels into cells by a darkness threshold or the theme's palette")
+                .possible_values(&["threshold", "palette"])
+                .default_value("threshold")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("threshold")
+                .long("threshold")
+                .value_name("LEVEL")
+                .help("Pixels darker than LEVEL (0-255) are alive [default: 128]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("theme_file")
+                .long("theme-file")
+                .value_name("FILE")
+                .help("Load glyphs and colours from a TOML theme file")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("state_style")
+                .long("state-style")
+                .value_name("STATE=GLYPH[,FG[,BG]]")
+                .help("Draw cells in STATE with GLYPH and the given colours; may be repeated")
+                .multiple(true)
+                .number_of_values(1)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("charset")
+                .long("charset")
+                .value_name("CHARSET")
+                .help("Draw cells with the theme's glyphs or with emoji [default: blocks]")
+                .possible_values(&["blocks", "emoji"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("emoji")
+                .long("emoji")
+                .value_name("YOUNG,OLD,DYING,DEAD")
+                .help("Emoji for young, old, just died and dead cells [default: 🌱,🌳,🔥,  ]")
+                .requires("charset")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("color_by")
+                .long("color-by")
+                .value_name("MODE")
+                .help("Colour cells by their state, or show births and deaths in green and red [default: state]")
+                .possible_values(&["state", "diff"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("sparkline")
+                .long("sparkline")
+                .value_name("N")
+                .help("Plot the population of the last N generations under the grid")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("dashboard")
+                .long("dashboard")
+                .help("Show a panel of statistics beside the grid, or under it if there's no room"),
+        )
+        .arg(
+            Arg::with_name("status")
+                .long("status")
+                .help("Show a status bar with live statistics below the grid"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .value_name("OUTPUT")
+                .help("Draw the grid as text or with a terminal graphics protocol")
+                .possible_values(OUTPUTS)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("no_ansi")
+                .long("no-ansi")
+                .help("Print frames as plain rows of digits, without escape codes or delays"),
+        )
+        .arg(
+            Arg::with_name("dump_every")
+                .long("dump-every")
+                .value_name("N")
+                .help("With --no-ansi, only print every Nth generation")
+                .requires("no_ansi")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("scale")
+                .long("scale")
+                .value_name("PIXELS")
+                .help("Size of a cell in pixels for graphical output")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("render_budget")
+                .long("render-budget")
+                .value_name("MS")
+                .help("Draw more cheaply whenever a frame takes longer than MS milliseconds to write")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("max_bandwidth")
+                .long("max-bandwidth")
+                .value_name("BYTES")
+                .help("Draw more cheaply to stay under BYTES per second")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("paused")
+                .long("paused")
+                .help("Draw the first generation and wait for space before running")
+                .conflicts_with("no_ansi"),
+        )
+        .arg(
+            Arg::with_name("break_at")
+                .long("break-at")
+                .value_name("N")
+                .help("Pause at generation N, or stop there if nobody's at the keyboard; can be given more than once")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("break_when")
+                .long("break-when")
+                .visible_alias("pause-when")
+                .value_name("CONDITION")
+                .help(
+                    "Pause when a condition such as \"population<10\", \"births==0 && !ship\" \
+                     or \"period_detected\" starts to hold, or stop there if nobody's at the \
+                     keyboard; can be given more than once",
+                )
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("record_script")
+                .long("record-script")
+                .value_name("FILE")
+                .help("Write down the keys pressed and cells clicked, to play back later")
+                .takes_value(true)
+                .conflicts_with("no_ansi"),
+        )
+        .arg(
+            Arg::with_name("play_script")
+                .long("play-script")
+                .value_name("FILE")
+                .help("Start from a recorded script's world and do what it did")
+                .takes_value(true)
+                .conflicts_with("no_ansi"),
+        )
+        .arg(
+            Arg::with_name("demo")
+                .long("demo")
+                .value_name("FILE")
+                .help("Run through the steps of a TOML demo file by itself")
+                .takes_value(true)
+                .conflicts_with_all(&["no_ansi", "play_script"]),
+        )
+        .arg(
+            Arg::with_name("grow_to")
+                .long("grow-to")
+                .value_name("MxN")
+                .help("Grow or shrink the starting world to M rows and N columns")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("anchor")
+                .long("anchor")
+                .value_name("WHERE")
+                .help("Keep the world in the centre or the top left corner when resizing [default: centre]")
+                .possible_values(&["centre", "center", "corner"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("clipboard")
+                .long("clipboard")
+                .value_name("FILE")
+                .help("Where `w` writes the clipboard as RLE [default: clipboard.rle]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .value_name("FILE")
+                .help(
+                    "Read settings, such as [keys], from FILE \
+                     [default: ~/.config/iron-cat/config.toml]",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("library")
+                .long("library")
+                .value_name("DIR")
+                .help(
+                    "Where `l` looks for patterns of your own, as .rle, .cells or .lif files \
+                     [default: ~/.config/iron-cat/patterns]",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("rewind")
+                .long("rewind")
+                .value_name("N")
+                .help("How many generations and edits `u` can take back [default: 100, or none when nothing reads the keys]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("generations")
+                .long("generations")
+                .value_name("N")
+                .help("Stop after N generations")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("duration")
+                .long("duration")
+                .value_name("TIME")
+                .help("Stop after running for TIME, such as 30s, 500ms, 5m or 1h, or seconds on their own")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("checkpoint_every")
+                .long("checkpoint-every")
+                .value_name("N")
+                .help("Write a checkpoint to carry the run on from every N generations, and when it ends")
+                .requires("checkpoint_file")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("checkpoint_file")
+                .long("checkpoint-file")
+                .value_name("FILE")
+                .help("Where checkpoints are written, and read back by --resume")
+                .conflicts_with("compare")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("resume")
+                .long("resume")
+                .help("Carry on the run in --checkpoint-file from where it was, under the same rule unless given another")
+                .requires("checkpoint_file")
+                .conflicts_with_all(&[
+                    "pattern", "place", "stdin", "banner", "image", "seed_bytes", "sprinkle", "seed_iter", "density", "seed_exact", "seed_region", "seed_center", "seed_dist", "rows", "columns", "fit",
+                    "play_script",
+                ]),
+        )
+        .arg(
+            Arg::with_name("until_stable")
+                .long("until-stable")
+                .help("Stop once the world dies out or settles into a still life, oscillator or spaceship"),
+        )
+        .arg(
+            Arg::with_name("emit_final")
+                .long("emit-final")
+                .value_name("FORMAT")
+                .help(
+                    "Write the last generation to standard output when the run ends, as a pattern \
+                     or as X Y coordinates of live cells, instead of drawing frames there",
+                )
+                .possible_values(&["rle", "cells", "coords"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("record")
+                .long("record")
+                .value_name("FILE")
+                .help("Record every generation, to be played back later with the replay subcommand")
+                .conflicts_with("compare")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("record_gif")
+                .long("record-gif")
+                .value_name("FILE")
+                .help("Record the run as an animated GIF")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("gif_delay")
+                .long("gif-delay")
+                .value_name("MS")
+                .help("Time between frames of the GIF in milliseconds [default: 100]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("record_video")
+                .long("record-video")
+                .value_name("FILE")
+                .help("Record the run as a video by piping frames to ffmpeg")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("video_fps")
+                .long("video-fps")
+                .value_name("FPS")
+                .help("Frame rate of the video [default: 10]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("video_size")
+                .long("video-size")
+                .value_name("WxH")
+                .help("Resolution of the video, if not the grid size times --scale")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("record_cast")
+                .long("record-cast")
+                .value_name("FILE")
+                .help("Record the terminal output as an asciinema cast")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("record_html")
+                .long("record-html")
+                .value_name("FILE")
+                .help("Record the run as a self-contained HTML page")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("html_player")
+                .long("html-player")
+                .value_name("PLAYER")
+                .help("Play the HTML recording with a JavaScript canvas or pure CSS [default: canvas]")
+                .possible_values(&["canvas", "css"])
+                .requires("record_html")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("html_delay")
+                .long("html-delay")
+                .value_name("MS")
+                .help("Time between frames of the HTML recording in milliseconds [default: 100]")
+                .requires("record_html")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("export_ans")
+                .long("export-ans")
+                .value_name("FILE")
+                .help("Write the final frame as an ANSI art file when the run ends")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("heatmap")
+                .long("heatmap")
+                .value_name("FILE")
+                .help("Count how many times every cell comes to life or dies, and write the counts as a heatmap when the run ends: a PNG, or a PGM or ANSI art if FILE ends in .pgm or .ans")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("plot")
+                .long("plot")
+                .value_name("FILE")
+                .help("Draw the population of every generation as a chart when the run ends, as a PNG or in the terminal for -")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ensemble")
+                .long("ensemble")
+                .value_name("N")
+                .help("Run N soups alike but for their seeds instead of drawing one, each until it settles or for --generations [default: 1000], and say how long they lasted, how many cells they ended with and how long they took to settle")
+                .conflicts_with_all(&["pattern", "stdin", "image", "banner", "place", "seed_bytes", "sprinkle", "init_from", "resume", "play_script", "compare", "twin_perturb"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("summary")
+                .long("summary")
+                .value_name("FORMAT")
+                .help("Say on standard error how the run ended: the generation, the population and any still life or oscillator it settled into, since when and between what populations")
+                .possible_values(&["text", "json"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("stats_out")
+                .long("stats-out")
+                .value_name("FILE")
+                .help("Write the generation, population, births, deaths, density, activity, block entropy, complexity and box-counting dimension of every generation to FILE as CSV, or - for standard output")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("entropy_block")
+                .long("entropy-block")
+                .value_name("K")
+                .help("Side of the blocks the entropy and complexity in --stats-out are measured over, from 1 to 8 [default: 2]")
+                .requires("stats_out")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("clusters_out")
+                .long("clusters-out")
+                .value_name("FILE")
+                .help("Write how many clusters of live cells there are of every size to FILE as CSV, every --clusters-every generations")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("clusters_every")
+                .long("clusters-every")
+                .value_name("N")
+                .help("How many generations apart to count clusters for --clusters-out [default: 100]")
+                .requires("clusters_out")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("event_log")
+                .long("event-log")
+                .value_name("FILE")
+                .help("Write every reseed, change of rule, settling down, peak and trough of the population and anything else said under the grid to FILE as JSON lines")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("stream")
+                .long("stream")
+                .value_name("FORMAT")
+                .help("Stream every generation in a machine-readable format")
+                .possible_values(&["ndjson"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("stream_file")
+                .long("stream-file")
+                .value_name("FILE")
+                .help("Write the stream to FILE instead of standard output")
+                .requires("stream")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("stream_cells")
+                .long("stream-cells")
+                .value_name("CELLS")
+                .help("Write live cells as coordinates or as run-length encoded rows [default: coords]")
+                .possible_values(&["coords", "rows"])
+                .requires("stream")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ruler")
+                .long("ruler")
+                .help("Draw a border with row and column numbers"),
+        )
+        .arg(
+            Arg::with_name("fit")
+                .long("fit")
+                .help("Size the world to fill the terminal, and resize it along with the terminal")
+                .conflicts_with_all(&["rows", "columns", "image"]),
+        )
+        .subcommand(
+            SubCommand::with_name("render")
+                .about("Writes a snapshot of one generation instead of animating")
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Format of the snapshot")
+                        .possible_values(&["svg", "png", "pbm", "pgm", "ans"])
+                        .default_value("svg")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("generation")
+                        .long("generation")
+                        .value_name("N")
+                        .help("Generation to snapshot [default: 0]")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("ages")
+                        .long("ages")
+                        .help("Colour cells by age using the theme's gradient"),
+                )
+                .arg(
+                    Arg::with_name("out")
+                        .short("o")
+                        .long("out")
+                        .value_name("FILE")
+                        .help("Write to FILE instead of standard output")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("Writes the live cells of one generation as a pattern file instead of animating")
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Format of the pattern")
+                        .possible_values(&["rle", "life105", "life106", "cells", "mc"])
+                        .default_value("rle")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("generation")
+                        .long("generation")
+                        .value_name("N")
+                        .help("Generation to export [default: 0]")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("out")
+                        .short("o")
+                        .long("out")
+                        .value_name("FILE")
+                        .help("Write to FILE instead of standard output")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("census")
+                .about("Counts the objects the world settles into by apgcode, naming the ones that are built in")
+                .arg(
+                    Arg::with_name("generation")
+                        .long("generation")
+                        .value_name("N")
+                        .help("Generation to count at [default: once the world settles, or 10000]")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("clusters")
+                        .long("clusters")
+                        .help("Count the clusters of live cells of every size instead, touching at a side or a corner"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("search")
+                .about("Runs many random soups on every core and adds up the objects they settle into by apgcode, as apgsearch does")
+                .arg(
+                    Arg::with_name("soups")
+                        .long("soups")
+                        .value_name("N")
+                        .help("How many soups to run, from --rng-seed on [default: 1000]")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("size")
+                        .long("size")
+                        .value_name("WxH")
+                        .help("Size of every soup, in the middle of a world three times as big unless given --rows and --columns [default: 16x16]")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("known")
+                        .long("known")
+                        .value_name("FILE")
+                        .help("The results of an earlier search, so that objects it didn't find are marked new")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("export_new")
+                        .long("export-new")
+                        .value_name("DIR")
+                        .help("Write every new object to DIR as an RLE file named by its apgcode, or every object without --known")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("sweep")
+                .about("Runs soups at a range of densities and writes a table of how long they took to settle, how full they were and how many did, within --generations [default: 1000]")
+                .arg(
+                    Arg::with_name("density")
+                        .long("density")
+                        .value_name("RANGE")
+                        .help("Densities to run, as FROM..TO:STEP such as 0.05..0.95:0.05, or just one")
+                        .required(true)
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("runs")
+                        .long("runs")
+                        .value_name("N")
+                        .help("How many soups to run at every density [default: 20]")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Format of the table")
+                        .possible_values(&["csv", "json"])
+                        .default_value("csv")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("out")
+                        .short("o")
+                        .long("out")
+                        .value_name("FILE")
+                        .help("Write to FILE instead of standard output")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("convert")
+                .about("Converts a pattern from one format to another")
+                .arg(
+                    Arg::with_name("input")
+                        .value_name("IN")
+                        .help("Pattern file, URL, apgcode or built-in pattern to read, in any format")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .value_name("OUT")
+                        .help("File to write, in the format its extension says [default: standard output]")
+                        .index(2),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Format to write instead of going by the extension [default: rle]")
+                        .possible_values(&["rle", "life105", "life106", "cells", "mc"])
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("collide")
+                .about("Crashes one pattern into another at every offset and phase and adds up what comes out by apgcode")
+                .arg(
+                    Arg::with_name("first")
+                        .value_name("PATTERN")
+                        .help("Pattern file, URL, apgcode or built-in pattern that stays put")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("second")
+                        .value_name("PATTERN")
+                        .help("Pattern to crash into it, turned as need be, as in name:glider:rot180")
+                        .required(true)
+                        .index(2),
+                )
+                .arg(
+                    Arg::with_name("range")
+                        .long("range")
+                        .value_name("N")
+                        .help("How many cells the second pattern's corner goes from the first's each way [default: 8]")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("phases")
+                        .long("phases")
+                        .value_name("N")
+                        .help("How many generations on to try the second pattern at, from none [default: 4]")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("predecessor")
+                .about("Looks for a pattern that turns into the one given a generation later, or proves there's none in the box searched")
+                .arg(
+                    Arg::with_name("input")
+                        .value_name("PATTERN")
+                        .help("Pattern file, URL, apgcode or built-in pattern to find a predecessor of")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("margin")
+                        .long("margin")
+                        .value_name("N")
+                        .help("How many cells around the pattern's bounding box the predecessor may spread into [default: 1]")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("out")
+                        .short("o")
+                        .long("out")
+                        .value_name("FILE")
+                        .help("Where to write the predecessor, in the format its extension says [default: standard output, as RLE]")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about("Says which cells differ between two states of a world, exiting with 0 if none do or 1 if some do")
+                .arg(
+                    Arg::with_name("first")
+                        .value_name("PATTERN")
+                        .help("Pattern file, URL, apgcode or built-in pattern to compare")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("second")
+                        .value_name("PATTERN")
+                        .help("Pattern to compare it with, lined up by their top left corners")
+                        .required(true)
+                        .index(2),
+                )
+                .arg(
+                    Arg::with_name("out")
+                        .short("o")
+                        .long("out")
+                        .value_name("FILE")
+                        .help("Draws the differences as a PNG, cells alive only in the first red, only in the second green and in both grey, or as characters if FILE is -")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("scale")
+                        .long("scale")
+                        .value_name("N")
+                        .help("Pixels per cell in the PNG [default: 4]")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("Runs a pattern without drawing it and exits with 0 if it ends up as expected, or 1 if not, for checking pattern collections")
+                .arg(
+                    Arg::with_name("pattern")
+                        .long("pattern")
+                        .value_name("PATTERN")
+                        .help("Pattern file, URL, apgcode or built-in pattern to run")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("generations")
+                        .long("generations")
+                        .value_name("N")
+                        .help("How many generations to run it for")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("expect")
+                        .long("expect")
+                        .value_name("PATTERN")
+                        .help("What it should have become, wherever it has moved to")
+                        .takes_value(true)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("info")
+                .about("Says what's in a pattern file: its size, population, rule and comments")
+                .arg(
+                    Arg::with_name("input")
+                        .value_name("PATTERN")
+                        .help("Pattern file, URL, apgcode or built-in pattern to look at")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("open")
+                .about("Carries on a project, or any checkpoint, as it was left: world, rule, theme and view")
+                .arg(
+                    Arg::with_name("input")
+                        .value_name("FILE")
+                        .help("Project to open, as written by --checkpoint-file")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("replay")
+                .about("Plays back a run written with --record, to pause and scrub through")
+                .arg(
+                    Arg::with_name("input")
+                        .value_name("FILE")
+                        .help("Recording to play")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("patterns")
+                .about("Lists the built-in patterns, for --pattern name:NAME and --place"),
+        )
+        .get_matches_from(args);
+
+    if matches.subcommand_matches("patterns").is_some() {
+        patterns();
+        return;
+    }
+    if let Some(sub) = matches.subcommand_matches("info") {
+        info(sub);
+        return;
+    }
+    if let Some(sub) = matches.subcommand_matches("convert") {
+        convert(sub, matches.value_of("rulestring"));
+        return;
+    }
+    if let Some(sub) = matches.subcommand_matches("collide") {
+        collisions(sub, &matches);
+        return;
+    }
+    if let Some(sub) = matches.subcommand_matches("predecessor") {
+        find_predecessor(sub, matches.value_of("rulestring"));
+        return;
+    }
+    if let Some(sub) = matches.subcommand_matches("diff") {
+        differences(sub);
+    }
+    if let Some(sub) = matches.subcommand_matches("verify") {
+        verify(sub, matches.value_of("rulestring"));
+    }
+
+    let mut theme = match matches.value_of("theme_file") {
+        Some(path) => match Theme::load(path) {
+            Ok(t) => t,
+            Err(e) => {
+                die!("Could not load theme.", e);
+            }
+        },
+        None => Theme::default(),
+    };
+
+    if let Some(specs) = matches.values_of("state_style") {
+        if let Err(e) = theme.restyle(&specs.collect::<Vec<_>>()) {
+            die!("Could not style states.", e);
+        }
+    }
+
+    let theme = match matches.value_of("color_by") {
+        Some("diff") => theme.color_by(Coloring::Diff),
+        _ => theme,
+    };
+
+    let theme = match matches.value_of("charset") {
+        Some("emoji") => {
+            let given: Vec<&str> = matches
+                .value_of("emoji")
+                .map_or(Vec::new(), |e| e.split(',').collect());
+            theme.with_charset(Charset::emoji(&given))
+        }
+        _ => theme,
+    };
+
+    let scale = match matches.value_of("scale").unwrap_or("4").parse::<usize>() {
+        Ok(x) if x > 0 => x,
+        Ok(_) => {
+            die!("Can't have zero-pixel cells.");
+        }
+        Err(e) => {
+            die!("Invalid value for 'scale'.", e);
+        }
+    };
+
+    // An image brings its own size, but only as scaled with `--scale` when
+    // that is given explicitly.
+    let picture = matches.value_of("image").map(|path| {
+        let raster = match Raster::load(path) {
+            Ok(r) => r,
+            Err(e) => {
+                die!("Could not load image.", e);
+            }
+        };
+
+        let mapping = match matches.value_of("image_map") {
+            Some("palette") => Mapping::Palette,
+            _ => Mapping::Threshold(number(&matches, "threshold").unwrap_or(128)),
+        };
+
+        let pixels = if matches.is_present("scale") {
+            scale
+        } else {
+            1
+        };
+        let (m, n, cells) = raster.cells(pixels, mapping, &theme);
+        if m == 0 || n == 0 {
+            die!("The image is smaller than a single cell.");
+        }
+
+        (m, n, cells)
+    });
+
+    let resumed = if matches.is_present("resume") {
+        let path = Path::new(matches.value_of("checkpoint_file").unwrap_or_default());
+        match Checkpoint::load(path) {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(e) => {
+                die!("Could not resume the run.", e);
+            }
+        }
+    } else {
+        None
+    };
+    let resumed_rule = resumed.as_ref().map(|c| c.matrix.rules.to_string());
+
+    let tape = matches.subcommand_matches("replay").map(|sub| {
+        if ["fit", "compare", "twin_perturb", "grow_to"].iter().any(|a| matches.is_present(a)) {
+            die!("A recording is played back at its own size, in one world, so it can't be fitted or compared.");
+        }
+        match Tape::load(Path::new(sub.value_of("input").unwrap_or_default())) {
+            Ok(tape) => tape,
+            Err(e) => {
+                die!("Could not play the recording.", e);
+            }
+        }
+    });
+    let tape_rule = tape.as_ref().map(|t| t.rules().to_string());
+
+    // A world carried on from a checkpoint keeps its size.
+    let mut init_size = None;
+    let pattern = if matches.is_present("stdin") {
+        let mut text = String::new();
+        if let Err(e) = std::io::stdin().read_to_string(&mut text) {
+            die!("Could not read the pattern from standard input.", e);
+        }
+        match pattern::read(&text) {
+            Ok(file) => Some(file),
+            Err(e) => {
+                die!("Could not read the pattern from standard input.", e);
+            }
+        }
+    } else if let Some(source) = matches.value_of("init_from") {
+        let path = match source {
+            "last" => match latest(Path::new(".")) {
+                Some(path) => path,
+                None => {
+                    die!("There's no checkpoint or pattern file here to start from.");
+                }
+            },
+            _ => PathBuf::from(source),
+        };
+        if Checkpoint::is_checkpoint(&path) {
+            match Checkpoint::load(&path) {
+                Ok(checkpoint) => {
+                    let world = &checkpoint.matrix;
+                    init_size = Some((world.m, world.n));
+                    Some(PatternFile {
+                        pattern: Pattern::copy(world, (0, 0), (world.m - 1, world.n - 1)),
+                        rule: Some(world.rules.to_string()),
+                        comments: Vec::new(),
+                    })
+                }
+                Err(e) => {
+                    die!(format!("Could not start from {}.", path.display()), e);
+                }
+            }
+        } else {
+            Some(open(&path.to_string_lossy()))
+        }
+    } else if let Some(text) = matches.value_of("banner") {
+        match banner::banner(text) {
+            Ok(pattern) => Some(PatternFile {
+                pattern,
+                rule: None,
+                comments: Vec::new(),
+            }),
+            Err(c) => {
+                die!(format!(
+                    "Invalid value for 'banner': there's no {:?} in the font.",
+                    c
+                ));
+            }
+        }
+    } else {
+        matches.value_of("pattern").map(load)
+    };
+
+    // Built-in patterns go by name, unless there's a file by that name.
+    let places: Vec<(Placement, PatternFile)> = matches
+        .values_of("place")
+        .into_iter()
+        .flatten()
+        .map(|spec| {
+            let place = match spec.parse::<Placement>() {
+                Ok(place) => place,
+                Err(e) => {
+                    die!("Invalid value for 'place'.", e);
+                }
+            };
+            let mut file = match library::get(&place.source) {
+                Some(pattern) if !Path::new(&place.source).exists() => PatternFile {
+                    pattern,
+                    rule: None,
+                    comments: Vec::new(),
+                },
+                _ => open(&place.source),
+            };
+            for &transform in &place.transforms {
+                file.pattern = file.pattern.transform(transform);
+            }
+            (place, file)
+        })
+        .collect();
+
+    if pattern.is_none() && matches.is_present("at") {
+        die!("--at places the pattern from --pattern or --stdin, and there isn't one.");
+    }
+
+    // A pattern gets a world at least big enough to hold it, and so do
+    // patterns placed where asked.
+    let reach = places.iter().fold((0, 0), |(m, n), (place, file)| {
+        let (x, y) = place.at;
+        (
+            m.max(y + file.pattern.height),
+            n.max(x + file.pattern.width),
+        )
+    });
+    let default_m = match (init_size, &picture, &pattern) {
+        (Some((m, _)), _, _) => m,
+        (_, Some(p), _) => p.0,
+        (_, _, Some(file)) => file.pattern.height.max(23),
+        _ => 23,
+    }
+    .max(reach.0)
+    .to_string();
+    let default_n = match (init_size, &picture, &pattern) {
+        (Some((_, n)), _, _) => n,
+        (_, Some(p), _) => p.1,
+        (_, _, Some(file)) => file.pattern.width.max(38),
+        _ => 38,
+    }
+    .max(reach.1)
+    .to_string();
+
+    let m = match matches
+        .value_of("rows")
+        .unwrap_or(&default_m)
+        .parse::<usize>()
+    {
+        Ok(x) => {
+            if x > 0 {
+                x
+            } else {
+                die!("Can't have zero rows.");
+            }
+        }
+        Err(e) => {
+            die!("Invalid value for 'rows'.", e);
+        }
+    };
+
+    let n = match matches
+        .value_of("columns")
+        .unwrap_or(&default_n)
+        .parse::<usize>()
+    {
+        Ok(x) => {
+            if x > 0 {
+                x
+            } else {
+                die!("Can't have zero columns.");
+            }
+        }
+        Err(e) => {
+            die!("Invalid value for 'columns'.", e);
+        }
+    };
+
+    // When comparing, the first rule runs in the usual world and the second
+    // in a copy of it.
+    let from_file = matches
+        .value_of("rule_file")
+        .map(|path| match rule_file(Path::new(path)) {
+            Ok(rules) => rules.to_string(),
+            Err(e) => {
+                die!(format!("Could not read the rule file {}: {}.", path, e));
+            }
+        });
+
+    let (rulestring, rival_rules) = match matches.value_of("compare") {
+        Some(pair) => match pair.split_once(',') {
+            Some((a, b)) => (a, Some(b)),
+            None => {
+                die!("Invalid value for 'compare', expected two rules separated by a comma.");
+            }
+        },
+        None => match matches
+            .value_of("rulestring")
+            .or(from_file.as_deref())
+            .or(pattern.as_ref().and_then(|file| file.rule.as_deref()))
+            .or(places.iter().find_map(|(_, file)| file.rule.as_deref()))
+            .or(resumed_rule.as_deref())
+            .or(tape_rule.as_deref())
+        {
+            Some(rule) => (rule, None),
+            None if pattern.is_some() || !places.is_empty() => {
+                die!("The pattern doesn't say which rule it runs under, so give one with --rulestring.");
+            }
+            None => {
+                die!("A rule is needed: give one with --rulestring.");
+            }
+        },
+    };
+
+    let rulestring = match rulestring.trim().parse::<Rulestring>() {
+        Ok(r) => r,
+        Err(e) => {
+            die!("Invalid Rulestring.", e);
+        }
+    };
+
+    let rival_rules = rival_rules.map(|r| match r.trim().parse::<Rulestring>() {
+        Ok(r) => r,
+        Err(e) => {
+            die!("Invalid Rulestring.", e);
+        }
+    });
+
+    // Consoles that can't do escape codes get plain output, but still at
+    // the usual pace since someone is watching.
+    let legacy = !matches.is_present("no_ansi") && !term::ansi();
+    if legacy {
+        eprintln!("This console doesn't understand escape codes, so printing plain output.");
+    }
+
+    let output = if matches.is_present("no_ansi") || legacy {
+        Output::Plain
+    } else {
+        matches
+            .value_of("output")
+            .unwrap_or("text")
+            .parse::<Output>()
+            .unwrap()
+    };
+
+    let mut view = View {
+        ruler: matches.is_present("ruler"),
+        status: matches.is_present("status"),
+        sparkline: number(&matches, "sparkline").unwrap_or(0),
+        output,
+        scale,
+        ..View::default()
+    };
+
+    // With `--fit` the terminal decides the size of the world. We don't know
+    // how many rows there will be yet, but there can't be more than the
+    // terminal has, so that's enough to size the ruler's gutter.
+    let fit = matches.is_present("fit");
+    let cell_width = theme.cell_width();
+
+    // Two worlds share the terminal between them, after the dashboard has
+    // taken its share.
+    let split = rival_rules.is_some() || matches.is_present("twin_perturb");
+    let docked = matches.is_present("dashboard") && output.is_text() && output != Output::Plain;
+    let pane = |size| {
+        let size = if docked { dashboard::room(size) } else { size };
+        if split {
+            view::halve(size)
+        } else {
+            size
+        }
+    };
+
+    let (m, n) = if fit {
+        if !output.is_text() {
+            die!("Can only fit the world to the terminal with text output.");
+        }
+        match term::size().map(pane) {
+            Some(size) => view.room(size, size.1, cell_width),
+            None => {
+                die!("Can't fit the world to the terminal without a terminal.");
+            }
+        }
+    } else {
+        (m, n)
+    };
+
+    let mut matrix = Matrix::new(m, n, rulestring);
+
+    // Well-known rules start from soups that suit them, unless told
+    // otherwise.
+    let preset = rules::of(&matrix.rules);
+    let region = match (
+        matches.value_of("seed_region"),
+        matches.value_of("seed_center"),
+    ) {
+        (Some(s), _) => match region(s) {
+            Some(r) if r.fits((m, n)) => Some(r),
+            _ => {
+                die!("Invalid value for 'seed_region', expected X,Y,W,H within the world.");
+            }
+        },
+        (_, Some(s)) => match dimensions(s) {
+            Some((w, h)) if w <= n && h <= m => Some(Region::centred(h, w, (m, n))),
+            _ => {
+                die!("Invalid value for 'seed_center', expected WxH no bigger than the world.");
+            }
+        },
+        _ => preset.and_then(|p| p.middle).map(|part| {
+            let side = |s: usize| ((s as f64 * part).round() as usize).clamp(1, s);
+            Region::centred(side(m), side(n), (m, n))
+        }),
+    };
+    let area = region.map_or(m * n, |r| r.height * r.width);
+    let density = match (
+        number::<f64>(&matches, "density"),
+        number::<usize>(&matches, "seed_iter"),
+    ) {
+        (Some(p), _) if (0.0..=1.0).contains(&p) => p,
+        (Some(_), _) => {
+            die!("Invalid value for 'density', expected a probability between 0 and 1.");
+        }
+        (_, Some(cells)) => (cells as f64 / area as f64).min(1.0),
+        _ => preset.map_or(0.5, |p| p.density),
+    };
+    let mut distribution = number(&matches, "seed_dist").unwrap_or(Distribution::Uniform);
+    match &mut distribution {
+        Distribution::Perlin { scale, threshold } => {
+            *scale = number(&matches, "noise_scale").unwrap_or(*scale);
+            *threshold = number(&matches, "noise_threshold").unwrap_or(*threshold);
+        }
+        _ if matches.is_present("noise_scale") || matches.is_present("noise_threshold") => {
+            die!(
+                "Invalid use of 'noise_scale' or 'noise_threshold', which need --seed-dist perlin."
+            );
+        }
+        _ => {}
+    }
+    let count = number::<usize>(&matches, "seed_exact");
+    if count.is_some_and(|count| count > area) {
+        die!(format!(
+            "Invalid value for 'seed_exact', expected at most the {} cells there are to seed.",
+            area
+        ));
+    }
+    let soup = Soup {
+        density,
+        region,
+        distribution,
+        count,
+    };
+    let rng_seed = match matches.value_of("seed_phrase") {
+        Some(words) => soup::phrase(words),
+        None => number::<u64>(&matches, "rng_seed").unwrap_or_else(rand::random),
+    };
+    let sprinkles: Vec<(Pattern, usize)> = matches
+        .value_of("sprinkle")
+        .into_iter()
+        .flat_map(|spec| spec.split(','))
+        .map(|item| {
+            let parsed = item.split_once(':').and_then(|(name, copies)| {
+                Some((library::get(name.trim())?, copies.trim().parse().ok()?))
+            });
+            match parsed {
+                Some(sprinkle) => sprinkle,
+                None => {
+                    die!(format!(
+                        "Invalid value for 'sprinkle': {} isn't a built-in pattern's name, a colon and how many (see `iron-cat patterns`).",
+                        item
+                    ));
+                }
+            }
+        })
+        .collect();
+    // The first soup gets a stream of its own, split off the one the run
+    // uses, so that reseeding doesn't make the same soup over again.
+    // Seeding from a `StdRng` can't fail.
+    let mut first = StdRng::from_rng(StdRng::seed_from_u64(rng_seed)).unwrap();
+    let bytes = matches
+        .value_of("seed_bytes")
+        .map(|path| match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                die!(format!("Could not read {}.", path), e);
+            }
+        });
+    match (&picture, &pattern) {
+        (Some((pm, pn, cells)), _) => matrix.place(0, 0, *pm, *pn, cells),
+        (_, Some(file)) => {
+            let at = match matches.value_of("at").map(coordinates) {
+                Some(Some((x, y))) if x < matrix.n && y < matrix.m => (y, x),
+                Some(_) => {
+                    die!("Invalid value for 'at', expected X,Y within the world.");
+                }
+                None => (
+                    matrix.m.saturating_sub(file.pattern.height) / 2,
+                    matrix.n.saturating_sub(file.pattern.width) / 2,
+                ),
+            };
+            file.pattern.paste(&mut matrix, at);
+        }
+        // Placed patterns go into an empty world.
+        _ if !places.is_empty() => {}
+        _ if !sprinkles.is_empty() => soup::sprinkle(&mut matrix, region, &sprinkles, &mut first),
+        _ => match &bytes {
+            Some(bytes) => soup::lay(&mut matrix, region, bytes),
+            None => soup.seed(&mut matrix, &mut first),
+        },
+    };
+    for (place, file) in &places {
+        let (x, y) = place.at;
+        if x >= matrix.n || y >= matrix.m {
+            die!(format!(
+                "Invalid value for 'place': {} is outside the world.",
+                place.source
+            ));
+        }
+        file.pattern.overlay(&mut matrix, (y, x));
+    }
+
+    // A script starts from the world it was recorded in.
+    let script = matches
+        .value_of("play_script")
+        .map(|path| match Script::load(Path::new(path)) {
+            Ok(script) => script,
+            Err(e) => {
+                die!("Could not play the script.", e);
+            }
+        });
+    if let Some(script) = &script {
+        let rules = match script.rule.as_deref().map(str::parse::<Rulestring>) {
+            Some(Ok(rules)) => rules,
+            Some(Err(e)) => {
+                die!("Invalid Rulestring in the script.", e);
+            }
+            None => matrix.rules.clone(),
+        };
+        let world = &script.world;
+        matrix = Matrix::new(world.height, world.width, rules);
+        world.paste(&mut matrix, (0, 0));
+    }
+
+    // A checkpoint carries on from the world it was written from.
+    if let Some(checkpoint) = &resumed {
+        matrix = Matrix {
+            rules: matrix.rules.clone(),
+            ..checkpoint.matrix.clone()
+        };
+    }
+
+    // So does a recording, which brings its own rule along.
+    if let Some(tape) = &tape {
+        matrix = tape.matrix();
+    }
+
+    let anchor = number::<Anchor>(&matches, "anchor").unwrap_or(Anchor::Centre);
+    if let Some(size) = matches.value_of("grow_to") {
+        match dimensions(size) {
+            Some((m, n)) => matrix.grow(m, n, anchor),
+            None => {
+                die!("Invalid value for 'grow-to', expected ROWSxCOLUMNS.");
+            }
+        }
+    }
+
+    let mut rival = rival_rules.map(|rules| Matrix {
+        rules,
+        ..matrix.clone()
+    });
+    let twin = match matches.value_of("twin_perturb").map(coordinates) {
+        _ if !matches.is_present("twin_perturb") => None,
+        None => Some((matrix.m / 2, matrix.n / 2)),
+        Some(Some((x, y))) if x < matrix.n && y < matrix.m => Some((y, x)),
+        Some(_) => {
+            die!("Invalid value for 'twin-perturb', expected X,Y inside the world.");
+        }
+    };
+    if let Some((row, col)) = twin {
+        let mut twin = matrix.clone();
+        twin.flip(row, col);
+        rival = Some(twin);
+    }
+
+    let rng_seed = match (&resumed, &script) {
+        (Some(checkpoint), _) => checkpoint.seed,
+        (_, Some(script)) => script.seed,
+        _ => rng_seed,
+    };
+    // Everything written says how to make it again, which takes the seed
+    // unless it comes from a checkpoint, a script or a phrase.
+    let mut args: Vec<String> = std::env::args().collect();
+    let given = matches.is_present("rng_seed") || matches.is_present("seed_phrase");
+    if resumed.is_none() && script.is_none() && !given {
+        let at = args.len().min(1);
+        args.splice(at..at, ["--rng-seed".to_string(), rng_seed.to_string()]);
+    }
+    let provenance = Provenance::new(&matrix.rules, (matrix.m, matrix.n), rng_seed, &args);
+
+    if let Some(sub) = matches.subcommand_matches("render") {
+        render(sub, &theme, &mut matrix, scale, &provenance);
+        return;
+    }
+    if let Some(sub) = matches.subcommand_matches("export") {
+        export(sub, &mut matrix, &provenance);
+        return;
+    }
+    if let Some(sub) = matches.subcommand_matches("census") {
+        census(sub, &mut matrix);
+        return;
+    }
+    if let Some(runs) = number::<u64>(&matches, "ensemble") {
+        ensemble(&matches, &matrix, &soup, rng_seed, runs);
+        return;
+    }
+    if let Some(sub) = matches.subcommand_matches("sweep") {
+        sweep(sub, &matches, &matrix, &soup, rng_seed, &provenance);
+        return;
+    }
+    if let Some(sub) = matches.subcommand_matches("search") {
+        search(sub, &matches, &matrix.rules, &soup, rng_seed, &provenance);
+        return;
+    }
+
+    let generations = number::<u64>(&matches, "generations");
+
+    let mut recorders: Vec<Box<dyn Recorder>> = Vec::new();
+
+    let link = Link::new(
+        number(&matches, "render_budget").map(time::Duration::from_millis),
+        number(&matches, "max_bandwidth"),
+    );
+
+    if let Some(path) = matches.value_of("record") {
+        recorders.push(Box::new(ReplayRecorder::new(path, &provenance)));
+    }
+
+    if let Some(path) = matches.value_of("record_gif") {
+        let delay = number(&matches, "gif_delay").unwrap_or(100);
+        recorders.push(Box::new(GifRecorder::new(path, scale, delay, &provenance)));
+    }
+
+    if let Some(path) = matches.value_of("record_html") {
+        let player = match matches.value_of("html_player") {
+            Some("css") => html::Player::Css,
+            _ => html::Player::Canvas,
+        };
+        let delay = number(&matches, "html_delay").unwrap_or(100);
+        recorders.push(Box::new(HtmlRecorder::new(
+            path,
+            player,
+            scale,
+            delay,
+            &provenance,
+        )));
+    }
+
+    if let Some(path) = matches.value_of("export_ans") {
+        recorders.push(Box::new(AnsRecorder::new(path, &provenance)));
+    }
+
+    if let Some(path) = matches.value_of("record_cast") {
+        recorders.push(Box::new(CastRecorder::new(path, &provenance)));
+    }
+
+    if let Some(path) = matches.value_of("record_video") {
+        let fps = match number::<u32>(&matches, "video_fps").unwrap_or(10) {
+            0 => {
+                die!("Can't have a video at zero frames per second.");
+            }
+            x => x,
+        };
+
+        let size = matches.value_of("video_size").map(|s| match dimensions(s) {
+            Some(d) => d,
+            None => {
+                die!("Invalid value for 'video-size', expected WIDTHxHEIGHT.");
+            }
+        });
+
+        recorders.push(Box::new(VideoRecorder::new(
+            path,
+            scale,
+            fps,
+            size,
+            &provenance,
+        )));
+    }
+
+    // A stream on standard output replaces the animation entirely.
+    let mut headless = false;
+
+    if matches.is_present("stream") {
+        let out: Box<dyn Write> = match matches.value_of("stream_file") {
+            Some(path) => match std::fs::File::create(path) {
+                Ok(f) => Box::new(std::io::BufWriter::new(f)),
+                Err(e) => {
+                    die!("Could not create stream file.", e);
+                }
+            },
+            None => {
+                headless = true;
+                Box::new(std::io::stdout())
+            }
+        };
+
+        let cells = match matches.value_of("stream_cells") {
+            Some("rows") => ndjson::Cells::Rows,
+            _ => ndjson::Cells::Coordinates,
+        };
+
+        recorders.push(Box::new(NdjsonRecorder::new(out, cells, &provenance)));
+    }
+
+    // As do the numbers of every generation.
+    let stats_log = matches.value_of("stats_out").map(|path| {
+        let out: Box<dyn Write> = match path {
+            "-" if headless => {
+                die!("Only one of --stream and --stats-out can go to standard output.");
+            }
+            "-" => {
+                headless = true;
+                Box::new(std::io::stdout())
+            }
+            _ => match std::fs::File::create(path) {
+                Ok(f) => Box::new(std::io::BufWriter::new(f)),
+                Err(e) => {
+                    die!("Could not create statistics file.", e);
+                }
+            },
+        };
+        let block = match number::<usize>(&matches, "entropy_block").unwrap_or(2) {
+            k @ 1..=8 => k,
+            _ => {
+                die!("Invalid value for 'entropy_block', expected a side from 1 to 8.");
+            }
+        };
+        StatsLog::new(out, block, &provenance)
+    });
+    let cluster_log = matches.value_of("clusters_out").map(|path| {
+        let out: Box<dyn Write> = match std::fs::File::create(path) {
+            Ok(f) => Box::new(std::io::BufWriter::new(f)),
+            Err(e) => {
+                die!("Could not create clusters file.", e);
+            }
+        };
+        let every = match number::<u64>(&matches, "clusters_every").unwrap_or(100) {
+            0 => {
+                die!("Invalid value for 'clusters_every', expected at least 1.");
+            }
+            every => every,
+        };
+        ClusterLog::new(out, every, &provenance)
+    });
+    let event_log = matches.value_of("event_log").map(|path| {
+        let out: Box<dyn Write> = match std::fs::File::create(path) {
+            Ok(f) => Box::new(std::io::BufWriter::new(f)),
+            Err(e) => {
+                die!("Could not create event log.", e);
+            }
+        };
+        match EventLog::new(out, &provenance) {
+            Ok(log) => log,
+            Err(e) => {
+                die!("Could not write the event log.", e);
+            }
+        }
+    });
+
+    // So does the final generation, when nothing else would be drawn in
+    // a terminal that's left behind afterwards.
+    if matches.is_present("emit_final") && output == Output::Plain {
+        headless = true;
+    }
+
+    // Plain output is meant for other programs, so it gets neither escape
+    // codes nor a delay, and it can be thinned out with `--dump-every`.
+    let plain = output == Output::Plain || headless;
+    let every = match number::<u64>(&matches, "dump_every").unwrap_or(1) {
+        0 => {
+            die!("Can't dump every zeroth generation.");
+        }
+        x => x,
+    };
+
+    if let Err(e) = term::install_handler() {
+        die!("Could not install signal handler.", e);
+    }
+
+    // notcurses looks after the terminal itself, and draws from the matrix
+    // rather than from our frames.
+    #[cfg(feature = "notcurses")]
+    let notcurses = match output {
+        Output::Notcurses => match Notcurses::new() {
+            Some(nc) => Some(nc),
+            None => {
+                die!("Could not start notcurses.");
+            }
+        },
+        _ => None,
+    };
+    #[cfg(feature = "notcurses")]
+    let native = notcurses.is_some();
+    #[cfg(not(feature = "notcurses"))]
+    let native = false;
+
+    let config = match Config::load(matches.value_of("config").map(Path::new)) {
+        Ok(config) => config,
+        Err(e) => {
+            die!("Could not load config.", e);
+        }
+    };
+    let keys = match Keymap::new(&config.keys) {
+        Ok(keys) => keys,
+        Err(e) => {
+            die!("Could not set up the keys.", e);
+        }
+    };
+
+    let demo = matches
+        .value_of("demo")
+        .map(|path| match Demo::load(Path::new(path)) {
+            Ok(demo) => demo,
+            Err(e) => {
+                die!("Could not load the demo.", e);
+            }
+        });
+
+    let mut breaks = Vec::new();
+    for at in matches.values_of("break_at").into_iter().flatten() {
+        match at.parse::<u64>() {
+            Ok(generation) => breaks.push(Condition::generation(generation)),
+            Err(e) => {
+                die!("Invalid value for 'break_at'.", e);
+            }
+        }
+    }
+    for when in matches.values_of("break_when").into_iter().flatten() {
+        match when.parse::<Condition>() {
+            Ok(condition) => breaks.push(condition),
+            Err(e) => {
+                die!("Invalid breakpoint.", e);
+            }
+        }
+    }
+
+    if !plain && !native {
+        term::enter();
+    }
+
+    let settings = Settings {
+        generations,
+        duration: matches.value_of("duration").map(|s| match duration(s) {
+            Some(d) => d,
+            None => {
+                die!("Invalid value for 'duration', expected a time such as 30s, 500ms, 5m or 1h.");
+            }
+        }),
+        until_stable: matches.is_present("until_stable"),
+        every,
+        period: time::Duration::new(1, 0),
+        soup,
+        plain,
+        headless,
+        paced: !plain || legacy,
+        // Only text is cropped to fit; the pixel outputs scale with the
+        // terminal.
+        crop: !plain && output.is_text(),
+        fit,
+        clipboard: matches
+            .value_of("clipboard")
+            .unwrap_or("clipboard.rle")
+            .into(),
+        rewind: number(&matches, "rewind").unwrap_or(if !plain || legacy { 100 } else { 0 }),
+        anchor,
+        library: matches
+            .value_of("library")
+            .map(PathBuf::from)
+            .or_else(library::user_dir),
+    };
+
+    if settings.crop {
+        if let Some(size) = term::size().map(pane) {
+            view.fit(size, &matrix, cell_width);
+        }
+    }
+    #[cfg(feature = "notcurses")]
+    if let Some(nc) = notcurses.as_ref() {
+        view.fit(nc.size(), &matrix, cell_width);
+    }
+
+    let writer = matches.value_of("record_script").map(|path| {
+        let world = Pattern::copy(&matrix, (0, 0), (matrix.m - 1, matrix.n - 1));
+        match ScriptWriter::create(Path::new(path), rng_seed, &world, &matrix.rules) {
+            Ok(writer) => writer,
+            Err(e) => {
+                die!("Could not record the script.", e);
+            }
+        }
+    });
+
+    let heatmap = matches
+        .value_of("heatmap")
+        .map(|path| Heatmap::new(path, scale, &matrix));
+    let mut session = Session::new(theme, view, matrix, rival, recorders, link, settings);
+    session.paused = matches.is_present("paused");
+    session.keys = keys;
+    session.rng = StdRng::seed_from_u64(rng_seed);
+    session.script = writer;
+    session.demo = demo;
+    session.dashboard.shown = matches.is_present("dashboard");
+    if let Some(path) = matches.value_of("checkpoint_file") {
+        let every = number::<u64>(&matches, "checkpoint_every");
+        if every == Some(0) {
+            die!("Can't write a checkpoint every zeroth generation.");
+        }
+        // Without --checkpoint-every it's only written at the end.
+        session.checkpoint = Some((every.unwrap_or(u64::MAX), path.into()));
+    }
+    if let Some(checkpoint) = &resumed {
+        session.elapsed = checkpoint.elapsed;
+        if let Some((origin, zoom)) = project::view(&checkpoint.settings) {
+            session.view.origin = origin;
+            session.view.zoom = zoom;
+        }
+    }
+    session.project = project::settings(&matches);
+    session.provenance = provenance;
+    session.noise = match number::<f64>(&matches, "noise") {
+        Some(p) if !(0.0..=1.0).contains(&p) => {
+            die!("Invalid value for 'noise', expected a probability between 0 and 1.");
+        }
+        p => p.unwrap_or(0.0),
+    };
+    session.noise_births = number(&matches, "noise_births").unwrap_or(0);
+    session.feed = matches.value_of("feed").map(|source| {
+        (
+            Feed::open(source),
+            number(&matches, "threshold").unwrap_or(128),
+        )
+    });
+    session.stats_log = stats_log;
+    session.cluster_log = cluster_log;
+    session.event_log = event_log;
+    session.heatmap = heatmap;
+    session.plot = matches.value_of("plot").map(Plot::new);
+    session.twin = twin;
+    session.reseed_every = match number::<u64>(&matches, "reseed_every") {
+        Some(0) => {
+            die!("Invalid value for 'reseed_every', expected at least 1.");
+        }
+        every => every.map(|every| (every, matches.is_present("reseed_keep"))),
+    };
+    session.tape = tape;
+    if let Some(script) = script {
+        session.replay = script.inputs;
+    }
+    // Only pattern files can change, not built-in patterns, apgcodes or
+    // URLs, and a recording being played has nothing to reload into.
+    if let Some(spec) = matches
+        .value_of("pattern")
+        .filter(|_| session.tape.is_none())
+    {
+        let (source, transforms) = pattern::suffixes(spec);
+        if Path::new(source).is_file() {
+            let at = matches
+                .value_of("at")
+                .and_then(coordinates)
+                .map(|(x, y)| (y, x));
+            session.pattern_file = Some((Watch::new(Path::new(source)), transforms, at));
+        }
+    }
+    session.rule_file = matches
+        .value_of("rule_file")
+        .map(|path| Watch::new(Path::new(path)));
+    for condition in breaks {
+        session.break_when(condition);
+    }
+    #[cfg(feature = "notcurses")]
+    {
+        session.notcurses = notcurses;
+    }
+
+    if let Err(e) = session.run() {
+        die!("The run ended early.", e);
+    }
+    if let Some(format) = matches.value_of("emit_final") {
+        emit_final(format, &session.matrix, &session.provenance);
+    }
+    if let Some(format) = matches.value_of("summary") {
+        eprintln!("{}", session.summary(format == "json"));
     }
 }
@@ -0,0 +1,64 @@
+//! Fetching patterns from the web, so that a LifeWiki link can be run
+//! without downloading it first.
+//!
+//! This needs the `net` feature. Without it a URL is refused, saying how to
+//! build iron-cat so that it isn't.
+
+use std::{error, fmt};
+
+/// How long to wait for a pattern before giving up.
+#[cfg(feature = "net")]
+const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(20);
+
+/// Why a pattern couldn't be fetched.
+#[derive(Debug)]
+pub enum NetError {
+    /// Built without the `net` feature.
+    #[cfg(not(feature = "net"))]
+    Disabled,
+    #[cfg(feature = "net")]
+    Fetch(Box<ureq::Error>),
+    #[cfg(feature = "net")]
+    Read(std::io::Error),
+}
+
+impl error::Error for NetError {}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(not(feature = "net"))]
+            NetError::Disabled => write!(
+                f,
+                "Could not fetch the pattern: iron-cat was built without the net feature \
+                 (cargo build --features net)"
+            ),
+            #[cfg(feature = "net")]
+            NetError::Fetch(e) => write!(f, "Could not fetch the pattern: {}", e),
+            #[cfg(feature = "net")]
+            NetError::Read(e) => write!(f, "Could not read the pattern fetched: {}", e),
+        }
+    }
+}
+
+/// Whether some text is a web address rather than a file.
+pub fn is_url(s: &str) -> bool {
+    s.starts_with("http://") || s.starts_with("https://")
+}
+
+/// Fetches the text at a URL.
+#[cfg(feature = "net")]
+pub fn fetch(url: &str) -> Result<String, NetError> {
+    ureq::get(url)
+        .timeout(TIMEOUT)
+        .call()
+        .map_err(|e| NetError::Fetch(Box::new(e)))?
+        .into_string()
+        .map_err(NetError::Read)
+}
+
+/// Refuses to fetch anything, there being no way to.
+#[cfg(not(feature = "net"))]
+pub fn fetch(_url: &str) -> Result<String, NetError> {
+    Err(NetError::Disabled)
+}
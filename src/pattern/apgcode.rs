@@ -0,0 +1,321 @@
+//! apgcodes, the names Catagolue gives objects, such as `xs4_33` for the
+//! block, `xp2_7` for the blinker and `xq4_153` for the glider.
+//!
+//! The part before the `_` says what the object is: `xs` and its
+//! population for a still life, `xp` and its period for an oscillator and
+//! `xq` and its period for a spaceship. After it the cells are written in
+//! strips five rows tall, one character to a column from `0` to `v`, the
+//! top cell the lowest bit, with `z` between strips. Runs of empty columns
+//! are shortened to `w` for two, `x` for three and `y` and a character from
+//! `0` to `z` for four to thirty-nine. Of all the ways an object could be
+//! written, turned around or in any of its phases, its apgcode is the
+//! shortest, and the first in ASCII order among those.
+//!
+//! Objects are identified by cutting a settled world into clusters of
+//! cells and running each cluster on its own until it comes back.
+
+use std::collections::HashMap;
+use std::{error, fmt};
+
+use super::Pattern;
+use crate::{Matrix, Rulestring};
+
+/// The characters a column of a strip is written with.
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// The most generations a cluster is run for to see it come back.
+const LONGEST: u64 = 30;
+
+/// The most rows or columns a cluster may span and still be identified.
+const WIDEST: usize = 40;
+
+/// Why an apgcode couldn't be read.
+#[derive(Debug)]
+pub enum ApgError {
+    Prefix(String),
+    Body(char),
+}
+
+impl error::Error for ApgError {}
+
+impl fmt::Display for ApgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApgError::Prefix(s) => write!(
+                f,
+                "Invalid apgcode {:?}: expected xs, xp or xq, a number and _",
+                s
+            ),
+            ApgError::Body(c) => write!(f, "Invalid apgcode: unexpected {:?}", c),
+        }
+    }
+}
+
+/// Whether some text is meant as an apgcode rather than anything else.
+pub fn is_apgcode(s: &str) -> bool {
+    s.split_once('_').is_some_and(|(prefix, _)| {
+        ["xs", "xp", "xq"].iter().any(|x| {
+            prefix
+                .strip_prefix(x)
+                .is_some_and(|n| n.parse::<u64>().is_ok())
+        })
+    })
+}
+
+/// Reads the cells of an object from its apgcode.
+pub fn decode(code: &str) -> Result<Pattern, ApgError> {
+    if !is_apgcode(code) {
+        return Err(ApgError::Prefix(code.to_string()));
+    }
+    let (_, body) = code.split_once('_').unwrap();
+
+    let mut cells = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            'z' => {
+                x = 0;
+                y += 5;
+            }
+            'w' => x += 2,
+            'x' => x += 3,
+            'y' => {
+                let n = chars.next().ok_or(ApgError::Body(c))?;
+                x += 4 + digit(n).ok_or(ApgError::Body(n))?;
+            }
+            c => {
+                let bits = digit(c).filter(|&d| d < 32).ok_or(ApgError::Body(c))?;
+                cells.extend((0..5).filter(|b| bits & (1 << b) != 0).map(|b| (x, y + b)));
+                x += 1;
+            }
+        }
+    }
+
+    Ok(Pattern::gather(&cells))
+}
+
+fn digit(c: char) -> Option<i64> {
+    DIGITS
+        .iter()
+        .position(|&d| d as char == c)
+        .map(|d| d as i64)
+}
+
+/// Writes a pattern's cells as they are, without a prefix and without
+/// trying it any other way round.
+fn wechsler(pattern: &Pattern) -> String {
+    let alive =
+        |r: usize, c: usize| r < pattern.height && pattern.cells[r * pattern.width + c] != 0;
+
+    let strips: Vec<String> = (0..pattern.height.div_ceil(5))
+        .map(|strip| {
+            let columns: Vec<usize> = (0..pattern.width)
+                .map(|c| {
+                    (0..5)
+                        .filter(|&b| alive(strip * 5 + b, c))
+                        .map(|b| 1 << b)
+                        .sum()
+                })
+                .collect();
+            let end = columns.iter().rposition(|&v| v != 0).map_or(0, |i| i + 1);
+
+            let mut out = String::new();
+            let mut zeros = 0;
+            for &v in &columns[..end] {
+                if v == 0 {
+                    zeros += 1;
+                    continue;
+                }
+                blanks(&mut out, zeros);
+                zeros = 0;
+                out.push(DIGITS[v] as char);
+            }
+            out
+        })
+        .collect();
+
+    strips.join("z")
+}
+
+/// Writes a run of empty columns as shortly as it can be.
+fn blanks(out: &mut String, mut zeros: usize) {
+    while zeros >= 4 {
+        let run = zeros.min(39);
+        out.push('y');
+        out.push(DIGITS[run - 4] as char);
+        zeros -= run;
+    }
+    out.push_str(["", "0", "w", "x"][zeros]);
+}
+
+/// The apgcode of an object with the given prefix, the best of all the
+/// ways round of all its phases.
+pub fn encode(prefix: &str, phases: &[Pattern]) -> String {
+    let best = phases
+        .iter()
+        .flat_map(|phase| {
+            let mut turned = vec![phase.clone()];
+            for i in 0..7 {
+                let next = if i == 3 {
+                    turned[0].flip()
+                } else {
+                    turned[i].rotate()
+                };
+                turned.push(next);
+            }
+            turned
+        })
+        .map(|p| wechsler(&p))
+        .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+        .unwrap_or_default();
+
+    format!("{}_{}", prefix, best)
+}
+
+/// What an object is, found by running it on its own until it comes back,
+/// or `None` if it doesn't within a few generations or is or grows too big.
+pub fn identify(pattern: &Pattern, rules: &Rulestring) -> Option<String> {
+    if pattern.height > WIDEST || pattern.width > WIDEST {
+        return None;
+    }
+    // Room for it to move by up to a cell a generation without wrapping.
+    let margin = LONGEST as usize + 2;
+    let mut matrix = Matrix::new(
+        pattern.height + 2 * margin,
+        pattern.width + 2 * margin,
+        rules.clone(),
+    );
+    pattern.paste(&mut matrix, (margin, margin));
+
+    let (start, at) = locate(&matrix)?;
+    let mut phases = vec![start.clone()];
+    while matrix.generation < LONGEST {
+        matrix.pulse();
+        let (now, to) = locate(&matrix)?;
+        if now.height > WIDEST || now.width > WIDEST {
+            return None;
+        }
+        if now == start {
+            let population = start.cells.iter().filter(|&&c| c != 0).count();
+            return Some(match (matrix.generation, to == at) {
+                (1, true) => encode(&format!("xs{}", population), &phases),
+                (p, true) => encode(&format!("xp{}", p), &phases),
+                (p, false) => encode(&format!("xq{}", p), &phases),
+            });
+        }
+        phases.push(now);
+    }
+
+    None
+}
+
+/// The live cells of a world and where their top left corner is, if
+/// there are any.
+fn locate(matrix: &Matrix) -> Option<(Pattern, (usize, usize))> {
+    let first = matrix.rows.iter().position(|&c| c != 0)?;
+    let left =
+        (0..matrix.n).find(|&c| (0..matrix.m).any(|r| matrix.rows[r * matrix.n + c] != 0))?;
+
+    Some((Pattern::live(matrix), (first / matrix.n, left)))
+}
+
+/// Every object in a world and how many of each there are, most first.
+/// Cells within two of each other count as one object. Those that can't
+/// be identified are counted under `None`.
+pub fn census(matrix: &Matrix) -> Vec<(Option<String>, usize)> {
+    let mut seen = vec![false; matrix.rows.len()];
+    let mut known: HashMap<Pattern, Option<String>> = HashMap::new();
+    let mut counts: HashMap<Option<String>, usize> = HashMap::new();
+
+    for start in 0..matrix.rows.len() {
+        if matrix.rows[start] == 0 || seen[start] {
+            continue;
+        }
+
+        // Gathers the cluster, not reaching around the edges of the world.
+        let mut cluster = Vec::new();
+        let mut stack = vec![start];
+        seen[start] = true;
+        while let Some(i) = stack.pop() {
+            let (r, c) = (i / matrix.n, i % matrix.n);
+            cluster.push((c as i64, r as i64));
+            for nr in r.saturating_sub(2)..(r + 3).min(matrix.m) {
+                for nc in c.saturating_sub(2)..(c + 3).min(matrix.n) {
+                    let j = nr * matrix.n + nc;
+                    if matrix.rows[j] != 0 && !seen[j] {
+                        seen[j] = true;
+                        stack.push(j);
+                    }
+                }
+            }
+        }
+
+        let pattern = Pattern::gather(&cluster);
+        let code = if pattern.height > WIDEST || pattern.width > WIDEST {
+            None
+        } else {
+            known
+                .entry(pattern)
+                .or_insert_with_key(|p| identify(p, &matrix.rules))
+                .clone()
+        };
+        *counts.entry(code).or_insert(0) += 1;
+    }
+
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes() {
+        let block = decode("xs4_33").unwrap();
+        assert_eq!((block.height, block.width), (2, 2));
+        assert_eq!(block.cells, [1, 1, 1, 1]);
+
+        let blinker = decode("xp2_7").unwrap();
+        assert_eq!((blinker.height, blinker.width), (3, 1));
+
+        let glider = decode("xq4_153").unwrap();
+        assert_eq!((glider.height, glider.width), (3, 3));
+        assert_eq!(glider.cells, [1, 1, 1, 0, 0, 1, 0, 1, 0]);
+    }
+
+    #[test]
+    fn encodes() {
+        for code in ["xs4_33", "xp2_7", "xq4_153"] {
+            let (prefix, _) = code.split_once('_').unwrap();
+            let pattern = decode(code).unwrap();
+            assert_eq!(encode(prefix, &[pattern.rotate()]), code);
+        }
+    }
+
+    #[test]
+    fn identifies() {
+        let rules: Rulestring = "B3/S23".parse().unwrap();
+        for code in ["xs4_33", "xp2_7", "xq4_153"] {
+            let pattern = decode(code).unwrap().flip();
+            assert_eq!(identify(&pattern, &rules).as_deref(), Some(code));
+        }
+    }
+
+    #[test]
+    fn long_gaps() {
+        // Four to thirty-nine empty columns are written as `y` and a count.
+        let pattern = decode("xs2_1y41").unwrap();
+        assert_eq!(pattern.width, 10);
+        assert_eq!(wechsler(&pattern), "1y41");
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(decode("block"), Err(ApgError::Prefix(_))));
+        assert!(matches!(decode("xs4_3!"), Err(ApgError::Body('!'))));
+        assert!(matches!(decode("xs4_3y"), Err(ApgError::Body('y'))));
+    }
+}
@@ -0,0 +1,98 @@
+//! Text as a pattern, in a small bitmap font, for `--banner HELLO`: watching
+//! a message get eaten away, or survive, makes a good demo.
+//!
+//! Every character is five cells wide and seven high, with a column between
+//! characters and a row between lines. Letters are all capitals, and
+//! anything the font doesn't have is refused rather than left out.
+
+use super::Pattern;
+
+const WIDTH: usize = 5;
+const HEIGHT: usize = 7;
+
+/// Every character, as its rows from the top with `/` between them.
+const FONT: &[(char, &str)] = &[
+    (' ', "...../...../...../...../...../...../....."),
+    ('A', ".OOO./O...O/O...O/OOOOO/O...O/O...O/O...O"),
+    ('B', "OOOO./O...O/O...O/OOOO./O...O/O...O/OOOO."),
+    ('C', ".OOO./O...O/O..../O..../O..../O...O/.OOO."),
+    ('D', "OOOO./O...O/O...O/O...O/O...O/O...O/OOOO."),
+    ('E', "OOOOO/O..../O..../OOOO./O..../O..../OOOOO"),
+    ('F', "OOOOO/O..../O..../OOOO./O..../O..../O...."),
+    ('G', ".OOO./O...O/O..../O.OOO/O...O/O...O/.OOOO"),
+    ('H', "O...O/O...O/O...O/OOOOO/O...O/O...O/O...O"),
+    ('I', ".OOO./..O../..O../..O../..O../..O../.OOO."),
+    ('J', "..OOO/...O./...O./...O./...O./O..O./.OO.."),
+    ('K', "O...O/O..O./O.O../OO.../O.O../O..O./O...O"),
+    ('L', "O..../O..../O..../O..../O..../O..../OOOOO"),
+    ('M', "O...O/OO.OO/O.O.O/O.O.O/O...O/O...O/O...O"),
+    ('N', "O...O/O...O/OO..O/O.O.O/O..OO/O...O/O...O"),
+    ('O', ".OOO./O...O/O...O/O...O/O...O/O...O/.OOO."),
+    ('P', "OOOO./O...O/O...O/OOOO./O..../O..../O...."),
+    ('Q', ".OOO./O...O/O...O/O...O/O.O.O/O..O./.OO.O"),
+    ('R', "OOOO./O...O/O...O/OOOO./O.O../O..O./O...O"),
+    ('S', ".OOOO/O..../O..../.OOO./....O/....O/OOOO."),
+    ('T', "OOOOO/..O../..O../..O../..O../..O../..O.."),
+    ('U', "O...O/O...O/O...O/O...O/O...O/O...O/.OOO."),
+    ('V', "O...O/O...O/O...O/O...O/O...O/.O.O./..O.."),
+    ('W', "O...O/O...O/O...O/O.O.O/O.O.O/O.O.O/.O.O."),
+    ('X', "O...O/O...O/.O.O./..O../.O.O./O...O/O...O"),
+    ('Y', "O...O/O...O/.O.O./..O../..O../..O../..O.."),
+    ('Z', "OOOOO/....O/...O./..O../.O.../O..../OOOOO"),
+    ('0', ".OOO./O...O/O..OO/O.O.O/OO..O/O...O/.OOO."),
+    ('1', "..O../.OO../..O../..O../..O../..O../.OOO."),
+    ('2', ".OOO./O...O/....O/...O./..O../.O.../OOOOO"),
+    ('3', "OOOOO/...O./..O../...O./....O/O...O/.OOO."),
+    ('4', "...O./..OO./.O.O./O..O./OOOOO/...O./...O."),
+    ('5', "OOOOO/O..../OOOO./....O/....O/O...O/.OOO."),
+    ('6', "..OO./.O.../O..../OOOO./O...O/O...O/.OOO."),
+    ('7', "OOOOO/....O/...O./..O../.O.../.O.../.O..."),
+    ('8', ".OOO./O...O/O...O/.OOO./O...O/O...O/.OOO."),
+    ('9', ".OOO./O...O/O...O/.OOOO/....O/...O./.OO.."),
+    ('.', "...../...../...../...../...../.OO../.OO.."),
+    (',', "...../...../...../...../.OO../..O../.O..."),
+    ('!', "..O../..O../..O../..O../..O../...../..O.."),
+    ('?', ".OOO./O...O/....O/...O./..O../...../..O.."),
+    ('-', "...../...../...../OOOOO/...../...../....."),
+    (':', "...../.OO../.OO../...../.OO../.OO../....."),
+    ('\'', "..O../..O../.O.../...../...../...../....."),
+    ('/', "...../....O/...O./..O../.O.../O..../....."),
+];
+
+/// Lays the text out, a line of characters for every line of it. Returns
+/// the first character the font doesn't have, if there is one.
+pub fn banner(text: &str) -> Result<Pattern, char> {
+    let lines: Vec<Vec<&str>> = text
+        .lines()
+        .map(|line| {
+            line.chars()
+                .map(|c| {
+                    FONT.iter()
+                        .find(|(f, _)| *f == c.to_ascii_uppercase())
+                        .map(|(_, glyph)| *glyph)
+                        .ok_or(c)
+                })
+                .collect()
+        })
+        .collect::<Result<_, _>>()?;
+
+    let longest = lines.iter().map(Vec::len).max().unwrap_or(0);
+    let width = (longest * (WIDTH + 1)).saturating_sub(1).max(1);
+    let height = (lines.len() * (HEIGHT + 1)).saturating_sub(1).max(1);
+    let mut pattern = Pattern::empty(height, width);
+
+    for (l, line) in lines.iter().enumerate() {
+        for (g, glyph) in line.iter().enumerate() {
+            for (r, row) in glyph.split('/').enumerate() {
+                for (c, cell) in row.chars().enumerate() {
+                    if cell == 'O' {
+                        let (y, x) = (l * (HEIGHT + 1) + r, g * (WIDTH + 1) + c);
+                        pattern.cells[y * width + x] = 1;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(pattern)
+}
@@ -0,0 +1,86 @@
+//! Plaintext patterns, the `.cells` files LifeWiki keeps, and the easiest
+//! format to write a pattern in by hand.
+//!
+//! ```text
+//! !Name: Glider
+//! .O.
+//! ..O
+//! OOO
+//! ```
+//!
+//! Lines starting with `!` are comments, and every other line is a row of
+//! `.` for dead cells and `O` for live ones. Rows can stop at their last
+//! live cell, and an empty line is a row with none.
+
+use std::{error, fmt};
+
+use super::{Pattern, PatternFile};
+
+/// Why a plaintext pattern couldn't be read.
+#[derive(Debug)]
+pub struct CellsError(usize, char);
+
+impl error::Error for CellsError {}
+
+impl fmt::Display for CellsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid cells on line {}: unexpected {:?}",
+            self.0, self.1
+        )
+    }
+}
+
+/// Reads a plaintext pattern. The format has no room for a rule.
+pub fn read(text: &str) -> Result<PatternFile, CellsError> {
+    let comments = text
+        .lines()
+        .filter_map(|l| l.strip_prefix('!'))
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect();
+    let rows: Vec<(usize, &str)> = text
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim_end()))
+        .filter(|(_, l)| !l.starts_with('!'))
+        .collect();
+    let width = rows
+        .iter()
+        .map(|(_, l)| l.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut pattern = Pattern::empty(rows.len(), width);
+
+    for (r, &(n, line)) in rows.iter().enumerate() {
+        for (c, ch) in line.chars().enumerate() {
+            match ch {
+                'O' | '*' => pattern.cells[r * width + c] = 1,
+                '.' => {}
+                ch => return Err(CellsError(n, ch)),
+            }
+        }
+    }
+
+    Ok(PatternFile {
+        pattern,
+        rule: None,
+        comments,
+    })
+}
+
+/// Writes a pattern as plaintext, leaving off the dead cells that end each
+/// row.
+pub fn write(pattern: &Pattern) -> String {
+    let mut out = String::new();
+
+    for row in pattern.cells.chunks(pattern.width.max(1)) {
+        let end = row.iter().rposition(|&c| c != 0).map_or(0, |i| i + 1);
+        out.extend(row[..end].iter().map(|&c| if c != 0 { 'O' } else { '.' }));
+        out.push('\n');
+    }
+
+    out
+}
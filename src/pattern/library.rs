@@ -0,0 +1,127 @@
+//! Patterns everyone knows, built in so they are always to hand.
+//!
+//! They are written as plaintext, rows of `.` for dead cells and `O` for
+//! live ones, like hand-drawn patterns usually are. `iron-cat patterns`
+//! lists them, and `--pattern name:glider` starts from one.
+//!
+//! More can be kept as pattern files in a directory of the user's own,
+//! which the library browser lists after these.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{cells, Pattern};
+use crate::config;
+
+/// The extensions of the pattern files in the user's directory.
+const EXTENSIONS: &[&str] = &["rle", "cells", "lif", "life", "mc"];
+
+/// Every built-in pattern by name.
+pub const PATTERNS: &[(&str, &str)] = &[
+    ("glider", ".O.\n..O\nOOO"),
+    ("LWSS", ".O..O\nO....\nO...O\nOOOO."),
+    ("MWSS", "...O..\n.O...O\nO.....\nO....O\nOOOOO."),
+    ("HWSS", "...OO..\n.O....O\nO......\nO.....O\nOOOOOO."),
+    ("R-pentomino", ".OO\nOO.\n.O."),
+    ("acorn", ".O.....\n...O...\nOO..OOO"),
+    (
+        "Gosper-gun",
+        "........................O...........\n\
+         ......................O.O...........\n\
+         ............OO......OO............OO\n\
+         ...........O...O....OO............OO\n\
+         OO........O.....O...OO..............\n\
+         OO........O...O.OO....O.O...........\n\
+         ..........O.....O.......O...........\n\
+         ...........O...O....................\n\
+         ............OO......................",
+    ),
+    (
+        "pulsar",
+        "..OOO...OOO..\n\
+         .............\n\
+         O....O.O....O\n\
+         O....O.O....O\n\
+         O....O.O....O\n\
+         ..OOO...OOO..\n\
+         .............\n\
+         ..OOO...OOO..\n\
+         O....O.O....O\n\
+         O....O.O....O\n\
+         O....O.O....O\n\
+         .............\n\
+         ..OOO...OOO..",
+    ),
+    ("pentadecathlon", "..O....O..\nOO.OOOO.OO\n..O....O.."),
+    ("block", "OO\nOO"),
+    ("beehive", ".OO.\nO..O\n.OO."),
+    ("loaf", ".OO.\nO..O\n.O.O\n..O."),
+    ("boat", "OO.\nO.O\n.O."),
+    ("blinker", "OOO"),
+    ("toad", ".OOO\nOOO."),
+    ("beacon", "OO..\nO...\n...O\n..OO"),
+];
+
+/// Looks a built-in pattern up by name, ignoring case.
+pub fn get(name: &str) -> Option<Pattern> {
+    PATTERNS
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, rows)| parse(rows))
+}
+
+/// Every built-in pattern, in order.
+pub fn all() -> Vec<(String, Pattern)> {
+    PATTERNS
+        .iter()
+        .map(|(name, rows)| (name.to_string(), parse(rows)))
+        .collect()
+}
+
+/// Where the user's own patterns live by default: `patterns` in the config
+/// directory.
+pub fn user_dir() -> Option<PathBuf> {
+    config::dir().map(|dir| dir.join("patterns"))
+}
+
+/// Reads every pattern file in `dir`, named after the file and sorted by
+/// name. Files that can't be read are kept, along with why, so they can
+/// still be listed. A directory that doesn't exist holds nothing.
+pub fn load(dir: &Path) -> Vec<(String, Result<Pattern, String>)> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut patterns: Vec<_> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|e| EXTENSIONS.iter().any(|x| e.eq_ignore_ascii_case(x)))
+        })
+        .map(|path| {
+            let name = path
+                .file_stem()
+                .map_or(String::new(), |s| s.to_string_lossy().into_owned());
+            let pattern = fs::read_to_string(&path)
+                .map_err(|e| format!("Could not read {}: {}", path.display(), e))
+                .and_then(|text| {
+                    super::read(&text)
+                        .map(|file| file.pattern)
+                        .map_err(|e| e.to_string())
+                });
+            (name, pattern)
+        })
+        .collect();
+    patterns.sort_by(|a, b| a.0.cmp(&b.0));
+
+    patterns
+}
+
+/// Reads a built-in pattern, which is known to be good.
+fn parse(rows: &str) -> Pattern {
+    cells::read(rows)
+        .expect("built-in patterns are plaintext")
+        .pattern
+}
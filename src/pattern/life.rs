@@ -0,0 +1,178 @@
+//! Life 1.05 and Life 1.06 patterns, which many older archives still use.
+//!
+//! Life 1.05 draws the pattern in blocks of `.` and `*`, each placed by a
+//! `#P` line giving its top left corner relative to the middle:
+//!
+//! ```text
+//! #Life 1.05
+//! #D A glider
+//! #N
+//! #P -1 -1
+//! .*.
+//! ..*
+//! ***
+//! ```
+//!
+//! `#N` means the usual rule, and `#R 23/3` any other, written survival
+//! first. Life 1.06 lists the coordinates of every live cell instead, one
+//! `x y` to a line, and says nothing about rules:
+//!
+//! ```text
+//! #Life 1.06
+//! 0 -1
+//! 1 0
+//! -1 1
+//! 0 1
+//! 1 1
+//! ```
+
+use std::fmt::Write as _;
+use std::{error, fmt};
+
+use super::{Pattern, PatternFile};
+use crate::Rulestring;
+
+/// Why a Life 1.05 or 1.06 file couldn't be read.
+#[derive(Debug)]
+pub enum LifeError {
+    Rule(String),
+    Line(usize, String),
+    NoBlock(usize),
+}
+
+impl error::Error for LifeError {}
+
+impl fmt::Display for LifeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LifeError::Rule(s) => write!(f, "Invalid rule {:?} in Life file", s),
+            LifeError::Line(n, s) => write!(f, "Invalid line {} in Life file: {}", n, s),
+            LifeError::NoBlock(n) => {
+                write!(f, "Invalid Life 1.05 file: line {} comes before any #P", n)
+            }
+        }
+    }
+}
+
+/// Reads a Life 1.05 file.
+pub fn read_105(text: &str) -> Result<PatternFile, LifeError> {
+    let mut cells = Vec::new();
+    let mut rule = None;
+    let mut comments = Vec::new();
+    let mut block: Option<(i64, i64)> = None;
+    let mut row = 0;
+
+    for (n, line) in text.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+        let bad = || LifeError::Line(n, line.to_string());
+        if let Some(at) = line.strip_prefix("#P") {
+            let mut parts = at.split_whitespace().map(str::parse::<i64>);
+            match (parts.next(), parts.next()) {
+                (Some(Ok(x)), Some(Ok(y))) => block = Some((x, y)),
+                _ => return Err(bad()),
+            }
+            row = 0;
+        } else if let Some(comment) = line.strip_prefix("#D") {
+            comments.push(comment.trim().to_string());
+        } else if line == "#N" {
+            rule = Some("B3/S23".to_string());
+        } else if let Some(r) = line.strip_prefix("#R") {
+            let (s, b) = r
+                .trim()
+                .split_once('/')
+                .ok_or_else(|| LifeError::Rule(r.trim().to_string()))?;
+            rule = Some(format!("B{}/S{}", b, s));
+        } else if line.is_empty() || line.starts_with('#') {
+            continue;
+        } else {
+            let (x, y) = block.ok_or(LifeError::NoBlock(n))?;
+            for (col, c) in line.chars().enumerate() {
+                match c {
+                    '*' => cells.push((x + col as i64, y + row)),
+                    '.' => {}
+                    _ => return Err(bad()),
+                }
+            }
+            row += 1;
+        }
+    }
+
+    Ok(PatternFile {
+        pattern: Pattern::gather(&cells),
+        rule,
+        comments,
+    })
+}
+
+/// Reads a Life 1.06 file.
+pub fn read_106(text: &str) -> Result<PatternFile, LifeError> {
+    let mut cells = Vec::new();
+    let mut comments = Vec::new();
+
+    for (n, line) in text.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+        if line.starts_with("#Life") {
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            // Comments are usually `#D`, as in Life 1.05.
+            let comment = comment.strip_prefix('D').unwrap_or(comment);
+            comments.push(comment.trim().to_string());
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+        let mut parts = line.split_whitespace().map(str::parse::<i64>);
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(x)), Some(Ok(y)), None) => cells.push((x, y)),
+            _ => return Err(LifeError::Line(n, line.to_string())),
+        }
+    }
+
+    Ok(PatternFile {
+        pattern: Pattern::gather(&cells),
+        rule: None,
+        comments,
+    })
+}
+
+/// Writes a pattern as Life 1.05, in a single block around the middle.
+pub fn write_105(pattern: &Pattern, rules: &Rulestring) -> String {
+    let rules = rules.to_string();
+    let (b, s) = rules.split_once('/').unwrap_or(("B", "S"));
+    let mut out = format!("#Life 1.05\n#R {}/{}\n", &s[1..], &b[1..]);
+    writeln!(
+        out,
+        "#P {} {}",
+        -(pattern.width as i64 / 2),
+        -(pattern.height as i64 / 2)
+    )
+    .unwrap();
+
+    for row in pattern.cells.chunks(pattern.width.max(1)) {
+        let end = row.iter().rposition(|&c| c != 0).map_or(0, |i| i + 1);
+        let line: String = row[..end]
+            .iter()
+            .map(|&c| if c != 0 { '*' } else { '.' })
+            .collect();
+        // An empty line would end the block, so empty rows get a dot.
+        out.push_str(if line.is_empty() { "." } else { &line });
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Writes a pattern as Life 1.06, around the middle as in Life 1.05.
+pub fn write_106(pattern: &Pattern) -> String {
+    let mut out = String::from("#Life 1.06\n");
+    let (dx, dy) = (pattern.width as i64 / 2, pattern.height as i64 / 2);
+
+    for (i, &c) in pattern.cells.iter().enumerate() {
+        if c != 0 {
+            let (x, y) = ((i % pattern.width) as i64, (i / pattern.width) as i64);
+            writeln!(out, "{} {}", x - dx, y - dy).unwrap();
+        }
+    }
+
+    out
+}
@@ -0,0 +1,434 @@
+//! Golly's Macrocell format, which keeps a pattern as a quadtree with every
+//! repeated square written once, so that enormous patterns stay small.
+//!
+//! ```text
+//! [M2] (golly 4.2)
+//! #R B3/S23
+//! .*$..*$***$
+//! 4 1 0 0 0
+//! ```
+//!
+//! Every line after the header is a node, numbered from one. A node of
+//! level 3 is an 8 by 8 square drawn like RLE without the counts, `.` for
+//! dead, `*` for alive and `$` at the end of each row. A node of level `k`
+//! above that is `k` and the numbers of the four nodes of level `k - 1`
+//! making up its top left, top right, bottom left and bottom right, zero
+//! standing for a square with nothing in it. Rules with more states than
+//! two build from level 1 nodes of four cell states instead. The last node
+//! is the whole pattern.
+//!
+//! There's no HashLife here to run these patterns at their full size, so
+//! they are drawn out cell by cell, and those that would take too much room
+//! that way are refused.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::{error, fmt};
+
+use super::{Pattern, PatternFile};
+use crate::Rulestring;
+
+/// The most cells a pattern may have room for once drawn out.
+const MOST: usize = 1 << 24;
+
+/// Why a Macrocell file couldn't be read.
+#[derive(Debug)]
+pub enum MacrocellError {
+    NoHeader,
+    Line(usize, String),
+    Empty,
+    TooBig,
+}
+
+impl error::Error for MacrocellError {}
+
+impl fmt::Display for MacrocellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MacrocellError::NoHeader => write!(f, "Invalid Macrocell: it doesn't start with [M2]"),
+            MacrocellError::Line(n, s) => write!(f, "Invalid line {} in Macrocell: {}", n, s),
+            MacrocellError::Empty => write!(f, "Invalid Macrocell: it has no nodes"),
+            MacrocellError::TooBig => write!(
+                f,
+                "Could not load Macrocell: the pattern is too big to run without HashLife"
+            ),
+        }
+    }
+}
+
+/// A node of the quadtree.
+enum Node {
+    /// An 8 by 8 square of cells, row by row.
+    Leaf(u64),
+    /// Four cell states, for rules with more than two.
+    Cells([usize; 4]),
+    /// A level and four nodes of the level below, zero for empty ones.
+    Quad(u32, [usize; 4]),
+}
+
+impl Node {
+    fn level(&self) -> u32 {
+        match self {
+            Node::Leaf(_) => 3,
+            Node::Cells(_) => 1,
+            Node::Quad(level, _) => *level,
+        }
+    }
+}
+
+/// Reads a Macrocell file.
+pub fn read(text: &str) -> Result<PatternFile, MacrocellError> {
+    let mut lines = text.lines().enumerate().map(|(i, l)| (i + 1, l.trim()));
+    match lines.next() {
+        Some((_, header)) if header.starts_with("[M2]") => {}
+        _ => return Err(MacrocellError::NoHeader),
+    }
+
+    // Node zero is the empty one, which is never looked at.
+    let mut nodes = vec![Node::Leaf(0)];
+    let mut rule = None;
+    let mut comments = Vec::new();
+    for (n, line) in lines {
+        let bad = || MacrocellError::Line(n, line.to_string());
+        if let Some(r) = line.strip_prefix("#R") {
+            rule = Some(r.trim().to_string());
+            continue;
+        }
+        // `#C` and `#N` lines say something about the pattern, and others
+        // such as Golly's `#G` for the generation don't.
+        if let Some(comment) = line.strip_prefix("#C").or(line.strip_prefix("#N")) {
+            comments.push(comment.trim().to_string());
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let node = if line.starts_with(['.', '*', '$']) {
+            Node::Leaf(leaf(line).ok_or_else(bad)?)
+        } else {
+            let numbers: Vec<usize> = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| bad())?;
+            let (level, children) = match numbers[..] {
+                [level, a, b, c, d] => (level, [a, b, c, d]),
+                _ => return Err(bad()),
+            };
+            match level {
+                1 => Node::Cells(children),
+                // Sizes must fit in the coordinates they're drawn at.
+                2..=62 => {
+                    let fits = children.iter().all(|&c| {
+                        c == 0 || nodes.get(c).is_some_and(|k| k.level() + 1 == level as u32)
+                    });
+                    if !fits {
+                        return Err(bad());
+                    }
+                    Node::Quad(level as u32, children)
+                }
+                _ => return Err(bad()),
+            }
+        };
+        nodes.push(node);
+    }
+
+    if nodes.len() == 1 {
+        return Err(MacrocellError::Empty);
+    }
+    // Even a few cells can need too much room if they're far apart.
+    let root = nodes.len() - 1;
+    let measures = measure(&nodes);
+    let (population, bounds) = measures[root];
+    let room = bounds.map_or(0, |((x0, y0), (x1, y1))| {
+        ((x1 - x0 + 1) as usize).saturating_mul((y1 - y0 + 1) as usize)
+    });
+    if population > MOST || room > MOST {
+        return Err(MacrocellError::TooBig);
+    }
+
+    let mut cells = Vec::with_capacity(population);
+    draw(&nodes, &measures, root, (0, 0), &mut cells);
+
+    Ok(PatternFile {
+        pattern: Pattern::gather(&cells),
+        rule,
+        comments,
+    })
+}
+
+/// Reads a leaf's rows into a bit for each cell, the top left first.
+fn leaf(line: &str) -> Option<u64> {
+    let (mut row, mut col, mut bits) = (0, 0, 0);
+    for c in line.chars() {
+        match c {
+            '$' => {
+                row += 1;
+                col = 0;
+                continue;
+            }
+            '*' if row < 8 && col < 8 => bits |= 1 << (row * 8 + col),
+            '.' if col < 8 => {}
+            _ => return None,
+        }
+        col += 1;
+    }
+
+    Some(bits)
+}
+
+/// The corners of the smallest rectangle holding some live cells, if
+/// there are any.
+type Bounds = Option<((i64, i64), (i64, i64))>;
+
+/// How many live cells every node has, and where they are within it,
+/// worked out from the smallest nodes up so that nodes used over and over
+/// are only looked at once.
+fn measure(nodes: &[Node]) -> Vec<(usize, Bounds)> {
+    let mut measures: Vec<(usize, Bounds)> = Vec::with_capacity(nodes.len());
+    let join = |a: Bounds, b: Bounds| match (a, b) {
+        (Some(((ax0, ay0), (ax1, ay1))), Some(((bx0, by0), (bx1, by1)))) => {
+            Some(((ax0.min(bx0), ay0.min(by0)), (ax1.max(bx1), ay1.max(by1))))
+        }
+        (a, None) => a,
+        (None, b) => b,
+    };
+
+    for (i, node) in nodes.iter().enumerate() {
+        let mut cells = Vec::new();
+        let measure = match node {
+            _ if i == 0 => (0, None),
+            Node::Leaf(_) | Node::Cells(_) => {
+                draw(nodes, &measures, i, (0, 0), &mut cells);
+                let bounds = cells.iter().fold(None, |b, &c| join(b, Some((c, c))));
+                (cells.len(), bounds)
+            }
+            Node::Quad(level, children) => {
+                let half = 1 << (level - 1);
+                children.iter().enumerate().fold(
+                    (0usize, None),
+                    |(population, bounds), (q, &child)| {
+                        let (p, b) = measures[child];
+                        let (dx, dy) = (half * (q as i64 % 2), half * (q as i64 / 2));
+                        let b =
+                            b.map(|((x0, y0), (x1, y1))| ((x0 + dx, y0 + dy), (x1 + dx, y1 + dy)));
+                        (population.saturating_add(p), join(bounds, b))
+                    },
+                )
+            }
+        };
+        measures.push(measure);
+    }
+
+    measures
+}
+
+/// Lists the live cells of a node with its top left corner at `(x, y)`.
+/// Empty children are skipped by how `measures` counts them, as a file can
+/// stack empty squares as deep as it likes without any of them being empty
+/// by name.
+fn draw(
+    nodes: &[Node],
+    measures: &[(usize, Bounds)],
+    node: usize,
+    (x, y): (i64, i64),
+    cells: &mut Vec<(i64, i64)>,
+) {
+    if node == 0 {
+        return;
+    }
+
+    match &nodes[node] {
+        Node::Leaf(bits) => {
+            for i in (0..64).filter(|i| bits & (1 << i) != 0) {
+                cells.push((x + i % 8, y + i / 8));
+            }
+        }
+        Node::Cells(states) => {
+            for (i, &state) in states.iter().enumerate() {
+                if state != 0 {
+                    cells.push((x + i as i64 % 2, y + i as i64 / 2));
+                }
+            }
+        }
+        Node::Quad(level, children) => {
+            let half = 1 << (level - 1);
+            for (i, &child) in children.iter().enumerate() {
+                if measures[child].0 == 0 {
+                    continue;
+                }
+                let at = (x + half * (i as i64 % 2), y + half * (i as i64 / 2));
+                draw(nodes, measures, child, at, cells);
+            }
+        }
+    }
+}
+
+/// Writes a pattern as Macrocell, writing each square that repeats only
+/// once.
+pub fn write(pattern: &Pattern, rules: &Rulestring) -> String {
+    let mut size = 8;
+    let mut level = 3;
+    while size < pattern.width.max(pattern.height) {
+        size *= 2;
+        level += 1;
+    }
+
+    let mut tree = Tree {
+        pattern,
+        lines: Vec::new(),
+        seen: HashMap::new(),
+    };
+    tree.node(level, 0, 0);
+    // A pattern with nothing in it is still one node.
+    if tree.lines.is_empty() {
+        tree.lines.push("$".to_string());
+    }
+
+    let mut out = format!("[M2] (iron-cat)\n#R {}\n", rules);
+    for line in tree.lines {
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Builds the lines of a Macrocell file, keeping the number of every node
+/// already written.
+struct Tree<'a> {
+    pattern: &'a Pattern,
+    lines: Vec<String>,
+    seen: HashMap<String, usize>,
+}
+
+impl Tree<'_> {
+    /// The number of the node of `level` with its top left corner at `row`,
+    /// `col`, writing it first if need be. Empty nodes are zero.
+    fn node(&mut self, level: u32, row: usize, col: usize) -> usize {
+        let line = if level == 3 {
+            let mut rows = Vec::new();
+            for r in row..row + 8 {
+                let cells: String = (col..col + 8)
+                    .map(|c| if self.alive(r, c) { '*' } else { '.' })
+                    .collect();
+                rows.push(cells.trim_end_matches('.').to_string());
+            }
+            while rows.last().is_some_and(String::is_empty) {
+                rows.pop();
+            }
+            if rows.is_empty() {
+                return 0;
+            }
+            rows.iter().fold(String::new(), |mut line, r| {
+                write!(line, "{}$", r).unwrap();
+                line
+            })
+        } else {
+            let half = 1 << (level - 1);
+            let children = [
+                self.node(level - 1, row, col),
+                self.node(level - 1, row, col + half),
+                self.node(level - 1, row + half, col),
+                self.node(level - 1, row + half, col + half),
+            ];
+            if children == [0; 4] {
+                return 0;
+            }
+            format!(
+                "{} {} {} {} {}",
+                level, children[0], children[1], children[2], children[3]
+            )
+        };
+
+        if let Some(&number) = self.seen.get(&line) {
+            return number;
+        }
+        self.lines.push(line.clone());
+        self.seen.insert(line, self.lines.len());
+        self.lines.len()
+    }
+
+    fn alive(&self, row: usize, col: usize) -> bool {
+        row < self.pattern.height
+            && col < self.pattern.width
+            && self.pattern.cells[row * self.pattern.width + col] != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLIDER: &str = "[M2] (golly 4.2)\n#R B3/S23\n.*$..*$***$\n4 1 0 0 0\n";
+
+    /// A file with a node of every level from 4 up to `top`, each built of
+    /// the one below as `children` says.
+    fn tower(leaf: &str, top: usize, children: impl Fn(usize) -> String) -> String {
+        let mut text = format!("[M2]\n{}\n", leaf);
+        for level in 4..=top {
+            writeln!(text, "{} {}", level, children(level - 3)).unwrap();
+        }
+        text
+    }
+
+    #[test]
+    fn glider_round_trips() {
+        let file = read(GLIDER).unwrap();
+        assert_eq!(file.rule.as_deref(), Some("B3/S23"));
+        assert_eq!((file.pattern.height, file.pattern.width), (3, 3));
+        assert_eq!(file.pattern.cells, [0, 1, 0, 0, 0, 1, 1, 1, 1]);
+
+        let rules: Rulestring = "B3/S23".parse().unwrap();
+        let text = write(&file.pattern, &rules);
+        assert_eq!(read(&text).unwrap().pattern, file.pattern);
+    }
+
+    #[test]
+    fn repeated_squares_are_written_once() {
+        let mut pattern = Pattern::empty(33, 33);
+        for (r, c) in [(0, 0), (0, 32), (32, 0), (32, 32)] {
+            pattern.cells[r * 33 + c] = 1;
+        }
+        let rules: Rulestring = "B3/S23".parse().unwrap();
+        let text = write(&pattern, &rules);
+        assert_eq!(read(&text).unwrap().pattern, pattern);
+        // After the header, one square of each level, the last the whole.
+        assert_eq!(
+            text.lines().skip(2).collect::<Vec<_>>(),
+            ["*$", "4 1 0 0 0", "5 2 0 0 0", "6 3 3 3 3"]
+        );
+    }
+
+    #[test]
+    fn deep_empty_squares() {
+        // Every square of level 46 is empty, and drawing it must not look
+        // in every one of them.
+        let text = tower("$", 46, |i| format!("{} {} {} {}", i, i, i, i));
+        let file = read(&text).unwrap();
+        assert!(file.pattern.cells.iter().all(|&c| c == 0));
+    }
+
+    #[test]
+    fn too_far_apart() {
+        let text = tower("*$", 16, |i| format!("{} 0 0 {}", i, i));
+        assert!(matches!(read(&text), Err(MacrocellError::TooBig)));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            read("x = 3, y = 3\n3o!"),
+            Err(MacrocellError::NoHeader)
+        ));
+        assert!(matches!(
+            read("[M2]\n#R B3/S23\n"),
+            Err(MacrocellError::Empty)
+        ));
+        // A level 5 node can't be made of level 3 ones.
+        assert!(matches!(
+            read("[M2]\n*$\n5 1 0 0 0\n"),
+            Err(MacrocellError::Line(3, _))
+        ));
+    }
+}
@@ -0,0 +1,387 @@
+//! Patterns are rectangles of cells lifted out of a world, to be turned
+//! around and put down again somewhere else, or written out for other
+//! programs.
+//!
+//! Pattern files can be RLE, Life 1.05, Life 1.06, plaintext or
+//! Macrocell, told apart by how they start.
+
+pub mod apgcode;
+pub mod banner;
+pub mod cells;
+pub mod library;
+pub mod life;
+pub mod macrocell;
+pub mod rle;
+
+use std::path::Path;
+use std::str::FromStr;
+use std::{error, fmt};
+
+use crate::{Matrix, Rulestring};
+use cells::CellsError;
+use life::LifeError;
+use macrocell::MacrocellError;
+use rle::RleError;
+
+/// A pattern read from a file, the rule it was written for if the file
+/// says, and what the file says about it otherwise, such as its name and
+/// who found it.
+#[derive(Clone, Debug)]
+pub struct PatternFile {
+    pub pattern: Pattern,
+    pub rule: Option<String>,
+    pub comments: Vec<String>,
+}
+
+/// Why a pattern file couldn't be read.
+#[derive(Debug)]
+pub enum ReadError {
+    Rle(RleError),
+    Life(LifeError),
+    Cells(CellsError),
+    Macrocell(MacrocellError),
+}
+
+impl error::Error for ReadError {}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Rle(e) => e.fmt(f),
+            ReadError::Life(e) => e.fmt(f),
+            ReadError::Cells(e) => e.fmt(f),
+            ReadError::Macrocell(e) => e.fmt(f),
+        }
+    }
+}
+
+/// The formats pattern files come in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Rle,
+    Life105,
+    Life106,
+    Cells,
+    Macrocell,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "rle" => Ok(Format::Rle),
+            "life105" => Ok(Format::Life105),
+            "life106" => Ok(Format::Life106),
+            "cells" => Ok(Format::Cells),
+            "mc" => Ok(Format::Macrocell),
+            _ => Err(format!("Invalid pattern format {}", s)),
+        }
+    }
+}
+
+impl Format {
+    /// The format of a file, going by its first line. Plaintext starts
+    /// with a `!` comment or a row of cells, and anything else without a
+    /// `#Life` line is taken for RLE.
+    pub fn of(text: &str) -> Format {
+        let first = text.lines().map(str::trim).find(|l| !l.is_empty());
+        match first {
+            Some(l) if l.starts_with("#Life 1.05") => Format::Life105,
+            Some(l) if l.starts_with("#Life 1.06") => Format::Life106,
+            Some(l) if l.starts_with("[M2]") => Format::Macrocell,
+            Some(l) if l.starts_with('!') || l.chars().all(|c| matches!(c, '.' | 'O' | '*')) => {
+                Format::Cells
+            }
+            _ => Format::Rle,
+        }
+    }
+
+    /// The format a file name says it's in, if its extension says.
+    /// `.lif` and `.life` are taken for Life 1.06, which is what most
+    /// programs write now.
+    pub fn by_extension(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "rle" => Some(Format::Rle),
+            "lif" | "life" => Some(Format::Life106),
+            "cells" => Some(Format::Cells),
+            "mc" => Some(Format::Macrocell),
+            _ => None,
+        }
+    }
+
+    /// Writes a pattern out in this format, with the rule where the format
+    /// has room for it, and comments, each on a line of its own, where the
+    /// format keeps them.
+    pub fn write(self, pattern: &Pattern, rules: &Rulestring, comments: &[String]) -> String {
+        let text = match self {
+            Format::Rle => rle::write(pattern, rules),
+            Format::Life105 => life::write_105(pattern, rules),
+            Format::Life106 => life::write_106(pattern),
+            Format::Cells => cells::write(pattern),
+            Format::Macrocell => macrocell::write(pattern, rules),
+        };
+
+        // RLE and plaintext start with their comments, and the rest have a
+        // first line that has to stay first.
+        let (prefix, after) = match self {
+            Format::Rle => ("#C ", 0),
+            Format::Cells => ("!", 0),
+            Format::Life105 => ("#D ", 1),
+            Format::Life106 => ("# ", 1),
+            Format::Macrocell => ("#C ", 1),
+        };
+        let mut lines: Vec<String> = text.lines().map(String::from).collect();
+        let at = after.min(lines.len());
+        lines.splice(at..at, comments.iter().map(|c| format!("{}{}", prefix, c)));
+        lines.join("\n") + "\n"
+    }
+}
+
+/// The most a pattern can be scaled up by.
+const SCALE: usize = 16;
+
+/// Ways a pattern can be turned around, or blown up, before it's put down.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transform {
+    Rot90,
+    Rot180,
+    Rot270,
+    FlipX,
+    FlipY,
+    Scale(usize),
+}
+
+impl FromStr for Transform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Transform, String> {
+        let lower = s.to_ascii_lowercase();
+        let factor = lower.strip_prefix("scale").map(str::parse::<usize>);
+        match lower.as_str() {
+            "rot90" => Ok(Transform::Rot90),
+            "rot180" => Ok(Transform::Rot180),
+            "rot270" => Ok(Transform::Rot270),
+            "flipx" => Ok(Transform::FlipX),
+            "flipy" => Ok(Transform::FlipY),
+            _ => match factor {
+                Some(Ok(n)) if (1..=SCALE).contains(&n) => Ok(Transform::Scale(n)),
+                _ => Err(format!(
+                    "Invalid transform {}: expected rot90, rot180, rot270, flipX, flipY \
+                     or scale2 up to scale{}",
+                    s, SCALE
+                )),
+            },
+        }
+    }
+}
+
+/// Splits the transforms off the end of a pattern's name, as in
+/// `gun.rle:rot90:scale2`. Anything after a `:` that isn't a transform is
+/// left as part of the name, so that `name:glider` and URLs stay whole.
+pub fn suffixes(spec: &str) -> (&str, Vec<Transform>) {
+    let mut source = spec;
+    let mut transforms = Vec::new();
+    while let Some((rest, last)) = source.rsplit_once(':') {
+        match last.parse::<Transform>() {
+            Ok(transform) => transforms.push(transform),
+            Err(_) => break,
+        }
+        source = rest;
+    }
+    transforms.reverse();
+
+    (source, transforms)
+}
+
+/// Reads a pattern file in whichever format it's in.
+pub fn read(text: &str) -> Result<PatternFile, ReadError> {
+    match Format::of(text) {
+        Format::Rle => rle::read(text).map_err(ReadError::Rle),
+        Format::Life105 => life::read_105(text).map_err(ReadError::Life),
+        Format::Life106 => life::read_106(text).map_err(ReadError::Life),
+        Format::Cells => cells::read(text).map_err(ReadError::Cells),
+        Format::Macrocell => macrocell::read(text).map_err(ReadError::Macrocell),
+    }
+}
+
+/// A rectangle of cell states, one row after another.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Pattern {
+    pub height: usize,
+    pub width: usize,
+    pub cells: Vec<usize>,
+}
+
+impl Pattern {
+    /// A pattern of nothing but dead cells.
+    pub fn empty(height: usize, width: usize) -> Pattern {
+        Pattern {
+            height,
+            width,
+            cells: vec![0; height * width],
+        }
+    }
+
+    /// Copies the rectangle between two opposite corners of a world, both
+    /// included.
+    pub fn copy(matrix: &Matrix, (r0, c0): (usize, usize), (r1, c1): (usize, usize)) -> Pattern {
+        let (top, bottom) = (r0.min(r1), r0.max(r1));
+        let (left, right) = (c0.min(c1), c0.max(c1));
+        let cells = (top..=bottom)
+            .flat_map(|r| matrix.rows[r * matrix.n + left..=r * matrix.n + right].iter())
+            .copied()
+            .collect();
+
+        Pattern {
+            height: bottom - top + 1,
+            width: right - left + 1,
+            cells,
+        }
+    }
+
+    /// The smallest rectangle of a world that holds every live cell, or
+    /// nothing at all if there are none.
+    pub fn live(matrix: &Matrix) -> Pattern {
+        let live = |r: usize, c: usize| matrix.rows[r * matrix.n + c] != 0;
+        let rows: Vec<usize> = (0..matrix.m)
+            .filter(|&r| (0..matrix.n).any(|c| live(r, c)))
+            .collect();
+        let cols: Vec<usize> = (0..matrix.n)
+            .filter(|&c| (0..matrix.m).any(|r| live(r, c)))
+            .collect();
+
+        match (rows.first(), rows.last(), cols.first(), cols.last()) {
+            (Some(&top), Some(&bottom), Some(&left), Some(&right)) => {
+                Pattern::copy(matrix, (top, left), (bottom, right))
+            }
+            _ => Pattern::empty(0, 0),
+        }
+    }
+
+    /// The smallest pattern holding every one of the live cells given, as
+    /// `(x, y)` anywhere at all.
+    pub fn gather(cells: &[(i64, i64)]) -> Pattern {
+        let (xs, ys): (Vec<i64>, Vec<i64>) = cells.iter().copied().unzip();
+        let (left, top) = match (xs.iter().min(), ys.iter().min()) {
+            (Some(&x), Some(&y)) => (x, y),
+            _ => return Pattern::empty(0, 0),
+        };
+        let width = (xs.iter().max().unwrap() - left + 1) as usize;
+        let height = (ys.iter().max().unwrap() - top + 1) as usize;
+
+        let mut pattern = Pattern::empty(height, width);
+        for &(x, y) in cells {
+            pattern.cells[(y - top) as usize * width + (x - left) as usize] = 1;
+        }
+
+        pattern
+    }
+
+    /// The pattern turned a quarter turn clockwise.
+    pub fn rotate(&self) -> Pattern {
+        let mut cells = Vec::with_capacity(self.cells.len());
+        for c in 0..self.width {
+            for r in (0..self.height).rev() {
+                cells.push(self.cells[r * self.width + c]);
+            }
+        }
+
+        Pattern {
+            height: self.width,
+            width: self.height,
+            cells,
+        }
+    }
+
+    /// The pattern mirrored left to right.
+    pub fn flip(&self) -> Pattern {
+        let cells = self
+            .cells
+            .chunks(self.width.max(1))
+            .flat_map(|row| row.iter().rev())
+            .copied()
+            .collect();
+
+        Pattern {
+            cells,
+            ..self.clone()
+        }
+    }
+
+    /// The pattern turned around as asked. Rotations are clockwise,
+    /// `FlipX` mirrors it left to right and `FlipY` top to bottom.
+    pub fn transform(&self, transform: Transform) -> Pattern {
+        match transform {
+            Transform::Rot90 => self.rotate(),
+            Transform::Rot180 => self.rotate().rotate(),
+            Transform::Rot270 => self.rotate().rotate().rotate(),
+            Transform::FlipX => self.flip(),
+            Transform::FlipY => self.rotate().rotate().flip(),
+            Transform::Scale(factor) => self.scale(factor),
+        }
+    }
+
+    /// The pattern with every cell blown up into a square of `factor` by
+    /// `factor` cells.
+    pub fn scale(&self, factor: usize) -> Pattern {
+        let (height, width) = (self.height * factor, self.width * factor);
+        let mut cells = Vec::with_capacity(height * width);
+        for r in 0..height {
+            for c in 0..width {
+                cells.push(self.cells[r / factor * self.width + c / factor]);
+            }
+        }
+
+        Pattern {
+            height,
+            width,
+            cells,
+        }
+    }
+
+    /// A small picture of the pattern, no more than `height` lines of
+    /// `width` characters. Bigger patterns are shrunk, each character
+    /// standing for a square of cells that's shown alive if any are.
+    pub fn thumbnail(&self, height: usize, width: usize) -> Vec<String> {
+        let scale = self
+            .height
+            .div_ceil(height.max(1))
+            .max(self.width.div_ceil(width.max(1)))
+            .max(1);
+        let alive = |r: usize, c: usize| {
+            (r * scale..((r + 1) * scale).min(self.height)).any(|r| {
+                (c * scale..((c + 1) * scale).min(self.width))
+                    .any(|c| self.cells[r * self.width + c] != 0)
+            })
+        };
+
+        (0..self.height.div_ceil(scale))
+            .map(|r| {
+                (0..self.width.div_ceil(scale))
+                    .map(|c| if alive(r, c) { '█' } else { '·' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Puts the pattern down with its top left corner at `row`, `col`,
+    /// replacing whatever was there.
+    pub fn paste(&self, matrix: &mut Matrix, (row, col): (usize, usize)) {
+        matrix.place(row, col, self.height, self.width, &self.cells);
+    }
+
+    /// Puts only the pattern's live cells down with its top left corner at
+    /// `row`, `col`, so that patterns close together don't wipe out each
+    /// other's cells.
+    pub fn overlay(&self, matrix: &mut Matrix, (row, col): (usize, usize)) {
+        for (i, &state) in self.cells.iter().enumerate() {
+            if state != 0 {
+                let r = (row + i / self.width) % matrix.m;
+                let c = (col + i % self.width) % matrix.n;
+                matrix.set(r, c, state);
+            }
+        }
+    }
+}
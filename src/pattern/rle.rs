@@ -0,0 +1,269 @@
+//! Run Length Encoded patterns, as used by Golly and LifeWiki.
+//!
+//! ```text
+//! x = 3, y = 3, rule = B3/S23
+//! bob$2bo$3o!
+//! ```
+//!
+//! After the header every row is written as runs of `b` for dead cells and
+//! `o` for live ones, each optionally preceded by a count, with `$` between
+//! rows and `!` at the end.
+
+use std::fmt::Write as _;
+use std::{error, fmt};
+
+use super::{Pattern, PatternFile};
+use crate::Rulestring;
+
+/// How long lines may get, as the format asks.
+const LINE: usize = 70;
+
+/// The most cells a header may ask room for, as for Macrocell.
+const MOST: usize = 1 << 24;
+
+/// Writes a pattern out, header and all.
+pub fn write(pattern: &Pattern, rules: &Rulestring) -> String {
+    let mut out = format!(
+        "x = {}, y = {}, rule = {}\n",
+        pattern.width, pattern.height, rules
+    );
+
+    // Runs of empty rows are counted like runs of cells, as in `3$`.
+    let mut body = String::new();
+    let mut ends = 0;
+    for (i, row) in pattern.cells.chunks(pattern.width.max(1)).enumerate() {
+        if i > 0 {
+            ends += 1;
+        }
+        let runs = run_length(row);
+        if runs.is_empty() {
+            continue;
+        }
+        match ends {
+            0 => {}
+            1 => body.push('$'),
+            n => write!(body, "{}$", n).unwrap(),
+        }
+        ends = 0;
+        body.push_str(&runs);
+    }
+    body.push('!');
+
+    // Lines are only broken after a run, never in the middle of one.
+    let mut line = 0;
+    let mut token = String::new();
+    for c in body.chars() {
+        token.push(c);
+        if c.is_ascii_digit() {
+            continue;
+        }
+        if line + token.len() > LINE {
+            out.push('\n');
+            line = 0;
+        }
+        line += token.len();
+        out.push_str(&token);
+        token.clear();
+    }
+    out.push('\n');
+
+    out
+}
+
+/// Encodes a row as runs of `b` and `o`, leaving out trailing dead cells.
+pub fn run_length(row: &[usize]) -> String {
+    let mut out = String::new();
+    let end = row.iter().rposition(|&c| c != 0).map_or(0, |i| i + 1);
+    let mut i = 0;
+
+    while i < end {
+        let alive = row[i] != 0;
+        let mut run = 1;
+        while i + run < end && (row[i + run] != 0) == alive {
+            run += 1;
+        }
+
+        if run > 1 {
+            write!(out, "{}", run).unwrap();
+        }
+        out.push(if alive { 'o' } else { 'b' });
+        i += run;
+    }
+
+    out
+}
+
+/// Why an RLE file couldn't be read.
+#[derive(Debug)]
+pub enum RleError {
+    NoHeader,
+    Header(String),
+    Body(char),
+    Count,
+    TooBig,
+}
+
+impl error::Error for RleError {}
+
+impl fmt::Display for RleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RleError::NoHeader => write!(f, "Invalid RLE: there is no `x = …, y = …` header"),
+            RleError::Header(s) => write!(f, "Invalid RLE header: {}", s),
+            RleError::Body(c) => write!(f, "Invalid RLE: unexpected {:?}", c),
+            RleError::Count => write!(f, "Invalid RLE: a run is too long to count"),
+            RleError::TooBig => write!(f, "Invalid RLE: the cells don't fit the header's size"),
+        }
+    }
+}
+
+/// Reads a pattern back in. Comment lines start with `#`, and of those the
+/// comments, name and author are kept. Anything after the `!` is ignored.
+///
+/// States other than `b` and `o`, as written for rules with more than two,
+/// are all read as alive.
+pub fn read(text: &str) -> Result<PatternFile, RleError> {
+    let comments = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| {
+            ["#C", "#c", "#N", "#O"]
+                .iter()
+                .any(|tag| l.starts_with(tag))
+        })
+        .map(|l| l[2..].trim().to_string())
+        .collect();
+
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'));
+    let header = lines.next().ok_or(RleError::NoHeader)?;
+    if !header.starts_with('x') {
+        return Err(RleError::NoHeader);
+    }
+
+    let (mut width, mut height, mut rule) = (None, None, None);
+    for part in header.split(',') {
+        let (key, value) = match part.split_once('=') {
+            Some((k, v)) => (k.trim(), v.trim()),
+            None => return Err(RleError::Header(part.trim().to_string())),
+        };
+        let size = || {
+            value
+                .parse::<usize>()
+                .map_err(|_| RleError::Header(part.trim().to_string()))
+        };
+        match key {
+            "x" => width = Some(size()?),
+            "y" => height = Some(size()?),
+            "rule" => rule = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    let (width, height) = match (width, height) {
+        (Some(w), Some(h)) => (w, h),
+        _ => return Err(RleError::NoHeader),
+    };
+    if width.checked_mul(height).is_none_or(|room| room > MOST) {
+        return Err(RleError::Header(format!(
+            "x = {}, y = {} is more than {} cells",
+            width, height, MOST
+        )));
+    }
+
+    let mut pattern = Pattern::empty(height, width);
+    let (mut row, mut col) = (0, 0);
+    let mut count: usize = 0;
+    'body: for line in lines {
+        for c in line.chars() {
+            let run = count.max(1);
+            match c {
+                '0'..='9' => {
+                    count = count
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add(c.to_digit(10).unwrap() as usize))
+                        .ok_or(RleError::Count)?;
+                    continue;
+                }
+                '!' => break 'body,
+                // Rows and columns are kept within the header's size, so
+                // nothing past it is ever counted up to.
+                '$' => {
+                    if run > height - row {
+                        return Err(RleError::TooBig);
+                    }
+                    row += run;
+                    col = 0;
+                }
+                'b' | '.' => {
+                    if run > width - col {
+                        return Err(RleError::TooBig);
+                    }
+                    col += run;
+                }
+                'o' | 'A'..='X' => {
+                    if row >= height || run > width - col {
+                        return Err(RleError::TooBig);
+                    }
+                    for cell in &mut pattern.cells[row * width + col..][..run] {
+                        *cell = 1;
+                    }
+                    col += run;
+                }
+                c if c.is_whitespace() => continue,
+                c => return Err(RleError::Body(c)),
+            }
+            count = 0;
+        }
+    }
+
+    Ok(PatternFile {
+        pattern,
+        rule,
+        comments,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLIDER: &str = "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n";
+
+    #[test]
+    fn glider_round_trips() {
+        let file = read(GLIDER).unwrap();
+        assert_eq!(file.pattern.cells, [0, 1, 0, 0, 0, 1, 1, 1, 1]);
+        assert_eq!(file.rule.as_deref(), Some("B3/S23"));
+
+        let rules: Rulestring = "B3/S23".parse().unwrap();
+        let text = write(&file.pattern, &rules);
+        assert_eq!(text, GLIDER);
+        assert_eq!(read(&text).unwrap().pattern, file.pattern);
+    }
+
+    #[test]
+    fn run_too_long_to_count() {
+        let text = "x = 3, y = 3\n99999999999999999999999o!";
+        assert!(matches!(read(text), Err(RleError::Count)));
+    }
+
+    #[test]
+    fn header_too_big() {
+        for header in ["x = 100000, y = 100000", "x = 18446744073709551615, y = 2"] {
+            let text = format!("{}\no!", header);
+            assert!(matches!(read(&text), Err(RleError::Header(_))));
+        }
+    }
+
+    #[test]
+    fn runs_past_the_header() {
+        for body in ["5$o!", "4o!", "2b2o!", "5b$o!", "3$o!"] {
+            let text = format!("x = 3, y = 3\n{}", body);
+            assert!(matches!(read(&text), Err(RleError::TooBig)), "{}", body);
+        }
+        // Ending on the row after the last is only running off the end.
+        assert!(read("x = 3, y = 3\nbo$o$3o$!").is_ok());
+    }
+}
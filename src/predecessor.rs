@@ -0,0 +1,130 @@
+//! Looking for what a pattern came from: `iron-cat predecessor gun.rle`
+//! finds a pattern that turns into it one generation later, or proves
+//! there's none in the box searched, which makes it a Garden of Eden as far
+//! as that box goes.
+//!
+//! The predecessor is looked for among the cells of the pattern's bounding
+//! box and `margin` cells around it, with everything outside that dead, on
+//! a plane that goes on for ever rather than the usual torus. Its next
+//! generation has to be the pattern exactly, leaving nothing alive around
+//! it. The search tries every cell dead and then alive, a row at a time,
+//! and backs out as soon as some cell of the next generation couldn't come
+//! out right whatever the cells still to be tried around it are, so small
+//! patterns are done in moments, while large ones can take for ever.
+
+use crate::pattern::Pattern;
+use crate::Rulestring;
+
+/// How the search went: the predecessor, if there's one, and how many
+/// cells were tried getting there.
+pub struct Found {
+    pub predecessor: Option<Pattern>,
+    pub tried: u64,
+}
+
+struct Search<'a> {
+    rules: &'a Rulestring,
+    /// The box the predecessor is looked for in.
+    height: usize,
+    width: usize,
+    /// Its cells: dead, alive or not tried yet.
+    cells: Vec<Option<bool>>,
+    /// What every cell of the box and the ring around it must be next.
+    want: Vec<bool>,
+    tried: u64,
+}
+
+impl Search<'_> {
+    /// Whether the cell of the next generation at `(i, j)`, counting from
+    /// the top left of the ring around the box, can still come out as it
+    /// should.
+    fn possible(&self, i: usize, j: usize) -> bool {
+        let cell = |r: usize, c: usize| -> Option<bool> {
+            // The ring's cell one down and one across is the box's first.
+            if r == 0 || c == 0 || r > self.height || c > self.width {
+                Some(false)
+            } else {
+                self.cells[(r - 1) * self.width + c - 1]
+            }
+        };
+        let (mut alive, mut open) = (0, 0);
+        for r in i.saturating_sub(1)..=i + 1 {
+            for c in j.saturating_sub(1)..=j + 1 {
+                if (r, c) == (i, j) {
+                    continue;
+                }
+                match cell(r, c) {
+                    Some(true) => alive += 1,
+                    Some(false) => {}
+                    None => open += 1,
+                }
+            }
+        }
+        let want = self.want[i * (self.width + 2) + j];
+        let centre = cell(i, j);
+        (alive..=alive + open).any(|sum| {
+            (centre != Some(true) && self.rules.b[sum] == want)
+                || (centre != Some(false) && self.rules.s[sum] == want)
+        })
+    }
+
+    /// Tries the cells from the `k`th on, returning true with the box
+    /// filled in if they can be made to work.
+    fn fill(&mut self, k: usize) -> bool {
+        if k == self.cells.len() {
+            return true;
+        }
+        let (r, c) = (k / self.width, k % self.width);
+        for alive in [false, true] {
+            self.tried += 1;
+            self.cells[k] = Some(alive);
+            // The box's cell is the ring's one down and one across, and
+            // only the next generation's cells around it can have changed.
+            let ok = (r..=r + 2).all(|i| (c..=c + 2).all(|j| self.possible(i, j)));
+            if ok && self.fill(k + 1) {
+                return true;
+            }
+        }
+        self.cells[k] = None;
+        false
+    }
+}
+
+/// Looks for a predecessor of `target` under `rules` with `margin` cells
+/// of room around it.
+pub fn search(target: &Pattern, rules: &Rulestring, margin: usize) -> Found {
+    let (height, width) = (target.height + 2 * margin, target.width + 2 * margin);
+
+    // The target sits `margin` in from the box, which is one in from the
+    // ring around it.
+    let mut want = vec![false; (height + 2) * (width + 2)];
+    for r in 0..target.height {
+        for c in 0..target.width {
+            want[(r + margin + 1) * (width + 2) + c + margin + 1] =
+                target.cells[r * target.width + c] != 0;
+        }
+    }
+
+    let mut search = Search {
+        rules,
+        height,
+        width,
+        cells: vec![None; height * width],
+        want,
+        tried: 0,
+    };
+    // Cells of the ring can be impossible before anything is tried, such as
+    // a cell that must be alive with no room left around it.
+    let start = (0..height + 2).all(|i| (0..width + 2).all(|j| search.possible(i, j)));
+    let found = start && search.fill(0);
+
+    // Trimmed down to the cells that are alive.
+    let alive: Vec<(i64, i64)> = (0..search.cells.len())
+        .filter(|&k| search.cells[k] == Some(true))
+        .map(|k| ((k % width) as i64, (k / width) as i64))
+        .collect();
+    Found {
+        predecessor: found.then(|| Pattern::gather(&alive)),
+        tried: search.tried,
+    }
+}
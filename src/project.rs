@@ -0,0 +1,136 @@
+//! Projects: a world together with how it was shown, to be opened again
+//! just as it was left.
+//!
+//! A project is a checkpoint with a few more lines: the topology, the
+//! arguments that chose the theme and how the world is drawn, and where
+//! the view was.
+//!
+//! ```text
+//! iron-cat checkpoint 1
+//! rule B3/S23
+//! ...
+//! seed 1234567
+//! topology torus
+//! arg --theme-file
+//! arg neon.toml
+//! arg --ruler
+//! view 10 20 0
+//!
+//! <the cells, compressed>
+//! ```
+//!
+//! So every run with `--checkpoint-file run.icat` writes one, and `iron-cat
+//! open run.icat` carries on from it as though `--resume --checkpoint-file
+//! run.icat` had been given along with the arguments kept in it.
+
+use std::ffi::OsString;
+use std::path::Path;
+use std::{error, fmt};
+
+use clap::ArgMatches;
+
+use crate::checkpoint::{Checkpoint, CheckpointError};
+
+/// The arguments about how the world is shown, which a project keeps.
+const SHOWN: &[&str] = &[
+    "theme_file",
+    "state_style",
+    "charset",
+    "emoji",
+    "color_by",
+    "output",
+    "scale",
+    "ruler",
+    "status",
+    "sparkline",
+    "dashboard",
+];
+
+/// The only topology there is, for now: the edges wrap around.
+const TOPOLOGY: &str = "torus";
+
+/// Why a project couldn't be opened.
+#[derive(Debug)]
+pub enum ProjectError {
+    Checkpoint(CheckpointError),
+    Topology(String),
+}
+
+impl error::Error for ProjectError {}
+
+impl fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProjectError::Checkpoint(e) => write!(f, "{}", e),
+            ProjectError::Topology(t) => {
+                write!(
+                    f,
+                    "Invalid project: a {} world, where only a {} can be run",
+                    t, TOPOLOGY
+                )
+            }
+        }
+    }
+}
+
+/// The lines a project keeps besides the world, but for the view, which
+/// changes as it runs.
+pub fn settings(matches: &ArgMatches) -> Vec<(String, String)> {
+    let mut settings = vec![("topology".to_string(), TOPOLOGY.to_string())];
+    for name in SHOWN {
+        let flag = format!("--{}", name.replace('_', "-"));
+        if !matches.is_present(name) {
+            continue;
+        }
+        // Flags have no values, and options one each time they're given.
+        let values: Vec<&str> = matches.values_of(name).into_iter().flatten().collect();
+        if values.is_empty() {
+            settings.push(("arg".to_string(), flag.clone()));
+        }
+        for value in values {
+            settings.push(("arg".to_string(), flag.clone()));
+            settings.push(("arg".to_string(), value.to_string()));
+        }
+    }
+    settings
+}
+
+/// Where the view was, as its top left cell and its zoom.
+pub fn view(settings: &[(String, String)]) -> Option<((usize, usize), usize)> {
+    let (_, value) = settings.iter().rev().find(|(key, _)| key == "view")?;
+    let mut numbers = value.split(' ').map(|n| n.parse::<usize>().ok());
+    Some(((numbers.next()??, numbers.next()??), numbers.next()??))
+}
+
+/// Turns `open project.icat` among the arguments into the ones that resume
+/// it, with those it was shown with. Arguments given along with it take
+/// the place of any kept that are the same.
+pub fn arguments(args: Vec<OsString>) -> Result<Vec<OsString>, ProjectError> {
+    let at = match args.iter().skip(1).position(|a| a == "open") {
+        Some(i) if i + 2 < args.len() => i + 1,
+        _ => return Ok(args),
+    };
+    let path = &args[at + 1];
+    let checkpoint = Checkpoint::load(Path::new(path)).map_err(ProjectError::Checkpoint)?;
+    if let Some((_, topology)) = checkpoint.settings.iter().find(|(k, _)| k == "topology") {
+        if topology != TOPOLOGY {
+            return Err(ProjectError::Topology(topology.clone()));
+        }
+    }
+
+    let given: Vec<&OsString> = args[1..at].iter().chain(&args[at + 2..]).collect();
+    let mut kept: Vec<Vec<OsString>> = Vec::new();
+    for (_, value) in checkpoint.settings.iter().filter(|(k, _)| k == "arg") {
+        match kept.last_mut() {
+            Some(arg) if !value.starts_with("--") => arg.push(value.into()),
+            _ => kept.push(vec![value.into()]),
+        }
+    }
+    kept.retain(|arg| !given.contains(&&arg[0]));
+
+    let mut out = args[..at].to_vec();
+    out.extend(kept.into_iter().flatten());
+    out.extend(["--resume".into(), "--checkpoint-file".into(), path.clone()]);
+    out.extend(args[at + 2..].iter().cloned());
+    Ok(out)
+}
@@ -0,0 +1,133 @@
+//! Provenance: where an exported file or a recording came from, written
+//! into it so it can be made again.
+//!
+//! Everything iron-cat writes carries the version that wrote it, the rule
+//! and size of the world it started with, the seed everything random came
+//! from, the command line and when it was run, in whatever way the format
+//! has of keeping notes: `#C` lines in RLE, a comment in SVG, text chunks in
+//! PNG, and so on.
+//!
+//! ```text
+//! #C iron-cat 0.1.0
+//! #C rule B3/S23
+//! #C size 40x60
+//! #C seed 1103
+//! #C args iron-cat --pattern gun.rle --rows 40 --columns 60 --rng-seed 1103
+//! #C time 2026-10-16T12:00:00Z
+//! ```
+//!
+//! The command line always has the seed in it, so running it again makes
+//! the same thing, unless someone was at the keyboard.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{json, Rulestring};
+
+/// What an output was made from, field by field.
+#[derive(Clone, Debug, Default)]
+pub struct Provenance {
+    fields: Vec<(&'static str, String)>,
+}
+
+impl Provenance {
+    /// `args` is the command line, to which the seed is added unless it's
+    /// there already or can't be given, as when resuming.
+    pub fn new(
+        rules: &Rulestring,
+        (m, n): (usize, usize),
+        seed: u64,
+        args: &[String],
+    ) -> Provenance {
+        Provenance {
+            fields: vec![
+                ("iron-cat", env!("CARGO_PKG_VERSION").to_string()),
+                ("rule", rules.to_string()),
+                ("size", format!("{}x{}", m, n)),
+                ("seed", seed.to_string()),
+                (
+                    "args",
+                    args.iter().map(|a| quote(a)).collect::<Vec<_>>().join(" "),
+                ),
+                ("time", timestamp(SystemTime::now())),
+            ],
+        }
+    }
+
+    /// A line for every field, as `key value`.
+    pub fn lines(&self) -> Vec<String> {
+        self.fields
+            .iter()
+            .map(|(key, value)| format!("{} {}", key, value))
+            .collect()
+    }
+
+    /// All the fields as one JSON object.
+    pub fn json(&self) -> String {
+        let fields: Vec<String> = self
+            .fields
+            .iter()
+            .map(|(key, value)| format!("{}: {}", json::string(key), json::string(value)))
+            .collect();
+        format!("{{{}}}", fields.join(", "))
+    }
+
+    /// A `meta` tag for every field, for the head of an HTML page.
+    pub fn meta(&self) -> String {
+        self.fields
+            .iter()
+            .map(|(key, value)| {
+                format!(
+                    "<meta name=\"iron-cat:{}\" content=\"{}\">\n",
+                    key,
+                    escape(value)
+                )
+            })
+            .collect()
+    }
+}
+
+/// Escapes text for XML or HTML, in an element or an attribute.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Quotes an argument for a POSIX shell, if it needs it.
+fn quote(arg: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./:=,@+%^".contains(c);
+    if !arg.is_empty() && arg.chars().all(plain) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// A time in UTC as ISO 8601, such as `2026-10-16T12:00:00Z`.
+pub fn timestamp(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, rest) = ((secs / 86_400) as i64, secs % 86_400);
+
+    // Days since 1970 to a date, as in Howard Hinnant's `civil_from_days`,
+    // counting years from March so leap days come last.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rest / 3600,
+        rest / 60 % 60,
+        rest % 60
+    )
+}
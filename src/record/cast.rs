@@ -0,0 +1,84 @@
+//! asciinema recordings.
+//!
+//! The [asciicast v2] format is a JSON header line followed by one JSON
+//! array per chunk of output: `[seconds, "o", "data"]`. Since we record the
+//! exact bytes sent to the terminal, `asciinema play` reproduces the run
+//! faithfully, colours and all. The provenance goes in the header, under
+//! `iron-cat`, which players pass over.
+//!
+//! [asciicast v2]: https://docs.asciinema.org/manual/asciicast/v2/
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use super::Recorder;
+use crate::json;
+use crate::provenance::Provenance;
+use crate::theme::Theme;
+use crate::view::visible_width;
+use crate::Matrix;
+
+/// Writes the terminal output of every generation with its timing.
+pub struct CastRecorder {
+    path: String,
+    provenance: Provenance,
+    file: Option<BufWriter<File>>,
+    start: Instant,
+}
+
+impl CastRecorder {
+    /// The file is created with the first frame, which gives us the size of
+    /// the terminal to put in the header.
+    pub fn new(path: &str, provenance: &Provenance) -> CastRecorder {
+        CastRecorder {
+            path: path.to_string(),
+            provenance: provenance.clone(),
+            file: None,
+            start: Instant::now(),
+        }
+    }
+
+    fn header(&self, frame: &str) -> String {
+        let width = frame.lines().map(visible_width).max().unwrap_or(0);
+        let height = frame.lines().count();
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let term = std::env::var("TERM").unwrap_or_else(|_| String::from("xterm-256color"));
+
+        format!(
+            "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}, \"env\": {{\"TERM\": {}}}, \"iron-cat\": {}}}\n",
+            width,
+            height,
+            timestamp,
+            json::string(&term),
+            self.provenance.json()
+        )
+    }
+}
+
+impl Recorder for CastRecorder {
+    fn record(&mut self, _theme: &Theme, _matrix: &Matrix, frame: &str) -> io::Result<()> {
+        if self.file.is_none() {
+            let mut file = BufWriter::new(File::create(&self.path)?);
+            file.write_all(self.header(frame).as_bytes())?;
+            self.file = Some(file);
+            self.start = Instant::now();
+        }
+
+        let time = self.start.elapsed().as_secs_f64();
+
+        // Created above.
+        let file = self.file.as_mut().unwrap();
+        writeln!(file, "[{:.6}, \"o\", {}]", time, json::string(frame))
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        match self.file.take() {
+            Some(mut file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
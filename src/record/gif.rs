@@ -0,0 +1,89 @@
+//! Animated GIF recording.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use ::gif::{AnyExtension, Encoder, Extension, Frame, Repeat};
+
+use super::Recorder;
+use crate::provenance::Provenance;
+use crate::render::Canvas;
+use crate::theme::Theme;
+use crate::Matrix;
+
+/// Writes every generation as one frame of a looping GIF.
+///
+/// Each frame carries its own palette, as a theme with a gradient can use
+/// different colours from one generation to the next.
+pub struct GifRecorder {
+    path: String,
+    scale: usize,
+    /// Time between frames, in hundredths of a second as GIF wants it.
+    delay: u16,
+    provenance: Provenance,
+    encoder: Option<Encoder<BufWriter<File>>>,
+}
+
+impl GifRecorder {
+    /// `delay` is in milliseconds. The file is only created once the first
+    /// frame arrives, as that is when we learn the image size.
+    pub fn new(path: &str, scale: usize, delay: u64, provenance: &Provenance) -> GifRecorder {
+        GifRecorder {
+            path: path.to_string(),
+            scale,
+            delay: (delay / 10).min(u16::MAX as u64) as u16,
+            provenance: provenance.clone(),
+            encoder: None,
+        }
+    }
+}
+
+impl Recorder for GifRecorder {
+    fn record(&mut self, theme: &Theme, matrix: &Matrix, _frame: &str) -> io::Result<()> {
+        let canvas = Canvas::new(theme, matrix, self.scale);
+
+        if canvas.width > u16::MAX as usize || canvas.height > u16::MAX as usize {
+            return Err(io::Error::other(
+                "the grid is too large for a GIF at this scale",
+            ));
+        }
+
+        if self.encoder.is_none() {
+            let file = BufWriter::new(File::create(&self.path)?);
+            let mut encoder = Encoder::new(file, canvas.width as u16, canvas.height as u16, &[])
+                .map_err(io::Error::other)?;
+            encoder
+                .set_repeat(Repeat::Infinite)
+                .map_err(io::Error::other)?;
+            let comment = self.provenance.lines().join("\n");
+            encoder.write_raw_extension(
+                AnyExtension(Extension::Comment as u8),
+                &[comment.as_bytes()],
+            )?;
+            self.encoder = Some(encoder);
+        }
+
+        let (palette, indices) = canvas.indexed();
+        let palette: Vec<u8> = palette.iter().flat_map(|c| vec![c.r, c.g, c.b]).collect();
+
+        let mut frame = Frame::from_palette_pixels(
+            canvas.width as u16,
+            canvas.height as u16,
+            indices,
+            palette,
+            None,
+        );
+        frame.delay = self.delay;
+
+        // Checked above.
+        let encoder = self.encoder.as_mut().unwrap();
+        encoder.write_frame(&frame).map_err(io::Error::other)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        match self.encoder.take() {
+            Some(encoder) => encoder.into_inner()?.flush(),
+            None => Ok(()),
+        }
+    }
+}
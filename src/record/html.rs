@@ -0,0 +1,253 @@
+//! Self-contained HTML players, so a run can be shared with nothing more
+//! than a browser.
+//!
+//! Frames are collected in memory and the page is written when the run is
+//! over. There are two flavours: a small JavaScript player drawing on a
+//! canvas, with play/pause and a scrubber, and a script-free page where
+//! every frame is a single `div` whose `box-shadow` paints the live cells,
+//! flipped through with a CSS animation. Either way the provenance is in
+//! `meta` tags in the head.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+
+use super::Recorder;
+use crate::provenance::Provenance;
+use crate::theme::{Color, Theme};
+use crate::Matrix;
+
+/// Which kind of page to write.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Player {
+    Canvas,
+    Css,
+}
+
+/// Collects the live cells of every generation and their colours.
+pub struct HtmlRecorder {
+    path: String,
+    player: Player,
+    scale: usize,
+    /// Time between frames, in milliseconds.
+    delay: u64,
+    provenance: Provenance,
+    size: (usize, usize),
+    background: Color,
+    palette: Vec<Color>,
+    colors: HashMap<Color, usize>,
+    /// Every frame as `(cell index, palette index)` pairs.
+    frames: Vec<Vec<(usize, usize)>>,
+}
+
+impl HtmlRecorder {
+    pub fn new(
+        path: &str,
+        player: Player,
+        scale: usize,
+        delay: u64,
+        provenance: &Provenance,
+    ) -> HtmlRecorder {
+        HtmlRecorder {
+            path: path.to_string(),
+            player,
+            scale,
+            delay: delay.max(1),
+            provenance: provenance.clone(),
+            size: (0, 0),
+            background: Color { r: 0, g: 0, b: 0 },
+            palette: Vec::new(),
+            colors: HashMap::new(),
+            frames: Vec::new(),
+        }
+    }
+
+    fn canvas_page(&self) -> String {
+        let (m, n) = self.size;
+        let palette: Vec<String> = self.palette.iter().map(|c| format!("\"{}\"", c)).collect();
+        let frames: Vec<String> = self
+            .frames
+            .iter()
+            .map(|f| {
+                let cells: Vec<String> = f.iter().map(|(i, c)| format!("{},{}", i, c)).collect();
+                format!("[{}]", cells.join(","))
+            })
+            .collect();
+
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+{meta}<title>IronCAT</title>
+<style>
+body {{ background: #222; color: #ddd; font-family: sans-serif; text-align: center; }}
+canvas {{ image-rendering: pixelated; margin: 1em; }}
+</style>
+</head>
+<body>
+<canvas id="grid" width="{w}" height="{h}"></canvas>
+<div>
+<button id="play">Pause</button>
+<input id="scrub" type="range" min="0" max="{last}" value="0">
+<span id="gen">0</span>
+</div>
+<script>
+const rows = {m}, cols = {n}, scale = {scale}, delay = {delay};
+const background = "{background}";
+const palette = [{palette}];
+const frames = [{frames}];
+const ctx = document.getElementById("grid").getContext("2d");
+const play = document.getElementById("play");
+const scrub = document.getElementById("scrub");
+const gen = document.getElementById("gen");
+let current = 0, timer = null;
+
+function draw(i) {{
+  current = i;
+  ctx.fillStyle = background;
+  ctx.fillRect(0, 0, cols * scale, rows * scale);
+  const cells = frames[i];
+  for (let j = 0; j < cells.length; j += 2) {{
+    ctx.fillStyle = palette[cells[j + 1]];
+    ctx.fillRect((cells[j] % cols) * scale, Math.floor(cells[j] / cols) * scale, scale, scale);
+  }}
+  scrub.value = i;
+  gen.textContent = i;
+}}
+
+function start() {{
+  timer = setInterval(() => draw((current + 1) % frames.length), delay);
+  play.textContent = "Pause";
+}}
+
+function stop() {{
+  clearInterval(timer);
+  timer = null;
+  play.textContent = "Play";
+}}
+
+play.onclick = () => (timer ? stop() : start());
+scrub.oninput = () => {{ stop(); draw(Number(scrub.value)); }};
+draw(0);
+start();
+</script>
+</body>
+</html>
+"#,
+            meta = self.provenance.meta(),
+            w = n * self.scale,
+            h = m * self.scale,
+            last = self.frames.len().saturating_sub(1),
+            m = m,
+            n = n,
+            scale = self.scale,
+            delay = self.delay,
+            background = self.background,
+            palette = palette.join(","),
+            frames = frames.join(",\n"),
+        )
+    }
+
+    /// Every frame sits on top of the others and is only visible for its
+    /// share of the animation. Negative delays start each frame's animation
+    /// part way through, so that frame `i` comes up after `i` steps.
+    fn css_page(&self) -> String {
+        let (m, n) = self.size;
+        let count = self.frames.len().max(1);
+        let total = self.delay as usize * count;
+        let share = 100.0 / count as f64;
+        let mut frames = String::new();
+
+        for (i, cells) in self.frames.iter().enumerate() {
+            let shadows: Vec<String> = cells
+                .iter()
+                .map(|&(c, p)| {
+                    format!(
+                        "{}px {}px {}",
+                        (c % n + 1) * self.scale,
+                        (c / n) * self.scale,
+                        self.palette[p]
+                    )
+                })
+                .collect();
+            let shadow = if shadows.is_empty() {
+                String::from("none")
+            } else {
+                shadows.join(",")
+            };
+
+            writeln!(
+                frames,
+                "<div class=\"f\" style=\"animation-delay:-{}ms;box-shadow:{}\"></div>",
+                (count - i) % count * self.delay as usize,
+                shadow
+            )
+            .unwrap();
+        }
+
+        // Each frame's div is one cell, placed just left of the grid, so
+        // that all the shadows fall inside it.
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+{meta}<title>IronCAT</title>
+<style>
+body {{ background: #222; }}
+.grid {{ position: relative; overflow: hidden; margin: 1em auto; width: {w}px; height: {h}px; background: {background}; }}
+.f {{ position: absolute; top: 0; left: -{scale}px; width: {scale}px; height: {scale}px; visibility: hidden; animation: show {total}ms step-end infinite; }}
+@keyframes show {{ 0% {{ visibility: visible; }} {share:.4}% {{ visibility: hidden; }} }}
+</style>
+</head>
+<body>
+<div class="grid">
+{frames}</div>
+</body>
+</html>
+"#,
+            meta = self.provenance.meta(),
+            w = n * self.scale,
+            h = m * self.scale,
+            scale = self.scale,
+            background = self.background,
+            total = total,
+            share = share,
+            frames = frames,
+        )
+    }
+}
+
+impl Recorder for HtmlRecorder {
+    fn record(&mut self, theme: &Theme, matrix: &Matrix, _frame: &str) -> io::Result<()> {
+        self.size = (matrix.m, matrix.n);
+        self.background = theme.pixel(0, 0);
+
+        let mut cells = Vec::new();
+        for (i, &state) in matrix.rows.iter().enumerate() {
+            if state != 0 {
+                let color = theme.pixel(state, matrix.ages[i]);
+                let palette = &mut self.palette;
+                let index = *self.colors.entry(color).or_insert_with(|| {
+                    palette.push(color);
+                    palette.len() - 1
+                });
+                cells.push((i, index));
+            }
+        }
+
+        self.frames.push(cells);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let page = match self.player {
+            Player::Canvas => self.canvas_page(),
+            Player::Css => self.css_page(),
+        };
+
+        fs::write(&self.path, page)
+    }
+}
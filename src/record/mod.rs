@@ -0,0 +1,24 @@
+//! Recorders capture a run as it happens and write it somewhere other than
+//! the terminal.
+
+pub mod cast;
+pub mod gif;
+pub mod html;
+pub mod ndjson;
+pub mod replay;
+pub mod video;
+
+use std::io;
+
+use crate::theme::Theme;
+use crate::Matrix;
+
+/// Something that wants to see every generation.
+pub trait Recorder {
+    /// Captures one generation. `frame` is exactly what was sent to the
+    /// terminal for it.
+    fn record(&mut self, theme: &Theme, matrix: &Matrix, frame: &str) -> io::Result<()>;
+
+    /// Writes out whatever is still pending once the run is over.
+    fn finish(&mut self) -> io::Result<()>;
+}
@@ -0,0 +1,93 @@
+//! Newline-delimited JSON, one object per generation, for analysis with
+//! tools like `jq` or pandas.
+//!
+//! ```text
+//! {"generation": 3, "population": 2, "births": 1, "deaths": 0, "cells": [[0, 1], [4, 2]]}
+//! ```
+//!
+//! Cells are `[row, column]` pairs. For dense grids `rows` can be written
+//! instead, with every row run-length encoded as in RLE pattern files: `b`
+//! for dead cells, `o` for live ones, each optionally preceded by a count.
+//!
+//! The first line is the provenance instead, as `{"provenance": {...}}`.
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+use super::Recorder;
+use crate::pattern::rle::run_length;
+use crate::provenance::Provenance;
+use crate::stats::Stats;
+use crate::theme::Theme;
+use crate::Matrix;
+
+/// How the cells of a generation are written out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cells {
+    Coordinates,
+    Rows,
+}
+
+/// Writes every generation as a line of JSON.
+pub struct NdjsonRecorder {
+    out: Box<dyn Write>,
+    cells: Cells,
+    /// Written before the first generation, and then forgotten.
+    provenance: Option<Provenance>,
+}
+
+impl NdjsonRecorder {
+    pub fn new(out: Box<dyn Write>, cells: Cells, provenance: &Provenance) -> NdjsonRecorder {
+        NdjsonRecorder {
+            out,
+            cells,
+            provenance: Some(provenance.clone()),
+        }
+    }
+}
+
+impl Recorder for NdjsonRecorder {
+    fn record(&mut self, _theme: &Theme, matrix: &Matrix, _frame: &str) -> io::Result<()> {
+        if let Some(provenance) = self.provenance.take() {
+            writeln!(self.out, "{{\"provenance\": {}}}", provenance.json())?;
+        }
+
+        let stats = Stats::of(matrix);
+        let mut line = format!(
+            "{{\"generation\": {}, \"population\": {}, \"births\": {}, \"deaths\": {}, ",
+            stats.generation, stats.population, stats.births, stats.deaths
+        );
+
+        match self.cells {
+            Cells::Coordinates => {
+                line.push_str("\"cells\": [");
+                let mut first = true;
+                for (i, &state) in matrix.rows.iter().enumerate() {
+                    if state != 0 {
+                        if !first {
+                            line.push_str(", ");
+                        }
+                        write!(line, "[{}, {}]", i / matrix.n, i % matrix.n).unwrap();
+                        first = false;
+                    }
+                }
+            }
+            Cells::Rows => {
+                line.push_str("\"rows\": [");
+                let rows: Vec<String> = matrix
+                    .rows
+                    .chunks(matrix.n)
+                    .map(|row| format!("\"{}\"", run_length(row)))
+                    .collect();
+                line.push_str(&rows.join(", "));
+            }
+        }
+
+        line.push_str("]}");
+        writeln!(self.out, "{}", line)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
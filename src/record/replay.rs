@@ -0,0 +1,486 @@
+//! Recordings of whole runs, to be played back later without working out
+//! a single generation again.
+//!
+//! `--record run.icrec` writes down the world as it starts and then only
+//! the cells that changed from one frame to the next, and `iron-cat replay
+//! run.icrec` plays it back, to pause, step and scrub through at will.
+//!
+//! ```text
+//! iron-cat recording 1
+//! rule B3/S23
+//! size 23 38
+//! 0 +39 1x3 +34 1
+//! 1 +40 0 +37 1 +37 0
+//! ```
+//!
+//! Every frame is its generation and the changes run-length encoded, row by
+//! row: `+N` skips over `N` cells that stayed as they were, and `S` or
+//! `SxN` puts the next cell, or the next `N`, in state `S`. The first frame
+//! is written as changes from an empty world. Resizing the world or
+//! changing the rule mid-run writes a new `size` or `rule` line, and after
+//! a `size` the next frame starts from an empty world again. Lines starting
+//! with `#` are comments, such as the provenance under the first line.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::{error, fmt};
+
+use super::Recorder;
+use crate::provenance::Provenance;
+use crate::theme::Theme;
+use crate::{Matrix, Rulestring};
+
+/// The version of the format written, the only one read.
+pub const VERSION: u32 = 1;
+
+/// The first line of every recording, up to the version.
+const MAGIC: &str = "iron-cat recording ";
+
+/// How many frames apart whole worlds are kept when playing back, so that
+/// jumping anywhere only needs a few changes worked through.
+const KEY: usize = 256;
+
+/// Why a recording couldn't be played.
+#[derive(Debug)]
+pub enum TapeError {
+    Io(io::Error),
+    NotRecording,
+    Version(String),
+    Line(usize, String),
+    Empty,
+}
+
+impl error::Error for TapeError {}
+
+impl fmt::Display for TapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TapeError::Io(e) => write!(f, "Could not read recording: {}", e),
+            TapeError::NotRecording => write!(f, "Invalid recording: it isn't one"),
+            TapeError::Version(v) => write!(
+                f,
+                "Invalid recording: version {} where only {} can be played",
+                v, VERSION
+            ),
+            TapeError::Line(n, s) => write!(f, "Invalid line {} in recording: {}", n, s),
+            TapeError::Empty => write!(f, "Invalid recording: it has no frames"),
+        }
+    }
+}
+
+/// Writes the changes every frame makes.
+pub struct ReplayRecorder {
+    path: String,
+    provenance: Provenance,
+    file: Option<BufWriter<File>>,
+    /// The last frame written, its size and rule, to write the next as
+    /// changes from.
+    last: Vec<usize>,
+    size: (usize, usize),
+    rules: String,
+}
+
+impl ReplayRecorder {
+    /// The file is created with the first frame.
+    pub fn new(path: &str, provenance: &Provenance) -> ReplayRecorder {
+        ReplayRecorder {
+            path: path.to_string(),
+            provenance: provenance.clone(),
+            file: None,
+            last: Vec::new(),
+            size: (0, 0),
+            rules: String::new(),
+        }
+    }
+}
+
+impl Recorder for ReplayRecorder {
+    fn record(&mut self, _theme: &Theme, matrix: &Matrix, _frame: &str) -> io::Result<()> {
+        if self.file.is_none() {
+            let mut file = BufWriter::new(File::create(&self.path)?);
+            writeln!(file, "{}{}", MAGIC, VERSION)?;
+            for line in self.provenance.lines() {
+                writeln!(file, "# {}", line)?;
+            }
+            self.file = Some(file);
+        }
+        // Created above.
+        let file = self.file.as_mut().unwrap();
+
+        let rules = matrix.rules.to_string();
+        if rules != self.rules {
+            writeln!(file, "rule {}", rules)?;
+            self.rules = rules;
+        }
+        if (matrix.m, matrix.n) != self.size {
+            writeln!(file, "size {} {}", matrix.m, matrix.n)?;
+            self.size = (matrix.m, matrix.n);
+            self.last = vec![0; matrix.rows.len()];
+        }
+
+        write!(file, "{}", matrix.generation)?;
+        let mut i = 0;
+        while i < matrix.rows.len() {
+            let same = matrix.rows[i..]
+                .iter()
+                .zip(&self.last[i..])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if same > 0 {
+                if i + same == matrix.rows.len() {
+                    break;
+                }
+                write!(file, " +{}", same)?;
+                i += same;
+            }
+            let state = matrix.rows[i];
+            let run = matrix.rows[i..]
+                .iter()
+                .zip(&self.last[i..])
+                .take_while(|&(&a, &b)| a == state && a != b)
+                .count();
+            match run {
+                1 => write!(file, " {}", state)?,
+                _ => write!(file, " {}x{}", state, run)?,
+            }
+            i += run;
+        }
+        writeln!(file)?;
+
+        self.last.copy_from_slice(&matrix.rows);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        match self.file.take() {
+            Some(mut file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A stretch of a frame's changes.
+#[derive(Clone, Copy, Debug)]
+enum Run {
+    Skip(usize),
+    Set(usize, usize),
+}
+
+/// One frame of a recording.
+#[derive(Debug)]
+struct Frame {
+    generation: u64,
+    size: (usize, usize),
+    rules: Rulestring,
+    runs: Vec<Run>,
+}
+
+/// A recording read back in, and where it's up to.
+pub struct Tape {
+    frames: Vec<Frame>,
+    /// Whole worlds every so often, and at every change of size, by the
+    /// frame they're of.
+    keys: Vec<(usize, Vec<usize>)>,
+    /// The frame being shown, and its cells.
+    pub at: usize,
+    cells: Vec<usize>,
+}
+
+impl Tape {
+    pub fn load(path: &Path) -> Result<Tape, TapeError> {
+        let text = fs::read_to_string(path).map_err(TapeError::Io)?;
+        let mut lines = text.lines().enumerate().map(|(i, l)| (i + 1, l.trim()));
+
+        let version = lines
+            .next()
+            .and_then(|(_, l)| l.strip_prefix(MAGIC))
+            .ok_or(TapeError::NotRecording)?;
+        if version != VERSION.to_string() {
+            return Err(TapeError::Version(version.to_string()));
+        }
+
+        let mut frames = Vec::new();
+        let mut keys = Vec::new();
+        let mut cells: Vec<usize> = Vec::new();
+        let mut size = None;
+        let mut rules = None;
+        for (n, line) in lines {
+            let bad = || TapeError::Line(n, line.to_string());
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(rule) = line.strip_prefix("rule ") {
+                rules = Some(rule.parse::<Rulestring>().map_err(|_| bad())?);
+                continue;
+            }
+            if let Some(s) = line.strip_prefix("size ") {
+                let (m, n) = s.split_once(' ').ok_or_else(bad)?;
+                let m = m.parse::<usize>().map_err(|_| bad())?;
+                let n = n.parse::<usize>().map_err(|_| bad())?;
+                if m == 0 || n == 0 {
+                    return Err(bad());
+                }
+                size = Some((m, n));
+                cells = vec![0; m.checked_mul(n).ok_or_else(bad)?];
+                // The next frame is the first of this size.
+                keys.push((frames.len(), Vec::new()));
+                continue;
+            }
+
+            let (size, rules) = match (size, &rules) {
+                (Some(size), Some(rules)) => (size, rules.clone()),
+                _ => return Err(bad()),
+            };
+            let mut parts = line.split_whitespace();
+            let generation = parts
+                .next()
+                .and_then(|g| g.parse::<u64>().ok())
+                .ok_or_else(bad)?;
+            let runs = parts
+                .map(|part| parse_run(part).ok_or_else(bad))
+                .collect::<Result<Vec<_>, _>>()?;
+            if !apply(&mut cells, &runs) {
+                return Err(bad());
+            }
+
+            // A key just made for a new size gets the first frame of it.
+            match keys.last_mut() {
+                Some((at, key)) if *at == frames.len() => *key = cells.clone(),
+                Some((at, _)) if frames.len() - *at >= KEY => {
+                    keys.push((frames.len(), cells.clone()))
+                }
+                _ => {}
+            }
+            frames.push(Frame {
+                generation,
+                size,
+                rules,
+                runs,
+            });
+        }
+
+        if frames.is_empty() {
+            return Err(TapeError::Empty);
+        }
+
+        let mut tape = Tape {
+            frames,
+            keys,
+            at: 0,
+            cells: Vec::new(),
+        };
+        tape.cells = tape.keys[0].1.clone();
+        Ok(tape)
+    }
+
+    /// How many frames there are.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// The rule the recording starts under.
+    pub fn rules(&self) -> &Rulestring {
+        &self.frames[0].rules
+    }
+
+    /// A world at the first frame, to play the rest in.
+    pub fn matrix(&self) -> Matrix {
+        let first = &self.frames[0];
+        let mut matrix = Matrix::new(first.size.0, first.size.1, first.rules.clone());
+        self.show(&mut matrix, self.cells.clone());
+        matrix
+    }
+
+    /// Moves to another frame and shows it in the world. Returns false,
+    /// and stays put, if there's no such frame.
+    pub fn seek(&mut self, frame: usize, matrix: &mut Matrix) -> bool {
+        if frame >= self.frames.len() {
+            return false;
+        }
+
+        if frame == self.at + 1 && self.frames[frame].size == self.frames[self.at].size {
+            apply(&mut self.cells, &self.frames[frame].runs);
+        } else {
+            let k = self.keys.partition_point(|(at, _)| *at <= frame) - 1;
+            let (at, key) = &self.keys[k];
+            self.cells = key.clone();
+            for f in &self.frames[at + 1..=frame] {
+                apply(&mut self.cells, &f.runs);
+            }
+        }
+        self.at = frame;
+
+        self.show(matrix, self.cells.clone());
+        true
+    }
+
+    /// Puts the cells of the current frame in the world, ageing those that
+    /// stayed as they were and counting those that were born or died.
+    fn show(&self, matrix: &mut Matrix, cells: Vec<usize>) {
+        let frame = &self.frames[self.at];
+        if (matrix.m, matrix.n) != frame.size {
+            *matrix = Matrix::new(frame.size.0, frame.size.1, frame.rules.clone());
+        }
+
+        let (mut births, mut deaths) = (0, 0);
+        for ((age, &old), &new) in matrix.ages.iter_mut().zip(&matrix.rows).zip(&cells) {
+            match (old != 0, new != 0) {
+                (false, true) => births += 1,
+                (true, false) => deaths += 1,
+                _ => {}
+            }
+            *age = if (old != 0) == (new != 0) {
+                age.saturating_add(1)
+            } else {
+                0
+            };
+        }
+
+        matrix.rows = cells;
+        matrix.rules = frame.rules.clone();
+        matrix.generation = frame.generation;
+        matrix.births = births;
+        matrix.deaths = deaths;
+    }
+}
+
+/// Reads one stretch of changes: `+N`, `S` or `SxN`.
+fn parse_run(part: &str) -> Option<Run> {
+    if let Some(skip) = part.strip_prefix('+') {
+        return skip.parse().ok().map(Run::Skip);
+    }
+    match part.split_once('x') {
+        Some((state, count)) => Some(Run::Set(state.parse().ok()?, count.parse().ok()?)),
+        None => Some(Run::Set(part.parse().ok()?, 1)),
+    }
+}
+
+/// Makes a frame's changes to the cells of the one before. Returns false
+/// if they don't fit.
+fn apply(cells: &mut [usize], runs: &[Run]) -> bool {
+    let mut i = 0usize;
+    for &run in runs {
+        match run {
+            Run::Skip(n) => i = i.saturating_add(n),
+            Run::Set(state, n) => {
+                match cells.get_mut(i..i.saturating_add(n)) {
+                    Some(stretch) => stretch.fill(state),
+                    None => return false,
+                }
+                i += n;
+            }
+        }
+    }
+    i <= cells.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A file of its own for each test, as they run side by side.
+    fn scratch(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("iron-cat-{}-{}.icrec", std::process::id(), name))
+    }
+
+    fn load(name: &str, text: &str) -> Result<Tape, TapeError> {
+        let path = scratch(name);
+        fs::write(&path, text).unwrap();
+        let tape = Tape::load(&path);
+        fs::remove_file(&path).unwrap();
+        tape
+    }
+
+    #[test]
+    fn plays_back_what_was_recorded() {
+        let path = scratch("recorded");
+        let mut recorder = ReplayRecorder::new(path.to_str().unwrap(), &Provenance::default());
+        let theme = Theme::default();
+
+        // A glider going round long enough for a few keys, and then the
+        // world resized under it halfway.
+        let mut matrix = Matrix::new(10, 12, "B3/S23".parse().unwrap());
+        for (r, c) in [(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)] {
+            matrix.set(r, c, 1);
+        }
+        let mut worlds = Vec::new();
+        for generation in 0..3 * KEY {
+            if generation == KEY + KEY / 2 {
+                matrix.resize(9, 14);
+            }
+            recorder.record(&theme, &matrix, "").unwrap();
+            worlds.push(((matrix.m, matrix.n), matrix.generation, matrix.rows.clone()));
+            matrix.pulse();
+        }
+        recorder.finish().unwrap();
+
+        let mut tape = Tape::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(tape.len(), worlds.len());
+        assert_eq!(tape.rules().to_string(), "B3/S23");
+
+        let mut played = tape.matrix();
+        assert_eq!(played.rows, worlds[0].2);
+        // Step forward, then jump about, across keys and the resize.
+        let frames = (1..20).chain([700, 383, 384, 385, 5, 767, 255, 256, 0]);
+        for frame in frames {
+            assert!(tape.seek(frame, &mut played), "{}", frame);
+            let (size, generation, rows) = &worlds[frame];
+            assert_eq!((played.m, played.n), *size, "{}", frame);
+            assert_eq!(played.generation, *generation, "{}", frame);
+            assert_eq!(&played.rows, rows, "{}", frame);
+        }
+        assert!(!tape.seek(worlds.len(), &mut played));
+        assert_eq!(tape.at, 0);
+    }
+
+    #[test]
+    fn runs() {
+        let mut tape = load(
+            "runs",
+            "iron-cat recording 1\n# a comment\nrule B3/S23\nsize 3 3\n0 +3 1x3\n1 +1 1 +1 0 +1 0 +1 1\n",
+        )
+        .unwrap();
+        assert_eq!(tape.len(), 2);
+        assert_eq!(tape.matrix().rows, [0, 0, 0, 1, 1, 1, 0, 0, 0]);
+
+        let mut matrix = tape.matrix();
+        assert!(tape.seek(1, &mut matrix));
+        assert_eq!(matrix.rows, [0, 1, 0, 0, 1, 0, 0, 1, 0]);
+        assert_eq!((matrix.births, matrix.deaths), (2, 2));
+    }
+
+    #[test]
+    fn errors() {
+        let start = "iron-cat recording 1\nrule B3/S23\nsize 3 3\n";
+        assert!(matches!(
+            load("magic", "x = 3, y = 3\n"),
+            Err(TapeError::NotRecording)
+        ));
+        assert!(matches!(
+            load("version", "iron-cat recording 2\n"),
+            Err(TapeError::Version(v)) if v == "2"
+        ));
+        assert!(matches!(load("empty", start), Err(TapeError::Empty)));
+        assert!(matches!(
+            load("no-size", "iron-cat recording 1\nrule B3/S23\n0 1\n"),
+            Err(TapeError::Line(3, _))
+        ));
+        for (name, frame) in [("past", "0 +8 1x2"), ("run", "0 +1 1y2"), ("gen", "zero 1")] {
+            assert!(
+                matches!(
+                    load(name, &format!("{}{}\n", start, frame)),
+                    Err(TapeError::Line(4, _))
+                ),
+                "{}",
+                frame
+            );
+        }
+        assert!(matches!(
+            load("zero", "iron-cat recording 1\nsize 0 3\n"),
+            Err(TapeError::Line(2, _))
+        ));
+    }
+}
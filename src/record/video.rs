@@ -0,0 +1,105 @@
+//! Video recording through `ffmpeg`.
+//!
+//! Rather than pull in a video encoder, we start `ffmpeg` as a child process
+//! and pipe raw RGB frames to its standard input. It then picks the codec
+//! from the output file's extension, and keeps the provenance as the
+//! video's comment.
+
+use std::io::{self, Write};
+use std::process::{Child, Command, Stdio};
+
+use super::Recorder;
+use crate::provenance::Provenance;
+use crate::render::Canvas;
+use crate::theme::Theme;
+use crate::Matrix;
+
+/// Streams every generation to `ffmpeg` as one video frame.
+pub struct VideoRecorder {
+    path: String,
+    scale: usize,
+    fps: u32,
+    /// Output resolution, if it should differ from the canvas.
+    size: Option<(usize, usize)>,
+    provenance: Provenance,
+    ffmpeg: Option<Child>,
+    dimensions: (usize, usize),
+}
+
+impl VideoRecorder {
+    pub fn new(
+        path: &str,
+        scale: usize,
+        fps: u32,
+        size: Option<(usize, usize)>,
+        provenance: &Provenance,
+    ) -> VideoRecorder {
+        VideoRecorder {
+            path: path.to_string(),
+            scale,
+            fps,
+            size,
+            provenance: provenance.clone(),
+            ffmpeg: None,
+            dimensions: (0, 0),
+        }
+    }
+
+    /// Starts `ffmpeg` for frames of the given size.
+    ///
+    /// Most players want `yuv420p`, which needs even dimensions, so unless
+    /// a size was given the frame is padded by a pixel where necessary.
+    /// Given sizes are scaled to with nearest neighbour to keep cells crisp.
+    fn spawn(&self, width: usize, height: usize) -> io::Result<Child> {
+        let filter = match self.size {
+            Some((w, h)) => format!("scale={}:{}:flags=neighbor", w, h),
+            None => String::from("pad=ceil(iw/2)*2:ceil(ih/2)*2"),
+        };
+
+        Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgb24"])
+            .args(["-s", &format!("{}x{}", width, height)])
+            .args(["-r", &self.fps.to_string()])
+            .args(["-i", "-"])
+            .args(["-vf", &filter, "-pix_fmt", "yuv420p"])
+            .arg("-metadata")
+            .arg(format!("comment={}", self.provenance.lines().join("\n")))
+            .arg(&self.path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("could not start ffmpeg: {}", e)))
+    }
+}
+
+impl Recorder for VideoRecorder {
+    fn record(&mut self, theme: &Theme, matrix: &Matrix, _frame: &str) -> io::Result<()> {
+        let canvas = Canvas::new(theme, matrix, self.scale);
+
+        if self.ffmpeg.is_none() {
+            self.ffmpeg = Some(self.spawn(canvas.width, canvas.height)?);
+            self.dimensions = (canvas.width, canvas.height);
+        } else if self.dimensions != (canvas.width, canvas.height) {
+            return Err(io::Error::other("the frame size changed mid-recording"));
+        }
+
+        // We always spawn with a piped stdin.
+        let ffmpeg = self.ffmpeg.as_mut().unwrap();
+        ffmpeg.stdin.as_mut().unwrap().write_all(&canvas.rgb())
+    }
+
+    /// Closes the pipe so `ffmpeg` sees the end of the stream, then waits
+    /// for it to finish writing the file.
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some(mut ffmpeg) = self.ffmpeg.take() {
+            drop(ffmpeg.stdin.take());
+            let status = ffmpeg.wait()?;
+            if !status.success() {
+                return Err(io::Error::other(format!("ffmpeg exited with {}", status)));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+//! Inline image protocols: kitty's graphics protocol and iTerm2's `OSC 1337`.
+//!
+//! Both send the whole frame as a base64 encoded image. Kitty takes raw RGB
+//! and lets us reuse an image id, so every frame replaces the last one in
+//! place; iTerm2 wants an image file, so we hand it a PNG.
+
+use super::{base64, Canvas};
+
+/// Kitty refuses escape codes with more than this much payload.
+const CHUNK: usize = 4096;
+
+/// The id of our image, and of its single placement.
+const ID: u32 = 1;
+
+/// Encodes the canvas for the kitty graphics protocol.
+pub fn kitty(canvas: &Canvas) -> String {
+    let data = base64(&canvas.rgb());
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(CHUNK).collect();
+    let mut out = String::with_capacity(data.len() + chunks.len() * 16);
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = if i + 1 < chunks.len() { 1 } else { 0 };
+
+        if i == 0 {
+            out.push_str(&format!(
+                "\x1B_Ga=T,f=24,s={},v={},i={},p={},q=2,m={};",
+                canvas.width, canvas.height, ID, ID, more
+            ));
+        } else {
+            out.push_str(&format!("\x1B_Gm={};", more));
+        }
+
+        // Base64 is plain ASCII, so this can't fail.
+        out.push_str(std::str::from_utf8(chunk).unwrap());
+        out.push_str("\x1B\\");
+    }
+
+    out
+}
+
+/// Encodes the canvas as an iTerm2 inline image.
+pub fn iterm(canvas: &Canvas) -> String {
+    let png = canvas.png(&[]);
+
+    format!(
+        "\x1B]1337;File=inline=1;size={};width={}px;height={}px;preserveAspectRatio=1:{}\x07",
+        png.len(),
+        canvas.width,
+        canvas.height,
+        base64(&png)
+    )
+}
@@ -0,0 +1,181 @@
+//! Renderers that draw the grid as pixels instead of characters.
+//!
+//! Each of them starts from a `Canvas`, which is the grid scaled up and
+//! coloured according to the theme, and encodes it in whatever form the
+//! terminal understands.
+
+pub mod kitty;
+#[cfg(feature = "notcurses")]
+pub mod notcurses;
+pub mod sixel;
+
+use std::collections::{HashMap, HashSet};
+
+use crate::theme::{Color, Theme};
+use crate::Matrix;
+
+/// How frames are sent to the terminal.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Output {
+    /// Two characters per cell, as drawn by the `Theme`.
+    #[default]
+    Text,
+    /// Sixel graphics, one square of `scale` pixels per cell.
+    Sixel,
+    /// The kitty graphics protocol, also spoken by WezTerm.
+    Kitty,
+    /// iTerm2's inline images.
+    Iterm,
+    /// One digit per cell and no escape codes, for other programs.
+    Plain,
+    /// Cells on a notcurses plane, or pixels where notcurses can.
+    #[cfg(feature = "notcurses")]
+    Notcurses,
+}
+
+impl Output {
+    /// Whether cells are drawn as characters, which can be cropped to the
+    /// terminal and zoomed out.
+    pub fn is_text(self) -> bool {
+        match self {
+            Output::Text => true,
+            #[cfg(feature = "notcurses")]
+            Output::Notcurses => true,
+            _ => false,
+        }
+    }
+}
+
+impl std::str::FromStr for Output {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Output::Text),
+            "sixel" => Ok(Output::Sixel),
+            "kitty" => Ok(Output::Kitty),
+            "iterm" => Ok(Output::Iterm),
+            #[cfg(feature = "notcurses")]
+            "notcurses" => Ok(Output::Notcurses),
+            _ => Err(format!("Unknown output {}", s)),
+        }
+    }
+}
+
+/// An RGB image of the grid.
+#[derive(Debug)]
+pub struct Canvas {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>,
+}
+
+impl Canvas {
+    /// Paints every cell as a `scale` by `scale` square.
+    pub fn new(theme: &Theme, matrix: &Matrix, scale: usize) -> Canvas {
+        let scale = scale.max(1);
+        let width = matrix.n * scale;
+        let height = matrix.m * scale;
+        let mut pixels = Vec::with_capacity(width * height);
+
+        for y in 0..height {
+            let row = (y / scale) * matrix.n;
+            for x in 0..width {
+                let i = row + x / scale;
+                pixels.push(theme.pixel(matrix.rows[i], matrix.ages[i]));
+            }
+        }
+
+        Canvas {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// The pixels as packed 8-bit RGB triples.
+    pub fn rgb(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|c| vec![c.r, c.g, c.b])
+            .collect()
+    }
+
+    /// The canvas as a PNG file, with any notes as a UTF-8 `Comment`.
+    pub fn png(&self, notes: &[String]) -> Vec<u8> {
+        let mut out = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut out, self.width as u32, self.height as u32);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            if !notes.is_empty() {
+                // Only fails for keywords that aren't allowed.
+                encoder
+                    .add_itxt_chunk("Comment".to_string(), notes.join("\n"))
+                    .unwrap();
+            }
+
+            // Writing to a `Vec` can't fail.
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&self.rgb()).unwrap();
+        }
+        out
+    }
+
+    /// Converts the canvas to at most 256 indexed colours, as needed by
+    /// sixel and GIF. Themes with gradients can use more colours than that,
+    /// in which case everything is snapped to a 6x6x6 colour cube first.
+    pub fn indexed(&self) -> (Vec<Color>, Vec<u8>) {
+        let mut palette = Vec::new();
+        let mut registers = HashMap::new();
+        let mut indices = Vec::with_capacity(self.pixels.len());
+
+        let snap = self.pixels.iter().collect::<HashSet<_>>().len() > 256;
+
+        for &c in &self.pixels {
+            let c = if snap { cube(c) } else { c };
+            let register = *registers.entry(c).or_insert_with(|| {
+                palette.push(c);
+                (palette.len() - 1) as u8
+            });
+            indices.push(register);
+        }
+
+        (palette, indices)
+    }
+}
+
+fn cube(c: Color) -> Color {
+    let snap = |v: u8| ((v as u32 * 5 + 127) / 255 * 51) as u8;
+
+    Color {
+        r: snap(c.r),
+        g: snap(c.g),
+        b: snap(c.b),
+    }
+}
+
+/// Standard base64, which both kitty and iTerm2 want their images in.
+pub fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+
+    out
+}
@@ -0,0 +1,343 @@
+//! Drawing through notcurses, for terminals it supports.
+//!
+//! Only built with the `notcurses` feature, and linked against the system's
+//! `libnotcurses-core`. Rather than printing a string of escape codes every
+//! frame, cells are put on a notcurses plane and notcurses works out what
+//! actually needs sending. The sparkline and status bar live on a plane of
+//! their own laid over the bottom of the grid, and where the terminal can
+//! show pixels the grid is blitted as an image instead.
+//!
+//! notcurses reads the terminal's input itself, so the main loop must not
+//! wait on `term::wait` while one of these is alive.
+
+use std::ffi::CString;
+use std::io;
+use std::os::raw::{c_char, c_int, c_uint, c_void};
+use std::ptr;
+
+use crate::render::Canvas;
+use crate::stats::Stats;
+use crate::theme::{Color, Theme};
+use crate::view::View;
+use crate::Matrix;
+
+#[repr(C)]
+struct RawNotcurses {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+struct Plane {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+struct Visual {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+struct Options {
+    termtype: *const c_char,
+    loglevel: c_int,
+    margin_t: c_uint,
+    margin_r: c_uint,
+    margin_b: c_uint,
+    margin_l: c_uint,
+    flags: u64,
+}
+
+#[repr(C)]
+struct PlaneOptions {
+    y: c_int,
+    x: c_int,
+    rows: c_uint,
+    cols: c_uint,
+    userptr: *mut c_void,
+    name: *const c_char,
+    resizecb: Option<unsafe extern "C" fn(*mut Plane) -> c_int>,
+    flags: u64,
+    margin_b: c_uint,
+    margin_r: c_uint,
+}
+
+#[repr(C)]
+struct VisualOptions {
+    n: *mut Plane,
+    scaling: c_int,
+    y: c_int,
+    x: c_int,
+    begy: c_uint,
+    begx: c_uint,
+    leny: c_uint,
+    lenx: c_uint,
+    blitter: c_int,
+    flags: u64,
+    transcolor: u32,
+    pxoffy: c_uint,
+    pxoffx: c_uint,
+}
+
+const NCLOGLEVEL_SILENT: c_int = -1;
+/// We have our own handlers for Ctrl-C and SIGTERM, which finish any
+/// recordings before leaving.
+const NCOPTION_NO_QUIT_SIGHANDLERS: u64 = 0x0008;
+const NCOPTION_SUPPRESS_BANNERS: u64 = 0x0020;
+const NCBLIT_PIXEL: c_int = 6;
+const NCVISUAL_OPTION_CHILDPLANE: u64 = 0x0020;
+
+#[link(name = "notcurses-core")]
+extern "C" {
+    fn notcurses_core_init(opts: *const Options, fp: *mut c_void) -> *mut RawNotcurses;
+    fn notcurses_stop(nc: *mut RawNotcurses) -> c_int;
+    fn notcurses_stdplane(nc: *mut RawNotcurses) -> *mut Plane;
+    fn notcurses_check_pixel_support(nc: *const RawNotcurses) -> c_int;
+    fn ncpile_render(n: *mut Plane) -> c_int;
+    fn ncpile_rasterize(n: *mut Plane) -> c_int;
+    fn ncplane_create(n: *mut Plane, opts: *const PlaneOptions) -> *mut Plane;
+    fn ncplane_destroy(n: *mut Plane) -> c_int;
+    fn ncplane_dim_yx(n: *const Plane, y: *mut c_uint, x: *mut c_uint);
+    fn ncplane_erase(n: *mut Plane);
+    fn ncplane_putegc_yx(
+        n: *mut Plane,
+        y: c_int,
+        x: c_int,
+        gclust: *const c_char,
+        sbytes: *mut usize,
+    ) -> c_int;
+    fn ncplane_set_fg_rgb8(n: *mut Plane, r: c_uint, g: c_uint, b: c_uint) -> c_int;
+    fn ncplane_set_bg_rgb8(n: *mut Plane, r: c_uint, g: c_uint, b: c_uint) -> c_int;
+    fn ncplane_set_fg_default(n: *mut Plane);
+    fn ncplane_set_bg_default(n: *mut Plane);
+    fn ncvisual_from_rgb_packed(
+        rgb: *const c_void,
+        rows: c_int,
+        rowstride: c_int,
+        cols: c_int,
+        alpha: c_int,
+    ) -> *mut Visual;
+    fn ncvisual_blit(
+        nc: *mut RawNotcurses,
+        ncv: *mut Visual,
+        opts: *const VisualOptions,
+    ) -> *mut Plane;
+    fn ncvisual_destroy(ncv: *mut Visual);
+}
+
+/// A running notcurses session. Dropping it gives the terminal back.
+pub struct Notcurses {
+    nc: *mut RawNotcurses,
+    stdplane: *mut Plane,
+    /// The plane the grid was last blitted to, in pixel mode.
+    image: *mut Plane,
+    /// The plane under the grid for the footer, and the size it was made for.
+    footer: *mut Plane,
+    footer_size: (usize, usize),
+    pixels: bool,
+}
+
+impl Notcurses {
+    /// Takes over the terminal, or returns `None` if notcurses can't.
+    pub fn new() -> Option<Notcurses> {
+        let options = Options {
+            termtype: ptr::null(),
+            loglevel: NCLOGLEVEL_SILENT,
+            margin_t: 0,
+            margin_r: 0,
+            margin_b: 0,
+            margin_l: 0,
+            flags: NCOPTION_NO_QUIT_SIGHANDLERS | NCOPTION_SUPPRESS_BANNERS,
+        };
+
+        // SAFETY: the options outlive the call and a null stream means
+        // standard output.
+        let nc = unsafe { notcurses_core_init(&options, ptr::null_mut()) };
+        if nc.is_null() {
+            return None;
+        }
+
+        // SAFETY: `nc` is a live notcurses session from here on.
+        unsafe {
+            Some(Notcurses {
+                nc,
+                stdplane: notcurses_stdplane(nc),
+                image: ptr::null_mut(),
+                footer: ptr::null_mut(),
+                footer_size: (0, 0),
+                pixels: notcurses_check_pixel_support(nc) > 0,
+            })
+        }
+    }
+
+    /// The terminal size in columns and rows.
+    pub fn size(&self) -> (usize, usize) {
+        let (mut rows, mut cols) = (0, 0);
+        // SAFETY: the standard plane lives as long as the session.
+        unsafe { ncplane_dim_yx(self.stdplane, &mut rows, &mut cols) };
+        (cols as usize, rows as usize)
+    }
+
+    /// Draws one frame: the grid as cells or pixels, and the footer on top.
+    pub fn draw(
+        &mut self,
+        theme: &Theme,
+        view: &View,
+        matrix: &Matrix,
+        stats: &Stats,
+        settled: Option<&str>,
+    ) -> io::Result<()> {
+        // SAFETY: every plane used below belongs to this session, and
+        // destroyed planes are nulled before they could be used again.
+        unsafe {
+            ncplane_erase(self.stdplane);
+            if !self.image.is_null() {
+                ncplane_destroy(self.image);
+                self.image = ptr::null_mut();
+            }
+
+            if self.pixels {
+                self.blit(theme, matrix, view.scale);
+            } else {
+                self.cells(theme, view, matrix);
+            }
+
+            self.draw_footer(view.footer(matrix, stats, settled));
+
+            if ncpile_render(self.stdplane) != 0 || ncpile_rasterize(self.stdplane) != 0 {
+                return Err(io::Error::other("notcurses could not render"));
+            }
+        }
+
+        Ok(())
+    }
+
+    unsafe fn cells(&mut self, theme: &Theme, view: &View, matrix: &Matrix) {
+        let (rows, cols) = view.visible(matrix);
+
+        for (y, &r) in rows.iter().enumerate() {
+            let mut first = true;
+            for &c in &cols {
+                let i = r * matrix.n + c;
+                let (state, age) = (matrix.rows[i], matrix.ages[i]);
+                let (fg, bg) = theme.colors(state, age);
+                set_colors(self.stdplane, fg, bg);
+
+                for ch in theme.glyph(state, age).chars() {
+                    let at = if first { (y as c_int, 0) } else { (-1, -1) };
+                    put(self.stdplane, at, ch);
+                    first = false;
+                }
+            }
+        }
+        set_colors(self.stdplane, None, None);
+    }
+
+    unsafe fn blit(&mut self, theme: &Theme, matrix: &Matrix, scale: usize) {
+        let canvas = Canvas::new(theme, matrix, scale);
+        let rgb = canvas.rgb();
+        let visual = ncvisual_from_rgb_packed(
+            rgb.as_ptr() as *const c_void,
+            canvas.height as c_int,
+            (canvas.width * 3) as c_int,
+            canvas.width as c_int,
+            255,
+        );
+        if visual.is_null() {
+            return;
+        }
+
+        let options = VisualOptions {
+            n: self.stdplane,
+            scaling: 0,
+            y: 0,
+            x: 0,
+            begy: 0,
+            begx: 0,
+            leny: 0,
+            lenx: 0,
+            blitter: NCBLIT_PIXEL,
+            flags: NCVISUAL_OPTION_CHILDPLANE,
+            transcolor: 0,
+            pxoffy: 0,
+            pxoffx: 0,
+        };
+        self.image = ncvisual_blit(self.nc, visual, &options);
+        ncvisual_destroy(visual);
+    }
+
+    /// The footer gets a plane along the bottom of the terminal, made anew
+    /// whenever the terminal or the footer changes size.
+    unsafe fn draw_footer(&mut self, lines: Vec<String>) {
+        let (columns, rows) = self.size();
+        let size = (columns, lines.len().min(rows));
+
+        if size != self.footer_size && !self.footer.is_null() {
+            ncplane_destroy(self.footer);
+            self.footer = ptr::null_mut();
+        }
+        if size.1 == 0 {
+            self.footer_size = size;
+            return;
+        }
+        if self.footer.is_null() {
+            let options = PlaneOptions {
+                y: (rows - size.1) as c_int,
+                x: 0,
+                rows: size.1 as c_uint,
+                cols: columns as c_uint,
+                userptr: ptr::null_mut(),
+                name: ptr::null(),
+                resizecb: None,
+                flags: 0,
+                margin_b: 0,
+                margin_r: 0,
+            };
+            self.footer = ncplane_create(self.stdplane, &options);
+            self.footer_size = size;
+            if self.footer.is_null() {
+                return;
+            }
+        }
+
+        ncplane_erase(self.footer);
+        for (y, line) in lines.iter().take(size.1).enumerate() {
+            let line = line.trim_end_matches("\x1B[K");
+            for (x, ch) in line.chars().enumerate() {
+                let at = if x == 0 { (y as c_int, 0) } else { (-1, -1) };
+                put(self.footer, at, ch);
+            }
+        }
+    }
+}
+
+impl Drop for Notcurses {
+    fn drop(&mut self) {
+        // SAFETY: stopping destroys every plane, and nothing is used after.
+        unsafe {
+            notcurses_stop(self.nc);
+        }
+    }
+}
+
+unsafe fn set_colors(plane: *mut Plane, fg: Option<Color>, bg: Option<Color>) {
+    match fg {
+        Some(c) => {
+            ncplane_set_fg_rgb8(plane, c.r as c_uint, c.g as c_uint, c.b as c_uint);
+        }
+        None => ncplane_set_fg_default(plane),
+    }
+    match bg {
+        Some(c) => {
+            ncplane_set_bg_rgb8(plane, c.r as c_uint, c.g as c_uint, c.b as c_uint);
+        }
+        None => ncplane_set_bg_default(plane),
+    }
+}
+
+/// Puts one character at `(y, x)`, or after the last one with `(-1, -1)`.
+unsafe fn put(plane: *mut Plane, (y, x): (c_int, c_int), ch: char) {
+    let mut buf = [0; 4];
+    if let Ok(egc) = CString::new(ch.encode_utf8(&mut buf).as_bytes()) {
+        ncplane_putegc_yx(plane, y, x, egc.as_ptr(), ptr::null_mut());
+    }
+}
@@ -0,0 +1,75 @@
+//! Sixel output, understood by xterm (with `-ti vt340`), mlterm, foot,
+//! WezTerm and others.
+//!
+//! A sixel image is sent as bands six pixels tall. Within a band, every
+//! colour gets its own pass over the columns, where each character encodes
+//! which of the six pixels in that column are painted in that colour.
+
+use std::fmt::Write;
+
+use super::Canvas;
+
+/// Encodes the canvas as a complete sixel escape sequence.
+pub fn encode(canvas: &Canvas) -> String {
+    let (palette, indices) = canvas.indexed();
+    let mut out = String::from("\x1BPq");
+
+    write!(out, "\"1;1;{};{}", canvas.width, canvas.height).unwrap();
+
+    for (i, c) in palette.iter().enumerate() {
+        let pct = |v: u8| v as u32 * 100 / 255;
+        write!(out, "#{};2;{};{};{}", i, pct(c.r), pct(c.g), pct(c.b)).unwrap();
+    }
+
+    for top in (0..canvas.height).step_by(6) {
+        let rows = (canvas.height - top).min(6);
+
+        for (register, _) in palette.iter().enumerate() {
+            let band: Vec<u8> = (0..canvas.width)
+                .map(|x| {
+                    (0..rows)
+                        .filter(|dy| indices[(top + dy) * canvas.width + x] as usize == register)
+                        .fold(0, |bits, dy| bits | 1 << dy)
+                })
+                .collect();
+
+            if band.iter().all(|&bits| bits == 0) {
+                continue;
+            }
+
+            write!(out, "#{}", register).unwrap();
+            run_length(&mut out, &band);
+            out.push('$');
+        }
+
+        out.push('-');
+    }
+
+    out.push_str("\x1B\\");
+    out
+}
+
+/// Writes one colour's pass over a band, compressing repeated columns with
+/// the `!` repeat introducer.
+fn run_length(out: &mut String, band: &[u8]) {
+    let mut i = 0;
+
+    while i < band.len() {
+        let bits = band[i];
+        let mut run = 1;
+        while i + run < band.len() && band[i + run] == bits {
+            run += 1;
+        }
+
+        let c = (63 + bits) as char;
+        if run > 3 {
+            write!(out, "!{}{}", run, c).unwrap();
+        } else {
+            for _ in 0..run {
+                out.push(c);
+            }
+        }
+
+        i += run;
+    }
+}
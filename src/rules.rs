@@ -0,0 +1,114 @@
+//! Well-known rules by name, and how best to start each of them.
+//!
+//! `-r seeds` or `-r "Day & Night"` can be given in place of a rulestring,
+//! ignoring case, spaces, hyphens and ampersands. However a rule is given,
+//! if it's one of these its random soup starts the way it's usually run:
+//! Life soups at 37.5%, Seeds very sparse so that it doesn't fill the world
+//! straight away, and rules that only grow from a small seed in a square in
+//! the middle. `--density`, `--seed-region` and the like still win.
+
+use crate::Rulestring;
+
+/// A rule, its name and its soup.
+pub struct Named {
+    pub name: &'static str,
+    pub rule: &'static str,
+    /// How likely every cell of its soup is to be alive.
+    pub density: f64,
+    /// How much of the world's height and width its soup takes up, in the
+    /// middle, if not all of it.
+    pub middle: Option<f64>,
+}
+
+/// Every rule known by name.
+pub const RULES: &[Named] = &[
+    Named {
+        name: "Life",
+        rule: "B3/S23",
+        density: 0.375,
+        middle: None,
+    },
+    Named {
+        name: "HighLife",
+        rule: "B36/S23",
+        density: 0.375,
+        middle: None,
+    },
+    Named {
+        name: "Seeds",
+        rule: "B2/S",
+        density: 0.02,
+        middle: None,
+    },
+    Named {
+        name: "Day & Night",
+        rule: "B3678/S34678",
+        density: 0.5,
+        middle: None,
+    },
+    Named {
+        name: "Morley",
+        rule: "B368/S245",
+        density: 0.375,
+        middle: None,
+    },
+    Named {
+        name: "2x2",
+        rule: "B36/S125",
+        density: 0.375,
+        middle: None,
+    },
+    Named {
+        name: "Anneal",
+        rule: "B4678/S35678",
+        density: 0.5,
+        middle: None,
+    },
+    Named {
+        name: "Diamoeba",
+        rule: "B35678/S5678",
+        density: 0.5,
+        middle: Some(0.5),
+    },
+    Named {
+        name: "Maze",
+        rule: "B3/S12345",
+        density: 0.3,
+        middle: Some(0.2),
+    },
+    Named {
+        name: "Coral",
+        rule: "B3/S45678",
+        density: 0.3,
+        middle: Some(0.3),
+    },
+    Named {
+        name: "Replicator",
+        rule: "B1357/S1357",
+        density: 0.3,
+        middle: Some(0.1),
+    },
+];
+
+/// Looks a rule up by name.
+pub fn by_name(name: &str) -> Option<&'static Named> {
+    let simple = |s: &str| -> String {
+        s.chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '&'))
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    RULES
+        .iter()
+        .find(|named| simple(named.name) == simple(name))
+}
+
+/// Looks a rule up by what it does, whatever it was called.
+pub fn of(rules: &Rulestring) -> Option<&'static Named> {
+    RULES.iter().find(|named| {
+        named
+            .rule
+            .parse::<Rulestring>()
+            .is_ok_and(|r| r.b == rules.b && r.s == rules.s)
+    })
+}
@@ -0,0 +1,211 @@
+//! Scripts of what the user did during a run, to do it all again.
+//!
+//! `--record-script` writes down the world as it started, the seed for
+//! everything random that happens afterwards, and every key pressed and
+//! cell clicked along with the generation it happened in. `--play-script`
+//! starts from the same world and does the same things at the same
+//! generations, so a demo or a tutorial goes the same way every time.
+//!
+//! ```text
+//! seed 1234567
+//! world
+//! x = 3, y = 3, rule = B3/S23
+//! bob$2bo$3o!
+//! 0 key e
+//! 0 key right
+//! 0 key shift+down
+//! 0 key space
+//! 2 mouse left-down 4 7
+//! ```
+//!
+//! Keys are written as in the `[keys]` section of the config file, and are
+//! played back as those keys, so a script should be played with the keys
+//! it was recorded with. Clicks are written as the cell that was clicked
+//! rather than where it was on screen.
+
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::{error, fmt};
+
+use crossterm::event::{KeyCode, KeyModifiers, MouseButton, MouseEventKind};
+
+use crate::keys;
+use crate::pattern::rle::{self, RleError};
+use crate::pattern::Pattern;
+use crate::Rulestring;
+
+/// Why a script couldn't be played.
+#[derive(Debug)]
+pub enum ScriptError {
+    Io(io::Error),
+    World(RleError),
+    NoWorld,
+    Line(usize, String),
+}
+
+impl error::Error for ScriptError {}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Io(e) => write!(f, "Could not read script: {}", e),
+            ScriptError::World(e) => write!(f, "Invalid world in script: {}", e),
+            ScriptError::NoWorld => write!(f, "Invalid script: it has no world"),
+            ScriptError::Line(n, s) => write!(f, "Invalid line {} in script: {}", n, s),
+        }
+    }
+}
+
+/// Something the user did.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Input {
+    /// A key, and whether shift was held, which only matters for keys that
+    /// aren't characters.
+    Key(KeyCode, KeyModifiers),
+    /// The mouse did something over the cell at a row and column.
+    Mouse(MouseEventKind, usize, usize),
+}
+
+/// What the mouse can do, as written in scripts. Nothing else it does
+/// means anything.
+pub const MOUSE: &[(&str, MouseEventKind)] = &[
+    ("left-down", MouseEventKind::Down(MouseButton::Left)),
+    ("left-drag", MouseEventKind::Drag(MouseButton::Left)),
+    ("right-down", MouseEventKind::Down(MouseButton::Right)),
+    ("right-drag", MouseEventKind::Drag(MouseButton::Right)),
+    ("up", MouseEventKind::Up(MouseButton::Left)),
+    ("up", MouseEventKind::Up(MouseButton::Right)),
+    ("up", MouseEventKind::Up(MouseButton::Middle)),
+];
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Input::Key(code, modifiers) => {
+                let shift = match code {
+                    KeyCode::Char(_) => "",
+                    _ if modifiers.contains(KeyModifiers::SHIFT) => "shift+",
+                    _ => "",
+                };
+                write!(f, "key {}{}", shift, keys::name(code))
+            }
+            Input::Mouse(kind, row, col) => {
+                let name = MOUSE
+                    .iter()
+                    .find(|&&(_, k)| k == kind)
+                    .map_or("up", |&(name, _)| name);
+                write!(f, "mouse {} {} {}", name, row, col)
+            }
+        }
+    }
+}
+
+impl Input {
+    fn parse(s: &str) -> Option<Input> {
+        let (what, rest) = s.split_once(' ')?;
+        match what {
+            "key" => match rest.strip_prefix("shift+") {
+                Some(key) => keys::parse(key).map(|k| Input::Key(k, KeyModifiers::SHIFT)),
+                None => keys::parse(rest).map(|k| Input::Key(k, KeyModifiers::NONE)),
+            },
+            "mouse" => {
+                let mut parts = rest.split_whitespace();
+                let kind = parts.next()?;
+                let kind = MOUSE.iter().find(|&&(name, _)| name == kind)?.1;
+                let row = parts.next()?.parse().ok()?;
+                let col = parts.next()?.parse().ok()?;
+                Some(Input::Mouse(kind, row, col))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A script read back in, to play.
+#[derive(Debug)]
+pub struct Script {
+    /// The seed for everything random.
+    pub seed: u64,
+    /// The world as it started, and the rule it ran under.
+    pub world: Pattern,
+    pub rule: Option<String>,
+    /// What the user did, in order, and the generation they did it in.
+    pub inputs: VecDeque<(u64, Input)>,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> Result<Script, ScriptError> {
+        let text = fs::read_to_string(path).map_err(ScriptError::Io)?;
+        let mut lines = text.lines().enumerate().map(|(i, l)| (i + 1, l.trim()));
+        let bad = |(n, line): (usize, &str)| ScriptError::Line(n, line.to_string());
+
+        let mut seed = None;
+        let mut world = None;
+        let mut inputs = VecDeque::new();
+        while let Some((n, line)) = lines.next() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(s) = line.strip_prefix("seed ") {
+                seed = Some(s.trim().parse().map_err(|_| bad((n, line)))?);
+            } else if line == "world" {
+                // The world runs up to the `!` that ends it.
+                let mut rle = String::new();
+                for (_, line) in lines.by_ref() {
+                    rle.push_str(line);
+                    rle.push('\n');
+                    if line.ends_with('!') {
+                        break;
+                    }
+                }
+                world = Some(rle::read(&rle).map_err(ScriptError::World)?);
+            } else {
+                let (generation, input) = line.split_once(' ').ok_or_else(|| bad((n, line)))?;
+                let generation = generation.parse().map_err(|_| bad((n, line)))?;
+                let input = Input::parse(input).ok_or_else(|| bad((n, line)))?;
+                inputs.push_back((generation, input));
+            }
+        }
+
+        let world = world.ok_or(ScriptError::NoWorld)?;
+        Ok(Script {
+            seed: seed.unwrap_or(0),
+            world: world.pattern,
+            rule: world.rule,
+            inputs,
+        })
+    }
+}
+
+/// Writes a script as the run goes, a line at a time, so that it's all
+/// there however the run ends.
+pub struct ScriptWriter {
+    out: BufWriter<File>,
+}
+
+impl ScriptWriter {
+    /// Starts a script with the seed and the world as it starts.
+    pub fn create(
+        path: &Path,
+        seed: u64,
+        world: &Pattern,
+        rules: &Rulestring,
+    ) -> io::Result<ScriptWriter> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "# Recorded by IronCAT; play with --play-script.")?;
+        writeln!(out, "seed {}", seed)?;
+        writeln!(out, "world")?;
+        write!(out, "{}", rle::write(world, rules))?;
+        out.flush()?;
+
+        Ok(ScriptWriter { out })
+    }
+
+    /// Writes down something the user did in the given generation.
+    pub fn write(&mut self, generation: u64, input: &Input) -> io::Result<()> {
+        writeln!(self.out, "{} {}", generation, input)?;
+        self.out.flush()
+    }
+}
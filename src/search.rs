@@ -0,0 +1,108 @@
+//! Searching soups for what they settle into, as apgsearch does: `iron-cat
+//! -r life search --soups 100000 --size 16x16` runs that many random soups
+//! on every core there is, takes a census of each once it has settled and
+//! adds them all up.
+//!
+//! Soup `S` is exactly the soup the run would start from with `--rng-seed
+//! S`, so anything found can be looked at again. A world that wraps round
+//! never quite settles while gliders fly around it, so a soup counts as
+//! settled once its population goes round in circles, which gliders don't
+//! stop it from doing until they run into something.
+
+use std::collections::{HashMap, VecDeque};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+
+use crate::pattern::apgcode;
+use crate::soup::Soup;
+use crate::{Matrix, Rulestring};
+
+/// The most generations a soup gets to settle in.
+const LONGEST: u64 = 10_000;
+
+/// The longest period a settled soup's population may go round in.
+const PERIOD: usize = 30;
+
+/// How many generations in a row the population must have gone round in
+/// the same period.
+const STEADY: usize = 4 * PERIOD;
+
+/// How many of an object were found, and the first soup it was found in.
+#[derive(Clone, Copy, Debug)]
+pub struct Tally {
+    pub count: usize,
+    pub first: u64,
+}
+
+/// Runs `soups` soups, seeded from `seed` on, in worlds of `m` by `n`, and
+/// counts the objects they settle into by apgcode, most first.
+pub fn search(
+    rules: &Rulestring,
+    (m, n): (usize, usize),
+    soup: &Soup,
+    seed: u64,
+    soups: u64,
+) -> Vec<(Option<String>, Tally)> {
+    let tallies = (0..soups)
+        .into_par_iter()
+        .map(|i| {
+            let seed = seed.wrapping_add(i);
+            let mut matrix = Matrix::new(m, n, rules.clone());
+            // As the run seeds its first soup; seeding from a `StdRng`
+            // can't fail.
+            let mut rng = StdRng::from_rng(StdRng::seed_from_u64(seed)).unwrap();
+            soup.seed(&mut matrix, &mut rng);
+            settle(&mut matrix, LONGEST);
+
+            apgcode::census(&matrix)
+                .into_iter()
+                .map(|(code, count)| (code, Tally { count, first: seed }))
+                .collect::<HashMap<_, _>>()
+        })
+        .reduce(HashMap::new, |mut all, some| {
+            for (code, tally) in some {
+                all.entry(code)
+                    .and_modify(|t: &mut Tally| {
+                        t.count += tally.count;
+                        t.first = t.first.min(tally.first);
+                    })
+                    .or_insert(tally);
+            }
+            all
+        });
+
+    let mut tallies: Vec<_> = tallies.into_iter().collect();
+    tallies.sort_by(|a, b| b.1.count.cmp(&a.1.count).then_with(|| a.0.cmp(&b.0)));
+    tallies
+}
+
+/// Runs a soup until its population has gone round in circles for a
+/// while, or for `longest` generations. Returns the generation it started
+/// going round from, if it did.
+pub fn settle(matrix: &mut Matrix, longest: u64) -> Option<u64> {
+    let mut populations = VecDeque::with_capacity(STEADY + PERIOD);
+    while matrix.generation < longest {
+        matrix.pulse();
+        if populations.len() == STEADY + PERIOD {
+            populations.pop_front();
+        }
+        populations.push_back(matrix.rows.iter().filter(|&&c| c != 0).count());
+
+        // Looking only every so often is plenty.
+        if populations.len() < STEADY + PERIOD || !matrix.generation.is_multiple_of(PERIOD as u64) {
+            continue;
+        }
+        let repeats = |p: usize, from: usize| {
+            (from.max(p)..populations.len()).all(|i| populations[i] == populations[i - p])
+        };
+        if let Some(p) = (1..=PERIOD).find(|&p| repeats(p, PERIOD)) {
+            // It may have been going round since a little before.
+            let from = (0..PERIOD).find(|&from| repeats(p, from)).unwrap_or(PERIOD);
+            let back = (populations.len() - 1 - from.saturating_sub(p)) as u64;
+            return Some(matrix.generation - back);
+        }
+    }
+    None
+}
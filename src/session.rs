@@ -0,0 +1,2223 @@
+//! A run of the simulation in the terminal.
+//!
+//! A `Session` owns the worlds and everything that shows or keeps them: the
+//! theme and view, the recorders, and the link frames are sent over. It
+//! runs generations at a steady pace, and between them it listens to the
+//! terminal, so the window can be resized and the run paused, stepped
+//! through, reseeded or quit from the keyboard.
+//!
+//! In edit mode the arrow keys move a cursor around the world and space
+//! toggles the cell under it, whether or not the run is paused. Clicking a
+//! cell toggles it too, and dragging paints whatever the first cell became.
+//!
+//! Shift and the arrows, or dragging with the right button, select a
+//! rectangle of cells to copy, cut or clear. The clipboard can be turned
+//! around, pasted at the cursor and written out as RLE. `C` copies the
+//! selection to the system clipboard too and `V` pastes from it, to trade
+//! patterns with Golly. `g` stamps built-in
+//! patterns the same way, through the clipboard. `l` opens a browser of
+//! those and the user's own patterns, with a preview of each, to place one
+//! at the cursor.
+//!
+//! The last few generations and edits are kept, so `u` or `,` can go back
+//! through them and `.` forward again, and then on into new generations.
+//! Like an editor's marks, `m` and a letter keeps the worlds as they are
+//! under that letter for the rest of the session, and `'` and the letter
+//! goes back to them.
+//!
+//! Some keys ask for a line of text, such as `R` for a new rule, which is
+//! typed in the message line under the grid. `?` lists every key.
+//!
+//! A demo takes the run through its steps by itself, though the keys all
+//! still work.
+//!
+//! Breakpoints pause the run when a condition such as `population<10`
+//! starts to hold, whether given on the command line or with `b`.
+//!
+//! `D` shows or hides the dashboard, a panel of statistics beside the grid,
+//! and `E` lists what has happened lately, from the event log.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+use std::{error, fmt};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind,
+};
+
+use crate::checkpoint::{Checkpoint, CheckpointError};
+use crate::clipboard;
+use crate::clusters::ClusterLog;
+use crate::condition::Condition;
+use crate::dashboard::{self, Dashboard, Period};
+use crate::demo::{self, Demo, Rule, Step};
+use crate::diff::Diff;
+use crate::events::{self, EventLog, Events, Extrema, Kind};
+use crate::export::heatmap::Heatmap;
+use crate::export::plot::Plot;
+use crate::feed::{Fed, Feed};
+use crate::keys::{Action, Keymap};
+use crate::link::Link;
+use crate::pattern::{self, library, Format, Pattern, Transform};
+use crate::provenance::Provenance;
+use crate::record::replay::Tape;
+use crate::record::Recorder;
+#[cfg(feature = "notcurses")]
+use crate::render::notcurses::Notcurses;
+use crate::script::{self, Input, ScriptWriter};
+use crate::soup::Soup;
+use crate::stats::{self, FrameClock, History, Stats, StatsLog};
+use crate::term;
+use crate::theme::Theme;
+use crate::view::{self, View};
+use crate::watch::Watch;
+use crate::{Anchor, Matrix, Rulestring};
+
+/// Why a session ended early.
+#[derive(Debug)]
+pub enum SessionError {
+    Draw(io::Error),
+    Record(io::Error),
+    Stats(io::Error),
+    Finish(io::Error),
+    Script(io::Error),
+    Checkpoint(CheckpointError),
+}
+
+impl error::Error for SessionError {}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Draw(e) => write!(f, "Could not draw frame: {}", e),
+            SessionError::Record(e) => write!(f, "Could not record frame: {}", e),
+            SessionError::Stats(e) => write!(f, "Could not write statistics: {}", e),
+            SessionError::Finish(e) => write!(f, "Could not finish recording: {}", e),
+            SessionError::Script(e) => write!(f, "Could not write script: {}", e),
+            SessionError::Checkpoint(e) => e.fmt(f),
+        }
+    }
+}
+
+/// How a session runs, decided once from the arguments.
+#[derive(Debug)]
+pub struct Settings {
+    /// Stop after this many generations.
+    pub generations: Option<u64>,
+    /// Stop after running for this long.
+    pub duration: Option<Duration>,
+    /// Stop once the world dies out or settles into a still life, an
+    /// oscillator or a spaceship.
+    pub until_stable: bool,
+    /// Only show every this many generations.
+    pub every: u64,
+    /// The time between generations.
+    pub period: Duration,
+    /// How to make a soup when reseeding.
+    pub soup: Soup,
+    /// Frames are rows of digits for other programs, with no escape codes.
+    pub plain: bool,
+    /// Nothing is drawn at all, as standard output carries a stream.
+    pub headless: bool,
+    /// Someone is watching, so generations are paced and keys are read.
+    pub paced: bool,
+    /// The text view is cropped to the terminal.
+    pub crop: bool,
+    /// The world is sized to the terminal, and resized along with it.
+    pub fit: bool,
+    /// Where `w` writes the clipboard.
+    pub clipboard: PathBuf,
+    /// How many generations and edits can be undone.
+    pub rewind: usize,
+    /// Where the user keeps patterns of their own, for the browser.
+    pub library: Option<PathBuf>,
+    /// Where the old world goes when the world is resized.
+    pub anchor: Anchor,
+}
+
+/// What every action does, for the help screen, with actions that go
+/// together on one line.
+const HELP: &[(&[Action], &str)] = &[
+    (&[Action::Pause], "pause or carry on"),
+    (&[Action::Step], "run one generation"),
+    (&[Action::Back], "go back a generation or undo an edit"),
+    (&[Action::Forward], "go forward again"),
+    (
+        &[Action::Rewind, Action::Skip],
+        "go back or forward ten generations, or a tenth of a recording",
+    ),
+    (&[Action::Mark], "and a letter: bookmark how things are"),
+    (&[Action::Jump], "and a letter: go back to a bookmark"),
+    (
+        &[Action::Diff],
+        "and a letter: show what differs from a bookmark",
+    ),
+    (&[Action::Reseed], "reseed as densely as before"),
+    (&[Action::Density], "reseed at a new random density"),
+    (&[Action::Clear], "clear everything"),
+    (&[Action::Rule], "change the rule"),
+    (&[Action::Resize], "resize the world, keeping what's in it"),
+    (&[Action::Break], "pause when a condition starts to hold"),
+    (&[Action::Edit], "edit, or stop editing"),
+    (
+        &[Action::Up, Action::Down, Action::Left, Action::Right],
+        "move the cursor; with shift, select",
+    ),
+    (&[Action::Toggle], "toggle the cell under the cursor"),
+    (&[Action::Inspect], "inspect the cell under the cursor"),
+    (
+        &[Action::Copy, Action::Cut, Action::Erase],
+        "copy, cut or clear the selection",
+    ),
+    (&[Action::Paste], "paste at the cursor"),
+    (
+        &[Action::SystemCopy, Action::SystemPaste],
+        "copy the selection to the system clipboard as RLE, or paste from it",
+    ),
+    (
+        &[Action::Stamp],
+        "stamp a glider, or whatever is picked next",
+    ),
+    (&[Action::NextStamp], "pick the next pattern to stamp"),
+    (
+        &[Action::Library],
+        "browse the pattern library and place one",
+    ),
+    (
+        &[Action::Rotate, Action::Flip],
+        "rotate or flip the clipboard or stamp",
+    ),
+    (&[Action::Export], "save the clipboard as RLE"),
+    (&[Action::Save], "save the live cells as RLE"),
+    (&[Action::Dashboard], "show or hide the dashboard"),
+    (&[Action::Events], "list what has happened lately"),
+    (&[Action::Help], "this help"),
+    (&[Action::Quit], "quit"),
+];
+
+/// Both worlds as they were at some point, to go back to.
+type Snapshot = (Matrix, Option<Matrix>);
+
+/// A pattern file being watched, how it was turned and where it went,
+/// unless in the middle.
+pub type Source = (Watch, Vec<Transform>, Option<(usize, usize)>);
+
+/// What a line typed at the prompt is for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Question {
+    Rule,
+    Break,
+    Size,
+}
+
+impl Question {
+    fn label(self) -> &'static str {
+        match self {
+            Question::Rule => "New rule",
+            Question::Break => "Break when (empty clears)",
+            Question::Size => "New size, rows x columns",
+        }
+    }
+}
+
+/// A line being typed, and what it's for.
+#[derive(Debug)]
+struct Prompt {
+    question: Question,
+    text: String,
+}
+
+/// How many patterns the browser lists at once, and how big their previews
+/// can be.
+const LISTED: usize = 8;
+const PREVIEW: (usize, usize) = (7, 16);
+
+/// The pattern library being browsed: every pattern there is to place,
+/// built-in ones first, and which of them is chosen.
+struct Browser {
+    patterns: Vec<(String, Result<Pattern, String>)>,
+    chosen: usize,
+}
+
+/// What a demo is waiting for before its next step: a generation, and
+/// then how long to pause for, or the end of a pause.
+#[derive(Clone, Copy, Debug)]
+enum Hold {
+    Generation(u64, Duration),
+    Until(Instant),
+}
+
+/// How long to leave between inputs played from a script.
+const REPLAY: Duration = Duration::from_millis(150);
+
+/// How many of the latest events `E` lists.
+const EVENTS: usize = 20;
+
+/// How often to look at the files being watched for changes.
+const WATCH: Duration = Duration::from_millis(250);
+
+/// What the main loop should do after the user has done something.
+enum Flow {
+    Wait,
+    Step,
+    Quit,
+}
+
+pub struct Session {
+    pub theme: Theme,
+    pub view: View,
+    pub matrix: Matrix,
+    /// The world that runs the second rule, when comparing.
+    pub rival: Option<Matrix>,
+    pub recorders: Vec<Box<dyn Recorder>>,
+    pub link: Link,
+    #[cfg(feature = "notcurses")]
+    pub notcurses: Option<Notcurses>,
+    pub settings: Settings,
+    /// No generations run while paused, unless stepped through one at a
+    /// time.
+    pub paused: bool,
+    /// Which keys do what.
+    pub keys: Keymap,
+    /// Where everything random comes from, seeded so scripts can be played
+    /// back.
+    pub rng: StdRng,
+    /// Where what the user does is written down, and what a script being
+    /// played still has to do, with the generations to do it in.
+    pub script: Option<ScriptWriter>,
+    pub replay: VecDeque<(u64, Input)>,
+    /// When the next input from the script may be played, so that it can
+    /// be followed on screen.
+    replay_at: Instant,
+    /// The demo being shown, if any, and what it's waiting for.
+    pub demo: Option<Demo>,
+    hold: Option<Hold>,
+    /// The panel of statistics, and what it keeps track of.
+    pub dashboard: Dashboard,
+    /// What has happened, for `E`, and where it's all written, with what
+    /// has still to be written there.
+    events: Events,
+    pub event_log: Option<EventLog>,
+    unlogged: Vec<events::Event>,
+    extrema: Extrema,
+    /// How many generations apart to write checkpoints, and where.
+    pub checkpoint: Option<(u64, PathBuf)>,
+    /// What else checkpoints keep, so they open as projects the way the
+    /// run was shown.
+    pub project: Vec<(String, String)>,
+    /// What the run was started from, written into every file it saves.
+    pub provenance: Provenance,
+    /// How many generations apart to add fresh soup, and whether to keep
+    /// what's alive when doing so rather than starting over.
+    pub reseed_every: Option<(u64, bool)>,
+    /// How likely every cell is to flip after each generation, and how many
+    /// random cells to bring to life, as mutation or thermal noise.
+    pub noise: f64,
+    pub noise_births: usize,
+    /// Frames fed in from outside, and how dark a pixel must be to bring a
+    /// cell to life.
+    pub feed: Option<(Feed, u8)>,
+    /// Where the numbers of every generation are written.
+    pub stats_log: Option<StatsLog>,
+    /// Where the sizes of the world's clusters are written every so often.
+    pub cluster_log: Option<ClusterLog>,
+    /// How many times every cell has changed, written out when the run
+    /// ends.
+    pub heatmap: Option<Heatmap>,
+    /// The population of every generation, drawn as a chart when the run
+    /// ends.
+    pub plot: Option<Plot>,
+    /// The second world started out as the first with the cell at this
+    /// row and column flipped, so how many cells they differ in says how far
+    /// the damage has spread.
+    pub twin: Option<(usize, usize)>,
+    /// How long the run went on for before it was resumed from a
+    /// checkpoint, and when it started this time.
+    pub elapsed: Duration,
+    started: Instant,
+    /// When the run is to stop, if it's only to go on for so long.
+    deadline: Option<Instant>,
+    /// The recording being played back, in place of running any
+    /// generations.
+    pub tape: Option<Tape>,
+    /// The pattern file the run started from and the rule file, to be
+    /// read again whenever they change, and when they were last looked at.
+    pub pattern_file: Option<Source>,
+    pub rule_file: Option<Watch>,
+    watched: Instant,
+    /// When the next generation should run.
+    due: Instant,
+    /// The state being painted while the mouse is dragged.
+    brush: Option<usize>,
+    /// The cells last copied or cut, to be pasted.
+    clipboard: Option<Pattern>,
+    /// The clipboard shared with other programs, such as Golly.
+    system: clipboard::System,
+    /// The built-in pattern `g` stamps, and whether it's in the clipboard,
+    /// where it can be turned around before stamping it.
+    stamp: usize,
+    stamped: bool,
+    /// Where we have been, oldest first, and where we went before going
+    /// back, latest first.
+    past: VecDeque<Snapshot>,
+    future: Vec<Snapshot>,
+    /// Bookmarked worlds by letter, and the key waiting for a letter
+    /// after it, `m` to make one or `'` to go back to one.
+    marks: BTreeMap<char, Snapshot>,
+    marking: Option<Action>,
+    prompt: Option<Prompt>,
+    /// Conditions to pause at, and whether each held last generation, as
+    /// they only pause the run when they start to hold.
+    breaks: Vec<(Condition, bool)>,
+    /// A breakpoint was hit with nobody there to carry on from it.
+    halted: bool,
+    browser: Option<Browser>,
+    /// Where the second world starts on screen, when comparing.
+    divide: usize,
+    stats: Stats,
+    rival_stats: Option<Stats>,
+    history: History,
+    rival_history: History,
+    clock: FrameClock,
+}
+
+impl Session {
+    pub fn new(
+        theme: Theme,
+        view: View,
+        matrix: Matrix,
+        rival: Option<Matrix>,
+        recorders: Vec<Box<dyn Recorder>>,
+        link: Link,
+        settings: Settings,
+    ) -> Session {
+        let mut session = Session {
+            // The dashboard's graph may need more history than the
+            // sparkline.
+            history: History::new(view.sparkline.max(dashboard::WIDTH)),
+            rival_history: History::new(view.sparkline.max(dashboard::WIDTH)),
+            stats: Stats::of(&matrix),
+            rival_stats: rival.as_ref().map(Stats::of),
+            theme,
+            view,
+            matrix,
+            rival,
+            recorders,
+            link,
+            #[cfg(feature = "notcurses")]
+            notcurses: None,
+            settings,
+            paused: false,
+            keys: Keymap::default(),
+            rng: StdRng::from_entropy(),
+            script: None,
+            replay: VecDeque::new(),
+            replay_at: Instant::now(),
+            demo: None,
+            hold: None,
+            dashboard: Dashboard::new(false),
+            events: Events::default(),
+            event_log: None,
+            unlogged: Vec::new(),
+            extrema: Extrema::default(),
+            checkpoint: None,
+            project: Vec::new(),
+            provenance: Provenance::default(),
+            reseed_every: None,
+            noise: 0.0,
+            noise_births: 0,
+            feed: None,
+            stats_log: None,
+            cluster_log: None,
+            heatmap: None,
+            plot: None,
+            twin: None,
+            elapsed: Duration::ZERO,
+            started: Instant::now(),
+            deadline: None,
+            tape: None,
+            pattern_file: None,
+            rule_file: None,
+            watched: Instant::now(),
+            due: Instant::now(),
+            brush: None,
+            clipboard: None,
+            system: clipboard::System::default(),
+            stamp: 0,
+            stamped: false,
+            past: VecDeque::new(),
+            future: Vec::new(),
+            marks: BTreeMap::new(),
+            marking: None,
+            prompt: None,
+            breaks: Vec::new(),
+            halted: false,
+            browser: None,
+            divide: 0,
+            clock: FrameClock::new(),
+        };
+
+        session.clock.tick();
+        session.history.push(&mut session.stats);
+        if let Some(s) = session.rival_stats.as_mut() {
+            session.rival_history.push(s);
+        }
+
+        session
+    }
+
+    /// Pauses the run whenever `condition` starts to hold. Conditions that
+    /// already hold wait until they stop holding first.
+    pub fn break_when(&mut self, condition: Condition) {
+        let held = condition.holds(&self.stats, self.dashboard.period);
+        self.breaks.push((condition, held));
+    }
+
+    /// Runs until we run out of generations or time, the user quits or
+    /// we're asked to stop, then puts the terminal back and finishes the recordings.
+    ///
+    /// Generations are timed from when the last one was due rather than
+    /// from when it was drawn, so slow drawing doesn't slow everything down.
+    pub fn run(&mut self) -> Result<(), SessionError> {
+        self.start()?;
+        self.due = Instant::now() + self.settings.period;
+        self.deadline = self.settings.duration.map(|d| Instant::now() + d);
+
+        while self
+            .settings
+            .generations
+            .is_none_or(|g| self.matrix.generation < g)
+        {
+            if self.deadline.is_some_and(|d| Instant::now() >= d) {
+                break;
+            }
+            if self.settings.paced && !self.wait()? {
+                break;
+            }
+            if !term::running() {
+                break;
+            }
+            // Without a keyboard nobody could scrub back from the end.
+            if !self.settings.paced && self.tape.as_ref().is_some_and(|t| t.at + 1 >= t.len()) {
+                break;
+            }
+            self.step()?;
+            self.due = (self.due + self.settings.period).max(Instant::now());
+            if self.halted {
+                break;
+            }
+            if self.settings.until_stable && self.dashboard.period != Period::Changing {
+                break;
+            }
+        }
+
+        self.finish()
+    }
+
+    /// How the run ended, for `--summary`, as text or JSON.
+    pub fn summary(&self, as_json: bool) -> String {
+        self.dashboard.summary(&self.stats, self.damage(), as_json)
+    }
+
+    /// How many cells the twin differs from the world in, if there's a
+    /// twin.
+    fn damage(&self) -> Option<usize> {
+        match &self.rival {
+            Some(twin) if self.twin.is_some() => Some(stats::damage(&self.matrix, twin)),
+            _ => None,
+        }
+    }
+
+    /// Whether notcurses is drawing instead of us.
+    #[cfg(feature = "notcurses")]
+    fn native(&self) -> bool {
+        self.notcurses.is_some()
+    }
+
+    #[cfg(not(feature = "notcurses"))]
+    fn native(&self) -> bool {
+        false
+    }
+
+    /// The escape codes that clear the screen and send the cursor home,
+    /// neither of which plain output gets.
+    fn escapes(&self) -> (&'static str, &'static str) {
+        if self.settings.plain {
+            ("", "")
+        } else {
+            ("\x1B[2J", "\x1B[H")
+        }
+    }
+
+    /// The room one world gets in a terminal of the given size. Two worlds
+    /// share it between them, after the dashboard has taken its share.
+    fn pane(&self, size: (usize, usize)) -> (usize, usize) {
+        let size = match self.docked() {
+            Some(_) => dashboard::room(size),
+            None => size,
+        };
+        if self.rival.is_some() {
+            view::halve(size)
+        } else {
+            size
+        }
+    }
+
+    /// The size of the terminal, if the dashboard is to be drawn in it.
+    /// Only text drawn by us has room for it.
+    fn docked(&self) -> Option<(usize, usize)> {
+        if self.dashboard.shown && self.settings.crop && !self.native() {
+            term::size()
+        } else {
+            None
+        }
+    }
+
+    /// Draws both worlds, next to each other when comparing, and the
+    /// dashboard beside or under them.
+    fn frame(&mut self) -> String {
+        self.view.paused = self.paused;
+        let report = self.dashboard.report();
+        let frame = self
+            .view
+            .draw(&self.theme, &self.matrix, &self.stats, report.as_deref());
+        self.divide = frame.lines().map(view::visible_width).max().unwrap_or(0)
+            + view::DIVIDER.chars().count();
+        // The dashboard only watches the first world, so the rival's status
+        // bar says nothing of settling. A twin's says how far the damage has
+        // spread instead.
+        let damage = self.damage().map(|d| format!("damage {}", d));
+        let mut frame = match (&self.rival, &self.rival_stats) {
+            (Some(r), Some(s)) => view::side_by_side(
+                &frame,
+                &self.view.draw(&self.theme, r, s, damage.as_deref()),
+            ),
+            _ => frame,
+        };
+
+        match self.docked() {
+            Some((columns, rows)) if dashboard::beside(columns) => {
+                // The line under the frame is kept for the cursor.
+                let panel = self.dashboard.panel(&self.matrix, &self.stats, rows - 1);
+                view::side_by_side(&frame, &panel.join("\n"))
+            }
+            Some((columns, _)) => {
+                for line in self.dashboard.strip(&self.matrix, &self.stats, columns) {
+                    frame.push_str(&line);
+                    frame.push('\n');
+                }
+                frame
+            }
+            None => frame,
+        }
+    }
+
+    /// Draws and records the first generation, on a clean screen.
+    fn start(&mut self) -> Result<(), SessionError> {
+        self.watch()?;
+        let (clear, _) = self.escapes();
+        let frame = format!("{}{}\n", clear, self.frame());
+
+        if !self.settings.headless && !self.native() {
+            print!("{}", frame);
+        }
+        #[cfg(feature = "notcurses")]
+        if let Some(nc) = self.notcurses.as_mut() {
+            let report = self.dashboard.report();
+            nc.draw(
+                &self.theme,
+                &self.view,
+                &self.matrix,
+                &self.stats,
+                report.as_deref(),
+            )
+            .map_err(SessionError::Draw)?;
+        }
+
+        let damage = self.damage();
+        if let Some(log) = self.stats_log.as_mut() {
+            log.write(&self.stats, &self.matrix, damage)
+                .map_err(SessionError::Stats)?;
+        }
+        if let Some(log) = self.cluster_log.as_mut() {
+            log.write(&self.matrix).map_err(SessionError::Stats)?;
+        }
+        if let Some(plot) = self.plot.as_mut() {
+            plot.add(&self.stats);
+        }
+        self.record(&frame)
+    }
+
+    /// Runs one generation in each world, or plays the next frame of a
+    /// recording, then draws and records it.
+    fn step(&mut self) -> Result<(), SessionError> {
+        match self.tape.as_mut() {
+            Some(tape) => {
+                if !tape.seek(tape.at + 1, &mut self.matrix) {
+                    self.paused = true;
+                    self.tell("End of the recording".to_string());
+                    return self.update();
+                }
+            }
+            None => {
+                self.remember();
+                self.matrix.pulse();
+                if let Some(r) = self.rival.as_mut() {
+                    r.pulse();
+                }
+                self.inject();
+                self.perturb();
+                if let Some((every, keep)) = self.reseed_every {
+                    if self.matrix.generation.is_multiple_of(every) {
+                        if keep {
+                            self.sow();
+                        } else {
+                            self.reseed(None);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(heatmap) = self.heatmap.as_mut() {
+            heatmap.add(&self.matrix);
+        }
+        self.stats = Stats::of(&self.matrix);
+        self.stats.fps = self.clock.tick();
+        self.history.push(&mut self.stats);
+        self.watch()?;
+        let damage = self.damage();
+        if let Some(log) = self.stats_log.as_mut() {
+            log.write(&self.stats, &self.matrix, damage)
+                .map_err(SessionError::Stats)?;
+        }
+        if let Some(log) = self.cluster_log.as_mut() {
+            log.write(&self.matrix).map_err(SessionError::Stats)?;
+        }
+        if let Some(plot) = self.plot.as_mut() {
+            plot.add(&self.stats);
+        }
+        if let Some((every, _)) = self.checkpoint {
+            if self.matrix.generation.is_multiple_of(every) {
+                self.save_checkpoint()?;
+            }
+        }
+        let fps = self.stats.fps;
+        let history = &mut self.rival_history;
+        self.rival_stats = self.rival.as_ref().map(|r| {
+            let mut s = Stats {
+                fps,
+                ..Stats::of(r)
+            };
+            history.push(&mut s);
+            s
+        });
+        self.check_breaks();
+
+        if !self.matrix.generation.is_multiple_of(self.settings.every) {
+            return Ok(());
+        }
+
+        let (_, home) = self.escapes();
+        let body = self.frame();
+        let frame = format!("{}{}\n", home, body);
+
+        #[cfg(feature = "notcurses")]
+        if let Some(nc) = self.notcurses.as_mut() {
+            let report = self.dashboard.report();
+            nc.draw(
+                &self.theme,
+                &self.view,
+                &self.matrix,
+                &self.stats,
+                report.as_deref(),
+            )
+            .map_err(SessionError::Draw)?;
+            return self.record(&frame);
+        }
+        if self.settings.plain {
+            if !self.settings.headless {
+                print!("{}", frame);
+            }
+        } else {
+            self.link.send(&body).map_err(SessionError::Draw)?;
+        }
+
+        self.record(&frame)
+    }
+
+    /// Reads the rule file and the pattern file again if either has been
+    /// saved since. A new pattern starts the world over, or is stamped at
+    /// the cursor while editing, and a new rule starts the pattern over
+    /// under it. Files that can't be read leave things as they are.
+    fn reload(&mut self) -> Result<(), SessionError> {
+        let rule = self.rule_file.as_mut().is_some_and(Watch::changed);
+        let changed = self
+            .pattern_file
+            .as_mut()
+            .is_some_and(|(watch, _, _)| watch.changed());
+        if !rule && !changed {
+            return Ok(());
+        }
+
+        if let Some(watch) = self.rule_file.as_ref().filter(|_| rule) {
+            let path = watch.path();
+            match crate::rule_file(path) {
+                Ok(rules) => {
+                    self.remember();
+                    self.matrix.rules = rules;
+                    self.tell_as(Kind::Rule, format!("Rule changed to {}", self.matrix.rules));
+                }
+                Err(e) => {
+                    self.view.message = Some(format!("Could not reload {}: {}", path.display(), e));
+                    return self.update();
+                }
+            }
+        }
+
+        let (watch, transforms, at) = match &self.pattern_file {
+            Some(file) => file,
+            None => return self.update(),
+        };
+        let read = fs::read_to_string(watch.path())
+            .map_err(|e| e.to_string())
+            .and_then(|text| pattern::read(&text).map_err(|e| e.to_string()));
+        let mut loaded = match read {
+            Ok(file) => file.pattern,
+            Err(e) => {
+                self.view.message = Some(format!(
+                    "Could not reload {}: {}",
+                    watch.path().display(),
+                    e
+                ));
+                return self.update();
+            }
+        };
+        for &transform in transforms {
+            loaded = loaded.transform(transform);
+        }
+        let name = watch.path().display().to_string();
+
+        if changed && self.view.cursor.is_some() {
+            self.clipboard = Some(loaded);
+            self.stamped = false;
+            self.paste();
+            return self.update();
+        }
+
+        let at = at.unwrap_or((
+            self.matrix.m.saturating_sub(loaded.height) / 2,
+            self.matrix.n.saturating_sub(loaded.width) / 2,
+        ));
+        self.remember();
+        self.matrix = Matrix::new(self.matrix.m, self.matrix.n, self.matrix.rules.clone());
+        loaded.paste(&mut self.matrix, at);
+        self.refresh();
+        self.tell(format!(
+            "Started over from {} under {}",
+            name, self.matrix.rules
+        ));
+        self.update()
+    }
+
+    /// Pauses if any breakpoint has just started to hold, saying which.
+    /// Nobody could carry on from a pause without the keyboard, so then
+    /// the run stops there instead.
+    fn check_breaks(&mut self) {
+        let mut hit = None;
+        for (condition, held) in self.breaks.iter_mut() {
+            let holds = condition.holds(&self.stats, self.dashboard.period);
+            if holds && !*held && hit.is_none() {
+                hit = Some(condition.to_string());
+            }
+            *held = holds;
+        }
+
+        let Some(condition) = hit else {
+            return;
+        };
+        let message = format!(
+            "Stopped at generation {}: {}",
+            self.matrix.generation, condition
+        );
+        if self.settings.paced {
+            self.paused = true;
+            self.tell(message);
+        } else {
+            self.halted = true;
+            eprintln!("{}", message);
+        }
+    }
+
+    /// Draws the current generation again, after something other than a new
+    /// generation has changed what should be on screen.
+    fn redraw(&mut self) -> Result<(), SessionError> {
+        #[cfg(feature = "notcurses")]
+        if let Some(nc) = self.notcurses.as_mut() {
+            self.view.paused = self.paused;
+            let report = self.dashboard.report();
+            return nc
+                .draw(
+                    &self.theme,
+                    &self.view,
+                    &self.matrix,
+                    &self.stats,
+                    report.as_deref(),
+                )
+                .map_err(SessionError::Draw);
+        }
+
+        let (clear, home) = self.escapes();
+        print!("{}{}{}", clear, home, self.frame());
+        let _ = io::stdout().flush();
+        self.link.reset();
+
+        Ok(())
+    }
+
+    /// Draws the current generation over the last frame, after the user
+    /// has changed something that doesn't move the frame around.
+    fn update(&mut self) -> Result<(), SessionError> {
+        if self.settings.plain || self.native() {
+            return self.redraw();
+        }
+
+        let body = self.frame();
+        self.link.send(&body).map_err(SessionError::Draw)
+    }
+
+    /// Hands a frame to every recorder, giving up on the first that fails.
+    fn record(&mut self, frame: &str) -> Result<(), SessionError> {
+        for recorder in self.recorders.iter_mut() {
+            recorder
+                .record(&self.theme, &self.matrix, frame)
+                .map_err(SessionError::Record)?;
+        }
+
+        Ok(())
+    }
+
+    /// Puts the terminal back and finishes every recording.
+    fn finish(&mut self) -> Result<(), SessionError> {
+        term::leave();
+        #[cfg(feature = "notcurses")]
+        {
+            self.notcurses = None;
+        }
+
+        // The run can be carried on from wherever it ended.
+        self.save_checkpoint()?;
+
+        for recorder in self.recorders.iter_mut() {
+            recorder.finish().map_err(SessionError::Finish)?;
+        }
+        if let Some(log) = self.stats_log.as_mut() {
+            log.finish().map_err(SessionError::Stats)?;
+        }
+        if let Some(log) = self.cluster_log.as_mut() {
+            log.finish().map_err(SessionError::Stats)?;
+        }
+        self.write_events()?;
+        if let Some(log) = self.event_log.as_mut() {
+            log.finish().map_err(SessionError::Stats)?;
+        }
+        if let Some(heatmap) = &self.heatmap {
+            heatmap
+                .write(&self.provenance)
+                .map_err(SessionError::Finish)?;
+        }
+        if let Some(plot) = &self.plot {
+            plot.write(&self.provenance).map_err(SessionError::Finish)?;
+        }
+
+        Ok(())
+    }
+
+    /// Waits until the next generation is due, dealing with whatever the
+    /// user does in the meantime. While paused it is never due, unless the
+    /// user steps. Returns false if it's time to stop.
+    ///
+    /// notcurses reads the terminal itself, so then we can only sleep.
+    fn wait(&mut self) -> Result<bool, SessionError> {
+        if self.native() {
+            term::sleep(self.deadline.map_or(self.due, |d| self.due.min(d)));
+            return Ok(term::running());
+        }
+
+        loop {
+            if !self.direct()? {
+                return Ok(false);
+            }
+
+            let now = Instant::now();
+            // Time runs out whether paused or not.
+            if self.deadline.is_some_and(|d| now >= d) {
+                return Ok(false);
+            }
+            let watching = self.pattern_file.is_some() || self.rule_file.is_some();
+            if watching && now >= self.watched + WATCH {
+                self.watched = now;
+                self.reload()?;
+            }
+
+            let due = if self.paused {
+                now + self.settings.period
+            } else {
+                self.due
+            };
+            let due = match self.hold {
+                Some(Hold::Until(end)) => due.min(end),
+                _ => due,
+            };
+
+            // No generation runs while the script has something to do
+            // before it.
+            let scripted = self
+                .replay
+                .front()
+                .is_some_and(|&(g, _)| g <= self.matrix.generation);
+            if scripted && now >= self.replay_at {
+                let (_, input) = self.replay.pop_front().unwrap();
+                self.replay_at = now + REPLAY;
+                match self.input(input)? {
+                    Flow::Wait => continue,
+                    Flow::Step => return Ok(true),
+                    Flow::Quit => return Ok(false),
+                }
+            }
+
+            // Waking up to look at the files isn't a reason to step.
+            let wake = if watching {
+                due.min(self.watched + WATCH)
+            } else {
+                due
+            };
+            let wake = self.deadline.map_or(wake, |d| wake.min(d));
+            match term::wait(if scripted { self.replay_at } else { wake }) {
+                Some(event) => match self.handle(event)? {
+                    Flow::Wait => {}
+                    Flow::Step => return Ok(true),
+                    Flow::Quit => return Ok(false),
+                },
+                None if !term::running() => return Ok(false),
+                None if !self.paused && !scripted && Instant::now() >= due => return Ok(true),
+                None => {}
+            }
+        }
+    }
+
+    /// Takes the demo's next steps, if it's done waiting. Returns false
+    /// once the demo is over and it's time to stop.
+    fn direct(&mut self) -> Result<bool, SessionError> {
+        if self.demo.is_none() {
+            return Ok(true);
+        }
+
+        let mut changed = false;
+        loop {
+            match self.hold {
+                Some(Hold::Generation(g, pause)) if self.matrix.generation >= g => {
+                    self.hold = None;
+                    if !pause.is_zero() {
+                        self.paused = true;
+                        self.hold = Some(Hold::Until(Instant::now() + pause));
+                        changed = true;
+                    }
+                    continue;
+                }
+                Some(Hold::Until(end)) if Instant::now() >= end => self.hold = None,
+                Some(_) => break,
+                None => {}
+            }
+
+            match self.demo.as_mut().and_then(Demo::step) {
+                Some(step) => self.take(step),
+                None => return Ok(false),
+            }
+            changed = true;
+        }
+
+        if changed {
+            self.update()?;
+        }
+        Ok(true)
+    }
+
+    /// Does everything a step of the demo says, then waits for as long as
+    /// it says, if at all.
+    fn take(&mut self, step: Step) {
+        if let Some(Rule::Given(rules)) = &step.rule {
+            self.matrix.rules = rules.clone();
+            self.tell_as(Kind::Rule, format!("Rule changed to {}", rules));
+        }
+        if let Some((name, pattern)) = step.pattern {
+            self.clear();
+            self.tell(name);
+            let at = (
+                self.matrix.m.saturating_sub(pattern.height) / 2,
+                self.matrix.n.saturating_sub(pattern.width) / 2,
+            );
+            self.change(|matrix| pattern.paste(matrix, at));
+        }
+        if let Some(density) = step.reseed {
+            self.reseed(Some(density));
+        }
+        // Random rules are judged on the world the step starts from.
+        if let Some(Rule::Random) = step.rule {
+            let judge = self.demo.as_ref().and_then(|d| d.judge);
+            let drawn = demo::draw(&self.matrix, &mut self.rng, judge);
+            self.matrix.rules = drawn.rules;
+            self.tell_as(
+                Kind::Rule,
+                match drawn.score {
+                    Some(score) => format!(
+                        "Random rule {}, scoring {:.2} after {} skipped",
+                        self.matrix.rules, score, drawn.skipped
+                    ),
+                    None => format!("Random rule {}", self.matrix.rules),
+                },
+            );
+        }
+        if let Some(zoom) = step.zoom {
+            self.view.zoom = zoom;
+        }
+        if let Some(period) = step.speed {
+            self.settings.period = period;
+            self.due = Instant::now() + period;
+        }
+        if let Some(say) = step.say {
+            self.tell(say);
+        }
+
+        if step.run > 0 {
+            if self.paused {
+                self.paused = false;
+                self.due = Instant::now() + self.settings.period;
+            }
+            self.hold = Some(Hold::Generation(
+                self.matrix.generation + step.run,
+                step.pause,
+            ));
+        } else if !step.pause.is_zero() {
+            self.paused = true;
+            self.hold = Some(Hold::Until(Instant::now() + step.pause));
+        }
+    }
+
+    /// Reacts to a key, the mouse or the terminal being resized.
+    fn handle(&mut self, event: Event) -> Result<Flow, SessionError> {
+        match event {
+            Event::Resize(..) => {
+                self.refit();
+                self.redraw()?;
+                Ok(Flow::Wait)
+            }
+            Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                if modifiers.contains(KeyModifiers::CONTROL) {
+                    // Only seen where raw mode swallows the signal, i.e. on
+                    // Windows.
+                    if code == KeyCode::Char('c') {
+                        return Ok(Flow::Quit);
+                    }
+                    return Ok(Flow::Wait);
+                }
+                self.input(Input::Key(code, modifiers & KeyModifiers::SHIFT))
+            }
+            Event::Mouse(mouse) if script::MOUSE.iter().any(|&(_, k)| k == mouse.kind) => {
+                let mut at = (mouse.column as usize, mouse.row as usize);
+                if self.rival.is_some() && at.0 >= self.divide {
+                    at.0 -= self.divide;
+                }
+                match self.view.cell_at(&self.matrix, self.theme.cell_width(), at) {
+                    Some((row, col)) => self.input(Input::Mouse(mouse.kind, row, col)),
+                    None => Ok(Flow::Wait),
+                }
+            }
+            _ => Ok(Flow::Wait),
+        }
+    }
+
+    /// Does what the user asked for, whether just now or in a script,
+    /// writing it down if a script is being recorded.
+    fn input(&mut self, input: Input) -> Result<Flow, SessionError> {
+        if let Some(script) = self.script.as_mut() {
+            script
+                .write(self.matrix.generation, &input)
+                .map_err(SessionError::Script)?;
+        }
+
+        let key = match input {
+            Input::Key(code, modifiers) => (code, modifiers),
+            Input::Mouse(kind, row, col) => {
+                if self.mouse(kind, row, col) {
+                    self.update()?;
+                }
+                return Ok(Flow::Wait);
+            }
+        };
+        self.view.message = None;
+
+        if let Some(how) = self.marking.take() {
+            if let KeyCode::Char(letter) = key.0 {
+                self.mark(how, letter);
+            }
+            self.update()?;
+            return Ok(Flow::Wait);
+        }
+
+        if self.browser.is_some() {
+            self.choose(key.0);
+            self.update()?;
+            return Ok(Flow::Wait);
+        }
+
+        // Any key puts the help away.
+        if self.view.overlay.take().is_some() {
+            self.update()?;
+            return Ok(Flow::Wait);
+        }
+
+        if self.prompt.is_some() {
+            self.type_in(key.0);
+            self.update()?;
+            return Ok(Flow::Wait);
+        }
+
+        if self.view.cursor.is_some() && self.edit(key) {
+            self.update()?;
+            return Ok(Flow::Wait);
+        }
+
+        let action = match self.keys.action(key.0, false) {
+            Some(action) => action,
+            None => return Ok(Flow::Wait),
+        };
+        match action {
+            Action::Quit => return Ok(Flow::Quit),
+            Action::Pause => {
+                self.paused = !self.paused;
+                self.due = Instant::now() + self.settings.period;
+            }
+            Action::Edit if self.view.cursor.is_some() => {
+                self.view.cursor = None;
+                self.view.crosshair = false;
+                self.view.selection = None;
+            }
+            Action::Edit => {
+                let (m, n) = (self.matrix.m, self.matrix.n);
+                self.view.cursor = Some((m / 2, n / 2));
+                self.view.crosshair = true;
+                self.view.reveal(&self.matrix);
+            }
+            Action::Step => {
+                self.paused = true;
+                return Ok(Flow::Step);
+            }
+            Action::Reseed => self.reseed(None),
+            Action::Density => {
+                let density = self.rng.gen_range(0.05, 0.8);
+                self.reseed(Some(density));
+            }
+            Action::Clear => {
+                self.clear();
+                self.tell("Cleared the world".to_string());
+            }
+            Action::Paste => self.paste(),
+            Action::SystemPaste => match self.system.paste() {
+                Ok(file) => {
+                    self.clipboard = Some(file.pattern);
+                    self.stamped = false;
+                    self.paste();
+                }
+                Err(e) => self.view.message = Some(e.to_string()),
+            },
+            Action::Stamp => {
+                if !self.stamped {
+                    self.pick(self.stamp);
+                }
+                self.paste();
+            }
+            Action::NextStamp => {
+                self.pick((self.stamp + 1) % library::PATTERNS.len());
+                self.view.message = Some(format!(
+                    "{} now stamps the {}",
+                    self.keys.names(Action::Stamp),
+                    library::PATTERNS[self.stamp].0
+                ));
+            }
+            Action::Library => self.browse(),
+            Action::Rotate => self.turn(Pattern::rotate, "Rotated"),
+            Action::Flip => self.turn(Pattern::flip, "Flipped"),
+            Action::Export => self.export(),
+            Action::Save => self.save(),
+            Action::Dashboard => {
+                self.dashboard.shown = !self.dashboard.shown;
+                self.refit();
+                self.redraw()?;
+                return Ok(Flow::Wait);
+            }
+            Action::Inspect => {
+                self.view.inspect = !self.view.inspect;
+                if self.view.inspect && self.view.cursor.is_none() {
+                    let (m, n) = (self.matrix.m, self.matrix.n);
+                    self.view.cursor = Some((m / 2, n / 2));
+                    self.view.crosshair = true;
+                    self.view.reveal(&self.matrix);
+                }
+            }
+            Action::Help => self.view.overlay = Some(self.help()),
+            Action::Events => {
+                let events = self.events.lines(EVENTS);
+                self.view.overlay = Some(if events.is_empty() {
+                    vec!["Nothing has happened yet".to_string()]
+                } else {
+                    events
+                });
+            }
+            Action::Rule => self.ask(Question::Rule, self.matrix.rules.to_string()),
+            Action::Break => self.ask(Question::Break, String::new()),
+            Action::Resize => {
+                let size = format!("{}x{}", self.matrix.m, self.matrix.n);
+                self.ask(Question::Size, size);
+            }
+            Action::Back => {
+                self.paused = true;
+                if let Some(tape) = self.tape.as_mut() {
+                    if tape.at == 0 || !tape.seek(tape.at - 1, &mut self.matrix) {
+                        self.view.message = Some("This is the start of the recording".to_string());
+                    }
+                    self.refit();
+                } else if !self.travel(true) {
+                    self.view.message = Some("Can't go back any further".to_string());
+                }
+            }
+            Action::Forward => {
+                self.paused = true;
+                if self.tape.is_some() || !self.travel(false) {
+                    return Ok(Flow::Step);
+                }
+            }
+            Action::Rewind | Action::Skip => {
+                self.paused = true;
+                self.skip(action == Action::Skip);
+            }
+            Action::Mark => {
+                self.marking = Some(action);
+                self.view.message = Some("Bookmark under which letter?".to_string());
+            }
+            Action::Jump => {
+                self.marking = Some(action);
+                self.view.message = Some(self.marked("Go back to which?"));
+            }
+            Action::Diff => {
+                self.marking = Some(action);
+                self.view.message = Some(self.marked("Compare with which?"));
+            }
+            // Only found while editing, and then `edit` has seen to them.
+            Action::Up
+            | Action::Down
+            | Action::Left
+            | Action::Right
+            | Action::Toggle
+            | Action::Copy
+            | Action::Cut
+            | Action::Erase
+            | Action::SystemCopy => return Ok(Flow::Wait),
+        }
+
+        self.update()?;
+        Ok(Flow::Wait)
+    }
+
+    /// Handles the keys that only mean something in edit mode, or something
+    /// else there. Returns false for any other key.
+    fn edit(&mut self, (code, modifiers): (KeyCode, KeyModifiers)) -> bool {
+        let (m, n) = (self.matrix.m, self.matrix.n);
+        let (row, col) = match self.view.cursor {
+            Some(cursor) => cursor,
+            None => return false,
+        };
+
+        if code == KeyCode::Esc {
+            if self.view.selection.take().is_none() {
+                self.view.cursor = None;
+                self.view.crosshair = false;
+            }
+            return true;
+        }
+
+        let cursor = match self.keys.action(code, true) {
+            Some(Action::Up) => ((row + m - 1) % m, col),
+            Some(Action::Down) => ((row + 1) % m, col),
+            Some(Action::Left) => (row, (col + n - 1) % n),
+            Some(Action::Right) => (row, (col + 1) % n),
+            Some(Action::Toggle) => {
+                self.apply(|matrix| matrix.flip(row, col));
+                return true;
+            }
+            Some(action @ (Action::Copy | Action::Cut | Action::Erase | Action::SystemCopy))
+                if self.view.selection.is_some() =>
+            {
+                self.cut(action);
+                return true;
+            }
+            _ => return false,
+        };
+
+        // Shift and the arrows stretch the selection from where the cursor
+        // was, while the arrows alone let go of it.
+        self.view.selection = if modifiers.contains(KeyModifiers::SHIFT) {
+            let anchor = self.view.selection.map_or((row, col), |(anchor, _)| anchor);
+            Some((anchor, cursor))
+        } else {
+            None
+        };
+        self.view.cursor = Some(cursor);
+        self.view.reveal(&self.matrix);
+        true
+    }
+
+    /// The keys and how things stand, for the help screen.
+    fn help(&self) -> Vec<String> {
+        let mut keys: Vec<(String, &str)> = HELP
+            .iter()
+            .map(|(actions, what)| {
+                let names: Vec<String> = actions.iter().map(|&a| self.keys.names(a)).collect();
+                (names.join(" "), *what)
+            })
+            .collect();
+        keys.push((
+            "mouse".to_string(),
+            "click toggles, drag paints, right-drag selects",
+        ));
+
+        let width = keys
+            .iter()
+            .map(|(k, _)| k.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut lines: Vec<String> = keys
+            .iter()
+            .map(|(key, what)| format!("{:>width$}  {}", key, what, width = width))
+            .collect();
+
+        lines.push(String::new());
+        lines.push(format!(
+            "{} │ {}×{} │ {} │ every {:.1}s",
+            self.matrix.rules,
+            self.matrix.n,
+            self.matrix.m,
+            if self.paused { "paused" } else { "running" },
+            self.settings.period.as_secs_f64(),
+        ));
+        lines.push(format!(
+            "{} to undo │ clipboard {}",
+            self.past.len(),
+            self.clipboard
+                .as_ref()
+                .map_or("empty".to_string(), |c| format!("{}×{}", c.width, c.height)),
+        ));
+        lines.push(format!("saves to {}", self.settings.clipboard.display()));
+        if !self.breaks.is_empty() {
+            let breaks: Vec<String> = self.breaks.iter().map(|(c, _)| c.to_string()).collect();
+            lines.push(format!("breaks when {}", breaks.join(" or ")));
+        }
+
+        lines
+    }
+
+    /// Opens the prompt, with `text` already typed in.
+    fn ask(&mut self, question: Question, text: String) {
+        self.prompt = Some(Prompt { question, text });
+        self.show_prompt();
+    }
+
+    fn show_prompt(&mut self) {
+        if let Some(prompt) = &self.prompt {
+            self.view.message = Some(format!("{}: {}▏", prompt.question.label(), prompt.text));
+        }
+    }
+
+    /// Edits the line at the prompt. Enter answers the question and escape
+    /// gives up on it.
+    fn type_in(&mut self, code: KeyCode) {
+        let prompt = match self.prompt.as_mut() {
+            Some(prompt) => prompt,
+            None => return,
+        };
+
+        match code {
+            KeyCode::Char(c) => prompt.text.push(c),
+            KeyCode::Backspace => {
+                prompt.text.pop();
+            }
+            KeyCode::Esc => {
+                self.prompt = None;
+                self.view.message = None;
+                return;
+            }
+            KeyCode::Enter => {
+                if let Some(prompt) = self.prompt.take() {
+                    self.answer(prompt);
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        self.show_prompt();
+    }
+
+    /// Does what was asked for at the prompt.
+    fn answer(&mut self, prompt: Prompt) {
+        self.view.message = Some(match prompt.question {
+            Question::Rule => match prompt.text.trim().parse::<Rulestring>() {
+                Ok(rules) => {
+                    self.remember();
+                    let message = format!("Rule changed to {}", rules);
+                    self.matrix.rules = rules;
+                    self.log(Kind::Rule, message.clone());
+                    message
+                }
+                Err(e) => e.to_string(),
+            },
+            Question::Size => match crate::dimensions(&prompt.text) {
+                Some((m, n)) => {
+                    self.remember();
+                    let anchor = self.settings.anchor;
+                    self.matrix.grow(m, n, anchor);
+                    if let Some(r) = self.rival.as_mut() {
+                        r.grow(m, n, anchor);
+                    }
+                    self.refit();
+                    let message = format!("Resized the world to {}x{}", m, n);
+                    self.log(Kind::Note, message.clone());
+                    message
+                }
+                None => format!(
+                    "Invalid size {}: expected rows x columns",
+                    prompt.text.trim()
+                ),
+            },
+            Question::Break if prompt.text.trim().is_empty() => {
+                self.breaks.clear();
+                "Breakpoints cleared".to_string()
+            }
+            Question::Break => match prompt.text.parse::<Condition>() {
+                Ok(condition) => {
+                    let message = format!("Will stop when {}", condition);
+                    self.break_when(condition);
+                    message
+                }
+                Err(e) => e.to_string(),
+            },
+        });
+    }
+
+    /// Makes the same change to both worlds, so that it can be undone.
+    fn apply(&mut self, change: impl Fn(&mut Matrix)) {
+        self.remember();
+        self.change(change);
+    }
+
+    /// Makes the same change to both worlds, and counts their cells again.
+    fn change(&mut self, change: impl Fn(&mut Matrix)) {
+        change(&mut self.matrix);
+        if let Some(r) = self.rival.as_mut() {
+            change(r);
+        }
+        self.refresh();
+    }
+
+    /// Copies the selection to the clipboard, or the system clipboard as
+    /// well, cuts it or just clears it, depending on the action.
+    fn cut(&mut self, action: Action) {
+        let (from, to) = match self.view.selection {
+            Some(selection) => selection,
+            None => return,
+        };
+        let copy = Pattern::copy(&self.matrix, from, to);
+        let corner = (from.0.min(to.0), from.1.min(to.1));
+        let size = format!("{}×{}", copy.width, copy.height);
+
+        if action != Action::Erase {
+            self.clipboard = Some(copy.clone());
+            self.stamped = false;
+        }
+        if matches!(action, Action::Cut | Action::Erase) {
+            let empty = Pattern::empty(copy.height, copy.width);
+            self.apply(|matrix| empty.paste(matrix, corner));
+        }
+
+        self.view.message = Some(match action {
+            Action::Copy => format!("Copied {} cells", size),
+            Action::SystemCopy => match self.system.copy(&copy, &self.matrix.rules) {
+                Ok(()) => format!("Copied {} cells to the system clipboard", size),
+                Err(e) => e.to_string(),
+            },
+            Action::Cut => format!("Cut {} cells", size),
+            _ => format!("Cleared {} cells", size),
+        });
+        self.view.selection = None;
+    }
+
+    /// Puts a built-in pattern in the clipboard, ready for stamping.
+    fn pick(&mut self, stamp: usize) {
+        self.stamp = stamp;
+        self.clipboard = library::get(library::PATTERNS[stamp].0);
+        self.stamped = true;
+    }
+
+    /// Puts the clipboard down at the cursor, or at the top left of the
+    /// screen when there isn't one.
+    fn paste(&mut self) {
+        let clipboard = match self.clipboard.take() {
+            Some(clipboard) => clipboard,
+            None => {
+                self.view.message = Some("Nothing to paste".to_string());
+                return;
+            }
+        };
+
+        let at = self.view.cursor.unwrap_or(self.view.origin);
+        self.apply(|matrix| clipboard.paste(matrix, at));
+        self.view.message = Some(format!(
+            "Pasted {}×{} cells at ({}, {})",
+            clipboard.width, clipboard.height, at.0, at.1
+        ));
+        self.clipboard = Some(clipboard);
+    }
+
+    /// Opens the pattern library. The user's directory is read afresh each
+    /// time, so patterns saved there since show up without restarting.
+    fn browse(&mut self) {
+        let mut patterns: Vec<_> = library::all()
+            .into_iter()
+            .map(|(name, pattern)| (name, Ok(pattern)))
+            .collect();
+        if let Some(dir) = &self.settings.library {
+            patterns.extend(library::load(dir));
+        }
+
+        self.browser = Some(Browser {
+            patterns,
+            chosen: 0,
+        });
+        self.show_browser();
+    }
+
+    /// Moves through the library with the keys that move the cursor, `j`
+    /// and `k`, page up and down, home and end. Enter places the chosen
+    /// pattern, while escape, or the keys that open the library or quit,
+    /// close it without doing anything.
+    fn choose(&mut self, code: KeyCode) {
+        let action = self.keys.action(code, true);
+        let browser = match self.browser.as_mut() {
+            Some(browser) => browser,
+            None => return,
+        };
+        let last = browser.patterns.len().saturating_sub(1);
+
+        browser.chosen = match (code, action) {
+            (KeyCode::Char('k'), _) | (_, Some(Action::Up)) => browser.chosen.saturating_sub(1),
+            (KeyCode::Char('j'), _) | (_, Some(Action::Down)) => (browser.chosen + 1).min(last),
+            (KeyCode::PageUp, _) => browser.chosen.saturating_sub(LISTED),
+            (KeyCode::PageDown, _) => (browser.chosen + LISTED).min(last),
+            (KeyCode::Home, _) => 0,
+            (KeyCode::End, _) => last,
+            (KeyCode::Enter, _) => {
+                let (name, pattern) = &browser.patterns[browser.chosen];
+                match pattern {
+                    Ok(pattern) => {
+                        let name = name.clone();
+                        self.clipboard = Some(pattern.clone());
+                        self.stamped = false;
+                        self.browser = None;
+                        self.view.overlay = None;
+                        self.paste();
+                        let at = self.view.cursor.unwrap_or(self.view.origin);
+                        self.tell(format!("Placed {} at ({}, {})", name, at.0, at.1));
+                    }
+                    Err(e) => self.view.message = Some(e.clone()),
+                }
+                return;
+            }
+            (KeyCode::Esc, _) | (_, Some(Action::Library | Action::Quit)) => {
+                self.browser = None;
+                self.view.overlay = None;
+                return;
+            }
+            _ => browser.chosen,
+        };
+
+        self.show_browser();
+    }
+
+    /// Lists the library over the grid, as much of it as fits around the
+    /// chosen pattern, with a preview of that pattern beside the list.
+    fn show_browser(&mut self) {
+        let browser = match &self.browser {
+            Some(browser) => browser,
+            None => return,
+        };
+        let count = browser.patterns.len();
+        let top = browser
+            .chosen
+            .saturating_sub(LISTED / 2)
+            .min(count.saturating_sub(LISTED));
+        let names: Vec<String> = browser.patterns[top..(top + LISTED).min(count)]
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| {
+                let mark = if top + i == browser.chosen {
+                    '▸'
+                } else {
+                    ' '
+                };
+                format!("{} {}", mark, name.chars().take(20).collect::<String>())
+            })
+            .collect();
+        let preview = match &browser.patterns[browser.chosen].1 {
+            Ok(pattern) => {
+                let mut preview = vec![format!("{}×{}", pattern.width, pattern.height)];
+                preview.extend(pattern.thumbnail(PREVIEW.0, PREVIEW.1));
+                preview
+            }
+            Err(_) => vec!["can't be read".to_string()],
+        };
+
+        let width = names.iter().map(|n| n.chars().count()).max().unwrap_or(0);
+        let mut lines = vec![format!("{} patterns", count), String::new()];
+        for i in 0..names.len().max(preview.len()) {
+            let name = names.get(i).map_or("", String::as_str);
+            let pad = width - name.chars().count();
+            let picture = preview.get(i).map_or("", String::as_str);
+            lines.push(format!("{}{}   {}", name, " ".repeat(pad), picture));
+        }
+        lines.push(String::new());
+        lines.push("↑↓ choose │ enter places │ esc closes".to_string());
+
+        self.view.overlay = Some(lines);
+    }
+
+    /// Rotates or flips the clipboard, ready for pasting.
+    fn turn(&mut self, how: fn(&Pattern) -> Pattern, done: &str) {
+        self.view.message = Some(match self.clipboard.as_ref() {
+            Some(clipboard) => {
+                self.clipboard = Some(how(clipboard));
+                format!("{} the clipboard", done)
+            }
+            None => "Nothing in the clipboard".to_string(),
+        });
+    }
+
+    /// Writes the clipboard out as an RLE file.
+    fn export(&mut self) {
+        let path = &self.settings.clipboard;
+        self.view.message = Some(match self.clipboard.as_ref() {
+            Some(clipboard) => match fs::write(
+                path,
+                Format::Rle.write(clipboard, &self.matrix.rules, &self.provenance.lines()),
+            ) {
+                Ok(()) => format!("Wrote the clipboard to {}", path.display()),
+                Err(e) => format!("Could not write {}: {}", path.display(), e),
+            },
+            None => "Nothing in the clipboard".to_string(),
+        });
+    }
+
+    /// Writes every live cell out as an RLE file named after the
+    /// generation, to share.
+    fn save(&mut self) {
+        let path = format!("generation-{}.rle", self.matrix.generation);
+        let live = Pattern::live(&self.matrix);
+        let rle = Format::Rle.write(&live, &self.matrix.rules, &self.provenance.lines());
+        match fs::write(&path, rle) {
+            Ok(()) => self.tell(format!("Wrote the live cells to {}", path)),
+            Err(e) => self.view.message = Some(format!("Could not write {}: {}", path, e)),
+        }
+    }
+
+    /// Toggles the cell under a left click and paints the cells the mouse
+    /// is dragged over the same. Dragging with the right button selects a
+    /// rectangle instead. Returns true if anything changed.
+    fn mouse(&mut self, kind: MouseEventKind, row: usize, col: usize) -> bool {
+        let (row, col) = (row % self.matrix.m, col % self.matrix.n);
+        let old = self.matrix.rows[row * self.matrix.n + col];
+
+        let state = match (kind, self.brush) {
+            (MouseEventKind::Down(MouseButton::Left), _) => {
+                let state = if old == 0 { 1 } else { 0 };
+                self.brush = Some(state);
+                state
+            }
+            (MouseEventKind::Drag(MouseButton::Left), Some(b)) if old != b => {
+                // The whole stroke is undone at once.
+                self.change(|matrix| matrix.set(row, col, b));
+                return true;
+            }
+            (MouseEventKind::Down(MouseButton::Right), _) => {
+                self.view.cursor = Some((row, col));
+                self.view.crosshair = true;
+                self.view.selection = Some(((row, col), (row, col)));
+                return true;
+            }
+            (MouseEventKind::Drag(MouseButton::Right), _) => {
+                self.view.cursor = Some((row, col));
+                self.view.selection = self.view.selection.map(|(anchor, _)| (anchor, (row, col)));
+                return true;
+            }
+            (MouseEventKind::Up(_), _) => {
+                self.brush = None;
+                return false;
+            }
+            _ => return false,
+        };
+
+        self.apply(|matrix| matrix.set(row, col, state));
+        true
+    }
+
+    /// Fits the view to the terminal again after it has been resized, and
+    /// the worlds too if they're meant to fill it.
+    fn refit(&mut self) {
+        if let Some(size) = term::size().filter(|_| self.settings.crop) {
+            let size = self.pane(size);
+            let cell_width = self.theme.cell_width();
+
+            if self.settings.fit {
+                let (m, n) = self.view.room(size, self.matrix.m, cell_width);
+                self.matrix.resize(m, n);
+                if let Some(r) = self.rival.as_mut() {
+                    r.resize(m, n);
+                }
+            }
+            self.view.fit(size, &self.matrix, cell_width);
+        }
+        // The worlds may be smaller now, whether fitted, resized or put
+        // back, and the cursor mustn't be left off the edge of them, nor
+        // off a window that has shrunk with the terminal.
+        self.view.clamp(&self.matrix);
+        self.view.reveal(&self.matrix);
+        self.refresh();
+    }
+
+    /// Empties both worlds. The generations carry on counting from where
+    /// they were.
+    fn clear(&mut self) {
+        self.remember();
+        let mut fresh = Matrix::new(self.matrix.m, self.matrix.n, self.matrix.rules.clone());
+        fresh.generation = self.matrix.generation;
+
+        self.rival = self.rival.take().map(|r| Matrix {
+            rules: r.rules,
+            ..fresh.clone()
+        });
+        self.matrix = fresh;
+        self.refresh();
+    }
+
+    /// Starts both worlds again from a fresh random seed, as dense as the
+    /// last one unless given a new `density`.
+    fn reseed(&mut self, density: Option<f64>) {
+        if let Some(d) = density {
+            self.settings.soup.density = d;
+            self.settings.soup.count = None;
+        }
+
+        self.clear();
+        self.settings.soup.seed(&mut self.matrix, &mut self.rng);
+        if let Some(r) = self.rival.as_mut() {
+            r.rows = self.matrix.rows.clone();
+            r.ages = self.matrix.ages.clone();
+            // A twin starts over one cell off again, or there's no damage
+            // left to spread.
+            if let Some((row, col)) = self.twin {
+                r.flip(row % r.m, col % r.n);
+            }
+        }
+        self.refresh();
+
+        self.tell_as(
+            Kind::Reseed,
+            format!("Reseeded, {:.1}% alive", self.stats.density() * 100.0),
+        );
+    }
+
+    /// Brings the dark pixels of the newest frame fed in to life in both
+    /// worlds, if one has come in since the last generation.
+    fn inject(&mut self) {
+        let Some((feed, threshold)) = self.feed.as_mut() else {
+            return;
+        };
+        let raster = match feed.take() {
+            Some(Fed::Frame(raster)) => raster,
+            Some(Fed::Ended) => {
+                self.feed = None;
+                return self.tell("The feed has ended".to_string());
+            }
+            Some(Fed::Failed(e)) => {
+                self.feed = None;
+                return self.tell(format!("The feed stopped: {}", e));
+            }
+            None => return,
+        };
+
+        let ink = raster.ink(self.matrix.m, self.matrix.n, *threshold);
+        for world in std::iter::once(&mut self.matrix).chain(self.rival.as_mut()) {
+            for (i, _) in ink.iter().enumerate().filter(|(_, &alive)| alive) {
+                if world.rows[i] == 0 {
+                    world.rows[i] = 1;
+                    world.ages[i] = 0;
+                }
+            }
+        }
+    }
+
+    /// Flips cells at random, and brings some more to life, the same ones
+    /// in both worlds. Rather than asking of every cell whether it flips,
+    /// the gaps between those that do are drawn, which for the tiny
+    /// probabilities noise usually has is far quicker.
+    fn perturb(&mut self) {
+        let cells = self.matrix.rows.len();
+        let mut flips = Vec::new();
+        if self.noise > 0.0 {
+            let stay = (1.0 - self.noise.min(1.0)).ln();
+            let mut i = 0;
+            loop {
+                let gap = (1.0 - self.rng.gen::<f64>()).ln() / stay;
+                i += if gap.is_finite() { gap as usize } else { cells };
+                if i >= cells {
+                    break;
+                }
+                flips.push(i);
+                i += 1;
+            }
+        }
+        let births: Vec<usize> = (0..self.noise_births)
+            .map(|_| self.rng.gen_range(0, cells))
+            .collect();
+
+        for world in std::iter::once(&mut self.matrix).chain(self.rival.as_mut()) {
+            for &i in &flips {
+                world.rows[i] = if world.rows[i] == 0 { 1 } else { 0 };
+                world.ages[i] = 0;
+            }
+            for &i in &births {
+                if world.rows[i] == 0 {
+                    world.rows[i] = 1;
+                    world.ages[i] = 0;
+                }
+            }
+        }
+    }
+
+    /// Adds fresh soup to both worlds, the same in each, leaving whatever is
+    /// alive as it is.
+    fn sow(&mut self) {
+        let mut soup = Matrix::new(self.matrix.m, self.matrix.n, self.matrix.rules.clone());
+        self.settings.soup.seed(&mut soup, &mut self.rng);
+        for world in std::iter::once(&mut self.matrix).chain(self.rival.as_mut()) {
+            for (i, _) in soup.rows.iter().enumerate().filter(|(_, &s)| s != 0) {
+                world.rows[i] = 1;
+                world.ages[i] = 0;
+            }
+        }
+        self.refresh();
+
+        self.tell_as(
+            Kind::Reseed,
+            format!(
+                "Added fresh soup, {:.1}% alive",
+                self.stats.density() * 100.0
+            ),
+        );
+    }
+
+    /// Writes a checkpoint, if asked to. Everything random from here on is
+    /// reseeded from a seed kept in it, so a resumed run goes the same way.
+    fn save_checkpoint(&mut self) -> Result<(), SessionError> {
+        let path = match &self.checkpoint {
+            Some((_, path)) => path,
+            None => return Ok(()),
+        };
+
+        let seed = self.rng.gen();
+        self.rng = StdRng::seed_from_u64(seed);
+        let mut checkpoint = Checkpoint {
+            matrix: self.matrix.clone(),
+            elapsed: self.elapsed + self.started.elapsed(),
+            seed,
+            settings: self.project.clone(),
+        };
+        checkpoint.settings.push((
+            "view".to_string(),
+            format!(
+                "{} {} {}",
+                self.view.origin.0, self.view.origin.1, self.view.zoom
+            ),
+        ));
+        checkpoint.save(path).map_err(SessionError::Checkpoint)
+    }
+
+    /// Keeps the worlds as they are, before they change, and forgets
+    /// anything that was undone. Without a keyboard nothing could ever be
+    /// undone, so nothing is kept.
+    fn remember(&mut self) {
+        if self.settings.rewind == 0 || !self.settings.paced {
+            return;
+        }
+        if self.past.len() == self.settings.rewind {
+            self.past.pop_front();
+        }
+        self.past
+            .push_back((self.matrix.clone(), self.rival.clone()));
+        self.future.clear();
+    }
+
+    /// Goes back or forward a tenth of the way through a recording, or
+    /// otherwise ten generations or edits through those remembered.
+    fn skip(&mut self, forward: bool) {
+        let tape = match self.tape.as_mut() {
+            Some(tape) => tape,
+            None => {
+                let moved = (0..10).take_while(|_| self.travel(!forward)).count();
+                if moved == 0 {
+                    self.view.message = Some(format!(
+                        "Can't go {} any further",
+                        if forward { "forward" } else { "back" }
+                    ));
+                }
+                return;
+            }
+        };
+
+        let jump = tape.len().div_ceil(10);
+        let frame = if forward {
+            (tape.at + jump).min(tape.len() - 1)
+        } else {
+            tape.at.saturating_sub(jump)
+        };
+        tape.seek(frame, &mut self.matrix);
+        self.view.message = Some(format!("Frame {} of {}", frame + 1, tape.len()));
+        self.refit();
+    }
+
+    /// Goes back to the worlds before the last generation or edit with
+    /// `back`, or forward again through what was undone. Returns false if
+    /// there's nowhere to go.
+    fn travel(&mut self, back: bool) -> bool {
+        let snapshot = if back {
+            self.past.pop_back()
+        } else {
+            self.future.pop()
+        };
+        let snapshot = match snapshot {
+            Some(snapshot) => snapshot,
+            None => return false,
+        };
+
+        let now = self.restore(snapshot);
+        if back {
+            self.future.push(now);
+        } else {
+            self.past.push_back(now);
+        }
+        true
+    }
+
+    /// Puts back the worlds from some other time, and returns the ones
+    /// there were. They come back at the size they were, undoing any
+    /// resizing, unless they're fitted to a terminal that may have been
+    /// resized since. The cursor and selection are kept inside them.
+    fn restore(&mut self, (mut matrix, mut rival): Snapshot) -> Snapshot {
+        if self.settings.fit {
+            let (m, n) = (self.matrix.m, self.matrix.n);
+            matrix.resize(m, n);
+            if let Some(r) = rival.as_mut() {
+                r.resize(m, n);
+            }
+        }
+
+        let now = (
+            std::mem::replace(&mut self.matrix, matrix),
+            std::mem::replace(&mut self.rival, rival),
+        );
+        self.refit();
+        now
+    }
+
+    /// Bookmarks the worlds under `letter` after `m`, goes back to the ones
+    /// bookmarked there after `'`, or shows what differs from them after
+    /// `=`. Going back can be undone.
+    fn mark(&mut self, how: Action, letter: char) {
+        if !letter.is_ascii_alphabetic() {
+            self.view.message = Some(format!("Bookmarks are letters, not {:?}", letter));
+            return;
+        }
+
+        if how == Action::Mark {
+            self.marks
+                .insert(letter, (self.matrix.clone(), self.rival.clone()));
+            self.view.message = Some(format!(
+                "Bookmarked generation {} as {}",
+                self.matrix.generation, letter
+            ));
+            return;
+        }
+
+        let snapshot = match self.marks.get(&letter) {
+            Some(snapshot) => snapshot.clone(),
+            None => {
+                self.view.message = Some(format!("Nothing bookmarked as {}", letter));
+                return;
+            }
+        };
+
+        if how == Action::Diff {
+            self.compare(letter, &snapshot.0);
+            return;
+        }
+
+        self.remember();
+        self.paused = true;
+        self.restore(snapshot);
+        self.tell(format!(
+            "Back to {}, generation {}",
+            letter, self.matrix.generation
+        ));
+    }
+
+    /// Shows what differs between the world and the one bookmarked under
+    /// `letter`, in a box over the grid that any key puts away.
+    fn compare(&mut self, letter: char, then: &Matrix) {
+        let whole = |matrix: &Matrix| Pattern::copy(matrix, (0, 0), (matrix.m - 1, matrix.n - 1));
+        let diff = Diff::new(&whole(then), &whole(&self.matrix));
+        // A clause a line, to fit over the grid.
+        let summary = diff.summary(
+            &format!("{} (generation {})", letter, then.generation),
+            &format!("now (generation {})", self.matrix.generation),
+        );
+        let mut lines: Vec<String> = summary.split(", ").map(str::to_string).collect();
+        let picture = diff.picture(16, 64);
+        if !picture.is_empty() {
+            lines.push(String::new());
+            lines.extend(picture);
+            lines.push(String::new());
+            lines.push(format!("- alive only in {}   + alive only now", letter));
+        }
+        self.view.overlay = Some(lines);
+    }
+
+    /// Lists the bookmarks there are to ask about.
+    fn marked(&self, question: &str) -> String {
+        if self.marks.is_empty() {
+            return format!(
+                "Nothing bookmarked yet; {} and a letter makes one",
+                self.keys.names(Action::Mark)
+            );
+        }
+        let marks: Vec<String> = self
+            .marks
+            .iter()
+            .map(|(letter, (matrix, _))| format!("{} gen {}", letter, matrix.generation))
+            .collect();
+        format!("{} {}", question, marks.join(" │ "))
+    }
+
+    /// Says what just happened under the grid, and keeps it among the
+    /// events.
+    fn tell(&mut self, message: String) {
+        self.tell_as(Kind::Note, message);
+    }
+
+    fn tell_as(&mut self, kind: Kind, message: String) {
+        self.log(kind, message.clone());
+        self.view.message = Some(message);
+    }
+
+    /// Keeps something that just happened among the events, without
+    /// saying so under the grid.
+    fn log(&mut self, kind: Kind, message: String) {
+        self.keep(events::Event {
+            time: SystemTime::now(),
+            generation: self.matrix.generation,
+            kind,
+            population: self.stats.population,
+            message,
+        });
+    }
+
+    fn keep(&mut self, event: events::Event) {
+        self.dashboard.log(event.generation, event.message.clone());
+        if self.event_log.is_some() {
+            self.unlogged.push(event.clone());
+        }
+        self.events.keep(event);
+    }
+
+    /// Looks for the world settling or starting up again and for the
+    /// population turning after a generation, then writes whatever has
+    /// happened since the last to the event log.
+    fn watch(&mut self) -> Result<(), SessionError> {
+        if let Some(event) = self.dashboard.watch(&self.matrix) {
+            self.log(Kind::Period, event);
+        }
+        let settled = self.dashboard.period != Period::Changing;
+        let turned = self
+            .extrema
+            .see(self.matrix.generation, self.stats.population, settled);
+        if let Some((kind, generation, population)) = turned {
+            let message = match kind {
+                Kind::Peak => format!("Population peaked at {}", population),
+                _ => format!("Population bottomed out at {}", population),
+            };
+            self.keep(events::Event {
+                time: SystemTime::now(),
+                generation,
+                kind,
+                population,
+                message,
+            });
+        }
+        self.write_events()
+    }
+
+    fn write_events(&mut self) -> Result<(), SessionError> {
+        if let Some(log) = self.event_log.as_mut() {
+            for event in self.unlogged.drain(..) {
+                log.write(&event).map_err(SessionError::Stats)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Counts the cells again after the worlds have changed between
+    /// generations, keeping the frame rate and the trend.
+    fn refresh(&mut self) {
+        let refresh = |old: &Stats, m: &Matrix| Stats {
+            fps: old.fps,
+            trend: old.trend.clone(),
+            ..Stats::of(m)
+        };
+
+        self.stats = refresh(&self.stats, &self.matrix);
+        self.dashboard.unsettle();
+        self.rival_stats = self
+            .rival
+            .as_ref()
+            .zip(self.rival_stats.as_ref())
+            .map(|(r, s)| refresh(s, r));
+    }
+}
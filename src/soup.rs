@@ -0,0 +1,410 @@
+//! Random soups, the usual way to start a world: cells set alive at random,
+//! everywhere or only in part of it.
+//!
+//! Most soup experiments put a small square of soup in the middle of a
+//! large empty world, so that whatever escapes it has room to be seen, which
+//! `--seed-center 16x16` does. `--seed-region X,Y,W,H` puts it anywhere.
+//!
+//! Cells needn't be spread evenly either. `--seed-dist gaussian` makes a
+//! cloud that's thickest in the middle and thins out towards the edges, and
+//! `--seed-dist clusters:5` five such clouds in random places, which many
+//! rules treat very differently from an even sprinkle. `--seed-dist perlin`
+//! makes solid, organic-looking blobs out of Perlin noise: smooth random
+//! hills and valleys about `--noise-scale` cells across, with every cell
+//! alive where the ground is higher than `--noise-threshold`. And
+//! `--seed-dist stratified` goes the other way, spreading cells out more
+//! evenly than chance would, with none touching, for rules such as Seeds
+//! where the least clump sets off an explosion.
+//!
+//! Soups can come from data instead of chance, too. `--seed-bytes FILE`
+//! lays the bits of a file out row by row, a live cell for every 1, and
+//! `--seed-phrase WORDS` hashes the words into the seed everything random
+//! comes from, so the same words always make the same soup.
+//!
+//! Or a soup can be made of whole patterns: `--sprinkle glider:20,blinker:10`
+//! puts twenty gliders and ten blinkers down at random, each turned and
+//! flipped at random, for glider gas and the like.
+
+use std::str::FromStr;
+
+use rand::seq::index;
+use rand::Rng;
+
+use crate::pattern::Pattern;
+use crate::Matrix;
+
+/// A rectangle of the world, as its top left cell and its size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+    pub row: usize,
+    pub col: usize,
+    pub height: usize,
+    pub width: usize,
+}
+
+impl Region {
+    /// The `height` by `width` cells in the middle of an `m` by `n` world.
+    pub fn centred(height: usize, width: usize, (m, n): (usize, usize)) -> Region {
+        Region {
+            row: m.saturating_sub(height) / 2,
+            col: n.saturating_sub(width) / 2,
+            height,
+            width,
+        }
+    }
+
+    /// Whether it's all inside an `m` by `n` world.
+    pub fn fits(&self, (m, n): (usize, usize)) -> bool {
+        self.row + self.height <= m && self.col + self.width <= n
+    }
+}
+
+/// How the live cells are spread over the region.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distribution {
+    /// Evenly.
+    Uniform,
+    /// In a cloud around the middle that has all but faded out by the edges
+    /// of the region.
+    Gaussian,
+    /// In this many smaller clouds at random.
+    Clusters(usize),
+    /// Where Perlin noise about `scale` cells across is above `threshold`,
+    /// between 0 and 1. The threshold takes the place of the density.
+    Perlin { scale: f64, threshold: f64 },
+    /// One cell to every square of a grid, somewhere inside it but not on
+    /// its edges, so that no two cells touch. The squares are as big as the
+    /// density asks for, and never smaller than two cells across, so this
+    /// is never denser than a quarter.
+    Stratified,
+}
+
+impl FromStr for Distribution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Distribution, String> {
+        match s.split_once(':') {
+            None if s == "uniform" => Ok(Distribution::Uniform),
+            None if s == "gaussian" => Ok(Distribution::Gaussian),
+            None if s == "stratified" || s == "blue" => Ok(Distribution::Stratified),
+            None if s == "perlin" => Ok(Distribution::Perlin {
+                scale: 8.0,
+                threshold: 0.5,
+            }),
+            Some(("clusters", n)) => match n.parse::<usize>() {
+                Ok(n) if n > 0 => Ok(Distribution::Clusters(n)),
+                _ => Err(format!("Invalid number of clusters {}", n)),
+            },
+            _ => Err(format!(
+                "Invalid distribution {}: expected uniform, gaussian, clusters:N, perlin or stratified",
+                s
+            )),
+        }
+    }
+}
+
+/// How to make a soup.
+#[derive(Clone, Debug)]
+pub struct Soup {
+    /// How likely every cell is to be alive, or for clouds, those at their
+    /// thickest.
+    pub density: f64,
+    /// Where the soup goes, or everywhere.
+    pub region: Option<Region>,
+    pub distribution: Distribution,
+    /// Exactly how many cells to set alive instead, every one a different
+    /// cell, evenly spread.
+    pub count: Option<usize>,
+}
+
+impl Soup {
+    /// Sets every cell in the region alive on its own with probability
+    /// `density`, or less away from the middle of a cloud, so that a
+    /// density of a half really does fill half of it. Cells already alive
+    /// stay that way. A region that no longer fits, as when the world has
+    /// shrunk, is cut down to what does.
+    pub fn seed(&self, matrix: &mut Matrix, rng: &mut impl Rng) {
+        let density = self.density.clamp(0.0, 1.0);
+        let region = self.region.unwrap_or(Region {
+            row: 0,
+            col: 0,
+            height: matrix.m,
+            width: matrix.n,
+        });
+
+        // Picking that many of the cells without putting any back means
+        // none is picked twice.
+        if let Some(count) = self.count {
+            let height = region.height.min(matrix.m.saturating_sub(region.row));
+            let width = region.width.min(matrix.n.saturating_sub(region.col));
+            for i in index::sample(rng, height * width, count.min(height * width)).into_iter() {
+                let (r, c) = (region.row + i / width, region.col + i % width);
+                matrix.rows[r * matrix.n + c] = 1;
+                matrix.ages[r * matrix.n + c] = 0;
+            }
+            return;
+        }
+
+        // Squares `side` across and down with a cell at one of the places
+        // inside them, away from their right and bottom edges, so that no
+        // two cells in neighbouring squares can touch.
+        if self.distribution == Distribution::Stratified {
+            let side = (1.0 / density).sqrt().round().max(2.0) as usize;
+            let bottom = (region.row + region.height).min(matrix.m);
+            let right = (region.col + region.width).min(matrix.n);
+            for top in (region.row..bottom).step_by(side) {
+                for left in (region.col..right).step_by(side) {
+                    let r = top + rng.gen_range(0, side - 1);
+                    let c = left + rng.gen_range(0, side - 1);
+                    if r < bottom && c < right {
+                        matrix.rows[r * matrix.n + c] = 1;
+                        matrix.ages[r * matrix.n + c] = 0;
+                    }
+                }
+            }
+            return;
+        }
+
+        // Every cloud as its middle and how wide it is, one way and the
+        // other, as the standard deviation of a bell curve. Clusters are as
+        // wide as the region divided up between them.
+        let (h, w) = (region.height as f64, region.width as f64);
+        let clouds: Vec<((f64, f64), (f64, f64))> = match self.distribution {
+            Distribution::Uniform | Distribution::Perlin { .. } | Distribution::Stratified => {
+                Vec::new()
+            }
+            Distribution::Gaussian => vec![((h / 2.0, w / 2.0), (h / 6.0, w / 6.0))],
+            Distribution::Clusters(n) => {
+                let spread = (h * w / n as f64).sqrt() / 4.0;
+                (0..n)
+                    .map(|_| {
+                        let middle = (rng.gen_range(0.0, h), rng.gen_range(0.0, w));
+                        (middle, (spread, spread))
+                    })
+                    .collect()
+            }
+        };
+        let noise = match self.distribution {
+            Distribution::Perlin { scale, threshold } => Some((
+                Noise::new(region.height, region.width, scale, rng),
+                threshold,
+            )),
+            _ => None,
+        };
+        let chance = |r: usize, c: usize| {
+            if let Some((noise, threshold)) = &noise {
+                return if noise.at(r, c) > *threshold {
+                    1.0
+                } else {
+                    0.0
+                };
+            }
+            if clouds.is_empty() {
+                return density;
+            }
+            let (y, x) = (r as f64 + 0.5, c as f64 + 0.5);
+            let thickest = clouds
+                .iter()
+                .map(|&((my, mx), (sy, sx))| {
+                    let d = ((y - my) / sy).powi(2) + ((x - mx) / sx).powi(2);
+                    (-d / 2.0).exp()
+                })
+                .fold(0.0, f64::max);
+            density * thickest
+        };
+
+        for r in region.row..(region.row + region.height).min(matrix.m) {
+            for c in region.col..(region.col + region.width).min(matrix.n) {
+                if rng.gen_bool(chance(r - region.row, c - region.col)) {
+                    matrix.rows[r * matrix.n + c] = 1;
+                    matrix.ages[r * matrix.n + c] = 0;
+                }
+            }
+        }
+    }
+}
+
+/// Lays bytes out in the region, or the whole world, row by row: a cell for
+/// every bit, highest first, alive for a 1. Stops when either runs out.
+pub fn lay(matrix: &mut Matrix, region: Option<Region>, bytes: &[u8]) {
+    let region = region.unwrap_or(Region {
+        row: 0,
+        col: 0,
+        height: matrix.m,
+        width: matrix.n,
+    });
+    let bits = bytes
+        .iter()
+        .flat_map(|&byte| (0..8).rev().map(move |i| byte >> i & 1 == 1));
+    let cells = (region.row..region.row + region.height)
+        .flat_map(|r| (region.col..region.col + region.width).map(move |c| (r, c)));
+
+    for ((r, c), bit) in cells.zip(bits) {
+        if bit {
+            matrix.rows[r * matrix.n + c] = 1;
+            matrix.ages[r * matrix.n + c] = 0;
+        }
+    }
+}
+
+/// Puts down copies of patterns, as many of each as asked, anywhere in the
+/// region, or the world, with each turned and flipped at random. Copies are
+/// kept a cell clear of each other where there's room, so that they don't
+/// react before they've had a chance to move; after a hundred tries at
+/// finding a clear spot one goes down wherever it lands.
+pub fn sprinkle(
+    matrix: &mut Matrix,
+    region: Option<Region>,
+    patterns: &[(Pattern, usize)],
+    rng: &mut impl Rng,
+) {
+    let region = region.unwrap_or(Region {
+        row: 0,
+        col: 0,
+        height: matrix.m,
+        width: matrix.n,
+    });
+
+    for (pattern, copies) in patterns {
+        for _ in 0..*copies {
+            let mut copy = pattern.clone();
+            for _ in 0..rng.gen_range(0, 4) {
+                copy = copy.rotate();
+            }
+            if rng.gen() {
+                copy = copy.flip();
+            }
+
+            let mut at = (region.row, region.col);
+            for _ in 0..100 {
+                at = (
+                    region.row + rng.gen_range(0, region.height.saturating_sub(copy.height) + 1),
+                    region.col + rng.gen_range(0, region.width.saturating_sub(copy.width) + 1),
+                );
+                if clear(matrix, at, (copy.height, copy.width)) {
+                    break;
+                }
+            }
+            copy.overlay(matrix, at);
+        }
+    }
+}
+
+/// Whether a rectangle and the cells just around it are all dead. Like
+/// everything else it wraps around the edges.
+fn clear(matrix: &Matrix, (row, col): (usize, usize), (height, width): (usize, usize)) -> bool {
+    (0..height + 2).all(|dr| {
+        (0..width + 2).all(|dc| {
+            let r = (row + matrix.m + dr - 1) % matrix.m;
+            let c = (col + matrix.n + dc - 1) % matrix.n;
+            matrix.rows[r * matrix.n + c] == 0
+        })
+    })
+}
+
+/// Hashes a phrase into a seed with 64-bit FNV-1a, which unlike the
+/// standard library's hasher is the same everywhere and always will be.
+pub fn phrase(words: &str) -> u64 {
+    words.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Perlin noise in a few octaves, each half as wide and half as high as
+/// the one before. A single octave is always halfway up on its lattice
+/// lines, which shows as straight edges along them, so the finer ones are
+/// shifted off it by a random amount.
+struct Noise {
+    octaves: Vec<Octave>,
+}
+
+impl Noise {
+    fn new(height: usize, width: usize, scale: f64, rng: &mut impl Rng) -> Noise {
+        let octaves = (0..3)
+            .map(|k| {
+                let shift = match k {
+                    0 => (0.0, 0.0),
+                    _ => (rng.gen_range(0.0, 1.0), rng.gen_range(0.0, 1.0)),
+                };
+                Octave::new(height, width, scale / (1 << k) as f64, shift, rng)
+            })
+            .collect();
+        Noise { octaves }
+    }
+
+    /// How high the ground is at a cell, between 0 and 1.
+    fn at(&self, row: usize, col: usize) -> f64 {
+        let (mut sum, mut total, mut weight) = (0.0, 0.0, 1.0);
+        for octave in &self.octaves {
+            sum += octave.at(row, col) * weight;
+            total += weight;
+            weight /= 2.0;
+        }
+        sum / total
+    }
+}
+
+/// Perlin's gradient noise: a lattice of random slopes `scale` cells apart,
+/// smoothed between, so that the ground rises and falls gently.
+struct Octave {
+    scale: f64,
+    /// Where the lattice starts, in lattice units up and to the left.
+    shift: (f64, f64),
+    /// How many lattice points there are across.
+    across: usize,
+    /// The slope at every lattice point, as a unit vector.
+    gradients: Vec<(f64, f64)>,
+}
+
+impl Octave {
+    fn new(
+        height: usize,
+        width: usize,
+        scale: f64,
+        shift: (f64, f64),
+        rng: &mut impl Rng,
+    ) -> Octave {
+        let scale = scale.max(1.0);
+        let down = (height as f64 / scale) as usize + 3;
+        let across = (width as f64 / scale) as usize + 3;
+        let gradients = (0..down * across)
+            .map(|_| {
+                let angle = rng.gen_range(0.0, std::f64::consts::TAU);
+                (angle.sin(), angle.cos())
+            })
+            .collect();
+
+        Octave {
+            scale,
+            shift,
+            across,
+            gradients,
+        }
+    }
+
+    /// How high the ground is at a cell, between 0 and 1.
+    fn at(&self, row: usize, col: usize) -> f64 {
+        let (y, x) = (
+            (row as f64 + 0.5) / self.scale + self.shift.0,
+            (col as f64 + 0.5) / self.scale + self.shift.1,
+        );
+        let (i, j) = (y as usize, x as usize);
+        let (fy, fx) = (y - i as f64, x - j as f64);
+
+        // How far up each corner's slope the cell is.
+        let corner = |di: usize, dj: usize| {
+            let (gy, gx) = self.gradients[(i + di) * self.across + j + dj];
+            gy * (fy - di as f64) + gx * (fx - dj as f64)
+        };
+        let fade = |t: f64| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+
+        let (sy, sx) = (fade(fy), fade(fx));
+        let top = lerp(corner(0, 0), corner(0, 1), sx);
+        let bottom = lerp(corner(1, 0), corner(1, 1), sx);
+
+        // Perlin noise in two dimensions stays within half the square root
+        // of two of zero.
+        (lerp(top, bottom, sy) * std::f64::consts::SQRT_2 + 1.0) / 2.0
+    }
+}
@@ -0,0 +1,281 @@
+//! Numbers describing the simulation as it runs.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Write};
+use std::time::Instant;
+
+use rayon::prelude::*;
+
+use crate::provenance::Provenance;
+use crate::Matrix;
+
+/// A snapshot of one generation.
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    pub generation: u64,
+    pub population: usize,
+    pub births: usize,
+    pub deaths: usize,
+    pub cells: usize,
+    /// Frames drawn per second, measured by a `FrameClock`.
+    pub fps: f64,
+    /// The population of recent generations, oldest first, as kept by a
+    /// `History`.
+    pub trend: Vec<usize>,
+}
+
+impl Stats {
+    /// Counts the live cells and picks up the rest from the `Matrix`. The
+    /// frame rate is left at zero as the matrix knows nothing about it.
+    pub fn of(matrix: &Matrix) -> Stats {
+        Stats {
+            generation: matrix.generation,
+            population: matrix.rows.par_iter().filter(|&&c| c != 0).count(),
+            births: matrix.births,
+            deaths: matrix.deaths,
+            cells: matrix.rows.len(),
+            fps: 0.0,
+            trend: Vec::new(),
+        }
+    }
+
+    /// The fraction of cells that are alive.
+    pub fn density(&self) -> f64 {
+        if self.cells == 0 {
+            0.0
+        } else {
+            self.population as f64 / self.cells as f64
+        }
+    }
+
+    /// The fraction of cells that were born or died in this generation.
+    pub fn activity(&self) -> f64 {
+        if self.cells == 0 {
+            0.0
+        } else {
+            (self.births + self.deaths) as f64 / self.cells as f64
+        }
+    }
+}
+
+/// How disordered the world is: the Shannon entropy, in bits, of the
+/// patterns of live cells in the `k` by `k` blocks it divides into, from
+/// nothing for a world all alike up to `k * k` for noise. Blocks that
+/// would go off the bottom or the right are left out.
+pub fn entropy(matrix: &Matrix, k: usize) -> f64 {
+    let k = k.clamp(1, 8);
+    let mut counts: HashMap<u64, usize> = HashMap::new();
+    for top in (0..matrix.m / k).map(|b| b * k) {
+        for left in (0..matrix.n / k).map(|b| b * k) {
+            let mut block = 0u64;
+            for r in top..top + k {
+                for c in left..left + k {
+                    block = block << 1 | (matrix.rows[r * matrix.n + c] != 0) as u64;
+                }
+            }
+            *counts.entry(block).or_insert(0) += 1;
+        }
+    }
+
+    let total = counts.values().sum::<usize>().max(1) as f64;
+    counts
+        .values()
+        .map(|&count| {
+            let p = count as f64 / total;
+            p * (1.0 / p).log2()
+        })
+        .sum()
+}
+
+/// How interesting the world looks, from 0 to 1: highest halfway between
+/// order and noise, where the entropy of its `k` by `k` blocks is half what
+/// it could be, and nothing at either end. Something to score rules by.
+pub fn complexity(matrix: &Matrix, k: usize) -> f64 {
+    let k = k.clamp(1, 8);
+    let h = entropy(matrix, k) / (k * k) as f64;
+    4.0 * h * (1.0 - h)
+}
+
+/// The box-counting dimension of the live cells: how the number of boxes
+/// with anything alive in them goes up as the boxes get smaller, from 1 for
+/// cells along a line to 2 for cells filling the world, with fractals such
+/// as replicators in between. The boxes go from a cell across to a quarter
+/// of the world's width or height, doubling each time, and the slope is the
+/// best fit through all of them. Nothing alive has no dimension, taken as 0.
+pub fn dimension(matrix: &Matrix) -> f64 {
+    let mut points = Vec::new();
+    let mut side = 1;
+    while side <= (matrix.m.min(matrix.n) / 4).max(1) {
+        let (rows, cols) = (matrix.m.div_ceil(side), matrix.n.div_ceil(side));
+        let mut full = vec![false; rows * cols];
+        for (i, _) in matrix.rows.iter().enumerate().filter(|(_, &c)| c != 0) {
+            full[i / matrix.n / side * cols + i % matrix.n / side] = true;
+        }
+        let boxes = full.iter().filter(|&&f| f).count();
+        if boxes == 0 {
+            return 0.0;
+        }
+        points.push(((1.0 / side as f64).ln(), (boxes as f64).ln()));
+        side *= 2;
+    }
+    if points.len() < 2 {
+        return 0.0;
+    }
+
+    let count = points.len() as f64;
+    let (mx, my) = points
+        .iter()
+        .fold((0.0, 0.0), |(x, y), p| (x + p.0 / count, y + p.1 / count));
+    let (sxy, sxx) = points.iter().fold((0.0, 0.0), |(xy, xx), p| {
+        (xy + (p.0 - mx) * (p.1 - my), xx + (p.0 - mx) * (p.0 - mx))
+    });
+    sxy / sxx
+}
+
+/// How many cells two worlds of the same size differ in: the Hamming
+/// distance between a world and its twin, for `--twin-perturb`.
+pub fn damage(a: &Matrix, b: &Matrix) -> usize {
+    a.rows
+        .par_iter()
+        .zip(b.rows.par_iter())
+        .filter(|(x, y)| x != y)
+        .count()
+}
+
+/// Writes the numbers of every generation as a line of CSV, for
+/// `--stats-out`, whether or not the generation is drawn: along with the
+/// counts, the entropy of the world's blocks and how complex that makes it,
+/// as `entropy` and `complexity` work them out, the box-counting
+/// `dimension` of the live cells, and with `--twin-perturb` the `damage`
+/// between the world and its twin.
+///
+/// ```text
+/// # iron-cat 0.1.0
+/// # rule B3/S23
+/// generation,population,births,deaths,density,activity,entropy,complexity,dimension
+/// 0,165,0,0,0.412500,0.000000,3.843532,0.150347,1.361233
+/// 1,154,75,86,0.385000,0.402500,3.516387,0.425143,1.311465
+/// ```
+///
+/// The provenance comes first as lines starting with `#`, which pandas
+/// skips with `comment="#"` and `grep -v '^#'` takes out for anything else.
+pub struct StatsLog {
+    out: Box<dyn Write>,
+    /// The side of the blocks entropy is measured over.
+    block: usize,
+    /// Written before the first generation, and then forgotten.
+    provenance: Option<Provenance>,
+}
+
+impl StatsLog {
+    pub fn new(out: Box<dyn Write>, block: usize, provenance: &Provenance) -> StatsLog {
+        StatsLog {
+            out,
+            block,
+            provenance: Some(provenance.clone()),
+        }
+    }
+
+    pub fn write(
+        &mut self,
+        stats: &Stats,
+        matrix: &Matrix,
+        damage: Option<usize>,
+    ) -> io::Result<()> {
+        if let Some(provenance) = self.provenance.take() {
+            for line in provenance.lines() {
+                writeln!(self.out, "# {}", line)?;
+            }
+            writeln!(
+                self.out,
+                "generation,population,births,deaths,density,activity,entropy,complexity,dimension{}",
+                if damage.is_some() { ",damage" } else { "" }
+            )?;
+        }
+
+        write!(
+            self.out,
+            "{},{},{},{},{:.6},{:.6},{:.6},{:.6},{:.6}",
+            stats.generation,
+            stats.population,
+            stats.births,
+            stats.deaths,
+            stats.density(),
+            stats.activity(),
+            entropy(matrix, self.block),
+            complexity(matrix, self.block),
+            dimension(matrix)
+        )?;
+        match damage {
+            Some(damage) => writeln!(self.out, ",{}", damage),
+            None => writeln!(self.out),
+        }
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Measures the frame rate as a moving average, so the figure in the status
+/// bar doesn't jitter from frame to frame.
+#[derive(Debug, Default)]
+pub struct FrameClock {
+    last: Option<Instant>,
+    fps: f64,
+}
+
+impl FrameClock {
+    pub fn new() -> FrameClock {
+        FrameClock::default()
+    }
+
+    /// Marks the end of a frame and returns the smoothed frame rate. The
+    /// first frame only starts the clock.
+    pub fn tick(&mut self) -> f64 {
+        let now = Instant::now();
+
+        if let Some(last) = self.last {
+            let elapsed = now.duration_since(last).as_secs_f64();
+            if elapsed > 0.0 {
+                let current = 1.0 / elapsed;
+                self.fps = if self.fps == 0.0 {
+                    current
+                } else {
+                    self.fps * 0.8 + current * 0.2
+                };
+            }
+        }
+        self.last = Some(now);
+
+        self.fps
+    }
+}
+
+/// Remembers the population of the last few generations.
+#[derive(Debug, Default)]
+pub struct History {
+    populations: VecDeque<usize>,
+    length: usize,
+}
+
+impl History {
+    pub fn new(length: usize) -> History {
+        History {
+            populations: VecDeque::with_capacity(length),
+            length,
+        }
+    }
+
+    /// Adds a generation and fills in the trend of its `Stats`.
+    pub fn push(&mut self, stats: &mut Stats) {
+        if self.length == 0 {
+            return;
+        }
+        if self.populations.len() == self.length {
+            self.populations.pop_front();
+        }
+        self.populations.push_back(stats.population);
+        stats.trend = self.populations.iter().copied().collect();
+    }
+}
@@ -0,0 +1,137 @@
+//! Sweeping the density of a soup for phase transitions: `iron-cat -r life
+//! sweep --density 0.05..0.95:0.05 --runs 50` runs fifty soups at every
+//! density from 5% to 95% and says, for each, how long they took on
+//! average to settle, how full they were when they did, and how many
+//! settled at all within `--generations`.
+//!
+//! ```text
+//! density,runs,mean_lifetime,final_density,settled
+//! 0.05,50,3.7,0.003570,1.000000
+//! 0.1,50,163.9,0.024050,0.980000
+//! ```
+//!
+//! Run `R` at the `K`th density is seeded with the sweep's seed plus `K`
+//! times the number of runs plus `R`, the same soup the run would start
+//! from at that density with that `--rng-seed`.
+
+use std::fmt::Write as _;
+
+use crate::ensemble;
+use crate::provenance::Provenance;
+use crate::soup::Soup;
+use crate::Rulestring;
+
+/// How the runs at one density went.
+#[derive(Clone, Copy, Debug)]
+pub struct Row {
+    pub density: f64,
+    pub runs: u64,
+    /// Generations until settling, counting those that didn't as having
+    /// lasted as long as they were let.
+    pub lifetime: f64,
+    pub final_density: f64,
+    /// The fraction of runs that settled.
+    pub settled: f64,
+}
+
+/// Reads densities as `FROM..TO:STEP`, from one to the other inclusive, or
+/// a single density.
+pub fn densities(s: &str) -> Option<Vec<f64>> {
+    let probability = |s: &str| {
+        s.trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|p| (0.0..=1.0).contains(p))
+    };
+    let Some((from, rest)) = s.split_once("..") else {
+        return Some(vec![probability(s)?]);
+    };
+    let (to, step) = rest.split_once(':')?;
+    let (from, to, step) = (probability(from)?, probability(to)?, probability(step)?);
+    if step <= 0.0 || to < from {
+        return None;
+    }
+
+    // Worked out afresh every time so the steps don't add up errors, and
+    // rounded so that 0.15 doesn't come out as 0.15000000000000002.
+    let steps = ((to - from) / step + 1e-9).floor() as u64;
+    Some(
+        (0..=steps)
+            .map(|k| ((from + k as f64 * step) * 1e9).round() / 1e9)
+            .collect(),
+    )
+}
+
+/// Runs `runs` soups at every density in worlds of `m` by `n`, for at most
+/// `longest` generations each.
+pub fn sweep(
+    rules: &Rulestring,
+    (m, n): (usize, usize),
+    soup: &Soup,
+    densities: &[f64],
+    runs: u64,
+    seed: u64,
+    longest: u64,
+) -> Vec<Row> {
+    densities
+        .iter()
+        .enumerate()
+        .map(|(k, &density)| {
+            let soup = Soup {
+                density,
+                ..soup.clone()
+            };
+            let seed = seed.wrapping_add(k as u64 * runs);
+            let outcomes = ensemble::run(rules, (m, n), &soup, seed, runs, longest);
+            let lifetime: u64 = outcomes.iter().map(|o| o.settled.unwrap_or(longest)).sum();
+            let full: usize = outcomes.iter().map(|o| o.population).sum();
+            let settled = outcomes.iter().filter(|o| o.settled.is_some()).count();
+
+            let runs_f = runs.max(1) as f64;
+            Row {
+                density,
+                runs,
+                lifetime: lifetime as f64 / runs_f,
+                final_density: full as f64 / (m * n) as f64 / runs_f,
+                settled: settled as f64 / runs_f,
+            }
+        })
+        .collect()
+}
+
+/// The table as CSV, the provenance first as `#` lines.
+pub fn csv(rows: &[Row], provenance: &Provenance) -> String {
+    let mut out = String::new();
+    for line in provenance.lines() {
+        writeln!(out, "# {}", line).unwrap();
+    }
+    out.push_str("density,runs,mean_lifetime,final_density,settled\n");
+    for row in rows {
+        writeln!(
+            out,
+            "{},{},{:.1},{:.6},{:.6}",
+            row.density, row.runs, row.lifetime, row.final_density, row.settled
+        )
+        .unwrap();
+    }
+    out
+}
+
+/// The table as a JSON object, with the provenance and a row for every
+/// density.
+pub fn json(rows: &[Row], provenance: &Provenance) -> String {
+    let rows: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            format!(
+                "{{\"density\": {}, \"runs\": {}, \"mean_lifetime\": {:.1}, \"final_density\": {:.6}, \"settled\": {:.6}}}",
+                row.density, row.runs, row.lifetime, row.final_density, row.settled
+            )
+        })
+        .collect();
+    format!(
+        "{{\"provenance\": {}, \"rows\": [\n  {}\n]}}\n",
+        provenance.json(),
+        rows.join(",\n  ")
+    )
+}
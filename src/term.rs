@@ -0,0 +1,168 @@
+//! Looking after the terminal.
+//!
+//! While animating we switch to the alternate screen and hide the cursor,
+//! and we must put both back however the program ends. Ctrl-C and SIGTERM
+//! don't kill us outright; they only clear `RUNNING`, so the main loop can
+//! wind down, finish its recordings and restore the terminal.
+//!
+//! We also keep an eye on the terminal's size, so the view can be fitted to
+//! it again whenever the window is resized, and read keys as they are
+//! pressed. On Unix that only takes turning off line buffering and echo:
+//! full raw mode would also stop `\n` returning the cursor to the start of
+//! the line, which every frame relies on, and turn Ctrl-C into a key.
+//! Windows consoles process output separately, so there raw mode is fine.
+//!
+//! Everything we draw is made of ANSI escape codes. Windows Terminal and
+//! recent versions of conhost understand them once virtual terminal
+//! processing is turned on, which `ansi` does; older consoles would print
+//! them as they are, so there we fall back to plain output.
+
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(unix)]
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, DisableMouseCapture, EnableMouseCapture, Event};
+use crossterm::execute;
+use crossterm::terminal;
+
+static RUNNING: AtomicBool = AtomicBool::new(true);
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Makes Ctrl-C and SIGTERM ask the main loop to stop.
+pub fn install_handler() -> Result<(), ctrlc::Error> {
+    ctrlc::set_handler(|| RUNNING.store(false, Ordering::SeqCst))
+}
+
+/// False once we've been asked to stop.
+pub fn running() -> bool {
+    RUNNING.load(Ordering::SeqCst)
+}
+
+/// Makes sure the terminal will understand our escape codes, and tells us
+/// if it won't.
+#[cfg(windows)]
+pub fn ansi() -> bool {
+    crossterm::ansi_support::supports_ansi()
+}
+
+/// Every Unix terminal worth the name understands escape codes.
+#[cfg(not(windows))]
+pub fn ansi() -> bool {
+    true
+}
+
+/// The terminal settings from before `enter`, to put back in `leave`.
+#[cfg(unix)]
+static SAVED: Mutex<Option<libc::termios>> = Mutex::new(None);
+
+/// Lets keys through as they are pressed, without echoing them. Nothing
+/// happens if standard input isn't a terminal.
+#[cfg(unix)]
+fn keys_on() {
+    // SAFETY: `termios` is plain data, filled in by `tcgetattr` before use.
+    unsafe {
+        let mut t: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(libc::STDIN_FILENO, &mut t) != 0 {
+            return;
+        }
+        *SAVED.lock().unwrap() = Some(t);
+
+        t.c_lflag &= !(libc::ICANON | libc::ECHO);
+        t.c_cc[libc::VMIN] = 1;
+        t.c_cc[libc::VTIME] = 0;
+        libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &t);
+    }
+}
+
+/// Undoes `keys_on`.
+#[cfg(unix)]
+fn keys_off() {
+    if let Some(t) = SAVED.lock().unwrap().take() {
+        // SAFETY: `t` is what `tcgetattr` gave us.
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &t);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn keys_on() {
+    let _ = terminal::enable_raw_mode();
+}
+
+#[cfg(not(unix))]
+fn keys_off() {
+    let _ = terminal::disable_raw_mode();
+}
+
+/// Switches to the alternate screen, hides the cursor and starts reading
+/// keys and the mouse as they are used.
+pub fn enter() {
+    if !ACTIVE.swap(true, Ordering::SeqCst) {
+        keys_on();
+        print!("\x1B[?1049h\x1B[?25l");
+        let _ = execute!(io::stdout(), EnableMouseCapture);
+        let _ = io::stdout().flush();
+    }
+}
+
+/// Undoes `enter`. Safe to call at any time, and more than once.
+pub fn leave() {
+    if ACTIVE.swap(false, Ordering::SeqCst) {
+        let _ = execute!(io::stdout(), DisableMouseCapture);
+        print!("\x1B[0m\x1B[?25h\x1B[?1049l");
+        let _ = io::stdout().flush();
+        keys_off();
+    }
+}
+
+/// The terminal size in columns and rows, if we're attached to one.
+pub fn size() -> Option<(usize, usize)> {
+    terminal::size()
+        .ok()
+        .filter(|&(c, r)| c > 0 && r > 0)
+        .map(|(c, r)| (c as usize, r as usize))
+}
+
+/// Sleeps until `end`, waking up early if we're asked to stop, without
+/// looking at the terminal's input.
+pub fn sleep(end: Instant) {
+    while running() {
+        let now = Instant::now();
+        if now >= end {
+            break;
+        }
+        thread::sleep((end - now).min(Duration::from_millis(50)));
+    }
+}
+
+/// Sleeps until `end`, waking up early if we're asked to stop or anything
+/// happens in the terminal, such as a key being pressed or the window being
+/// resized. Returns what happened, or `None` if nothing did.
+///
+/// Resizes arrive as SIGWINCH, which crossterm turns into an event for us.
+/// If there's no terminal to read events from we just sleep.
+pub fn wait(end: Instant) -> Option<Event> {
+    while running() {
+        let now = Instant::now();
+        if now >= end {
+            break;
+        }
+        let slice = (end - now).min(Duration::from_millis(50));
+
+        match event::poll(slice) {
+            Ok(true) => {
+                if let Ok(event) = event::read() {
+                    return Some(event);
+                }
+            }
+            Ok(false) => {}
+            Err(_) => thread::sleep(slice),
+        }
+    }
+
+    None
+}
@@ -0,0 +1,554 @@
+//! Themes decide what each cell looks like on screen.
+//!
+//! A theme is read from a TOML file given with `--theme-file`. Every cell
+//! state gets its own glyph and optional foreground and background colours,
+//! and live cells can additionally be tinted according to how many
+//! generations they have survived.
+//!
+//! # Format
+//!
+//! ```toml
+//! [state.0]
+//! glyph = "  "
+//! bg = "#101010"
+//!
+//! [state.1]
+//! glyph = "██"
+//! fg = "#f0f0f0"
+//!
+//! [[gradient]]
+//! age = 0
+//! color = "#ffffff"
+//!
+//! [[gradient]]
+//! age = 30
+//! color = "#3050ff"
+//! ```
+//!
+//! Glyphs should be two columns wide to keep the grid square. States that
+//! have no entry of their own fall back to the highest state that does, so
+//! a two-state theme still works for rules with more states, and states
+//! skipped over between two that do are graded from one to the other.
+//! Gradient stops are linearly interpolated and override the `fg` of every
+//! live state.
+//!
+//! Individual states can also be styled from the command line with
+//! `--state-style 2=▒▒,#c04000`, on top of any theme file.
+//!
+//! `--charset emoji` swaps the glyphs for emoji that tell young cells from
+//! old ones and show where cells have just died. Emoji are two columns wide
+//! in most terminals, so they take the place of a two-character glyph.
+//!
+//! With `--color-by diff` the theme's colours give way for cells that have
+//! just changed: newborn cells are green and cells that have just died are
+//! red, fading back to the dead colour over a few generations.
+
+use std::collections::BTreeMap;
+use std::{error, fmt, fs, io};
+
+use serde::Deserialize;
+use unicode_width::UnicodeWidthStr;
+
+/// Represents everything that can go wrong while loading a theme.
+#[derive(Debug)]
+pub enum ThemeError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    BadState(String),
+    BadColor(String),
+    BadStyle(String),
+}
+
+impl error::Error for ThemeError {}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeError::Io(e) => write!(f, "Could not read theme file: {}", e),
+            ThemeError::Parse(e) => write!(f, "Could not parse theme file: {}", e),
+            ThemeError::BadState(s) => write!(f, "Invalid state {} in theme", s),
+            ThemeError::BadColor(s) => write!(f, "Invalid colour {} in theme", s),
+            ThemeError::BadStyle(s) => write!(f, "Invalid state style {}", s),
+        }
+    }
+}
+
+/// A 24-bit colour, written as `#rrggbb` in theme files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    /// Linear interpolation between two colours, `t` running from 0 to 1.
+    pub fn lerp(self, other: Color, t: f32) -> Color {
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+
+        Color {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+        }
+    }
+}
+
+/// Formats the colour as `#rrggbb`, the same way it is written in themes.
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+impl std::str::FromStr for Color {
+    type Err = ThemeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.trim().trim_start_matches('#');
+
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ThemeError::BadColor(s.to_string()));
+        }
+
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+
+        Ok(Color {
+            r: channel(0),
+            g: channel(2),
+            b: channel(4),
+        })
+    }
+}
+
+pub const BLACK: Color = Color { r: 0, g: 0, b: 0 };
+pub const WHITE: Color = Color {
+    r: 255,
+    g: 255,
+    b: 255,
+};
+
+const GREEN: Color = Color {
+    r: 0x33,
+    g: 0xcc,
+    b: 0x33,
+};
+const RED: Color = Color {
+    r: 0xee,
+    g: 0x33,
+    b: 0x33,
+};
+
+/// Generations it takes a dead cell to fade from red when colouring by
+/// changes.
+const FADE: u32 = 4;
+
+/// What decides the colour of a cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Coloring {
+    /// Its state, and its age if the theme has a gradient.
+    #[default]
+    State,
+    /// Whether it was just born or just died.
+    Diff,
+}
+
+/// Generations a cell has to survive before it counts as old.
+const MATURE: u32 = 10;
+
+/// The glyphs cells are drawn with.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Charset {
+    /// Each state's own glyph.
+    #[default]
+    Blocks,
+    /// Emoji for young, old, dying and dead cells, in that order.
+    Emoji([String; 4]),
+}
+
+impl Charset {
+    /// The emoji charset, with `given` replacing the default emoji from the
+    /// start of the list. Everything is padded out to the same width.
+    pub fn emoji(given: &[&str]) -> Charset {
+        let mut glyphs = [
+            String::from("🌱"),
+            String::from("🌳"),
+            String::from("🔥"),
+            String::from("  "),
+        ];
+
+        for (glyph, &g) in glyphs.iter_mut().zip(given) {
+            *glyph = g.to_string();
+        }
+
+        let width = glyphs.iter().map(|g| g.width()).max().unwrap_or(0);
+        for glyph in glyphs.iter_mut() {
+            let pad = width - glyph.width();
+            glyph.push_str(&" ".repeat(pad));
+        }
+
+        Charset::Emoji(glyphs)
+    }
+}
+
+/// How a single cell state is drawn.
+#[derive(Clone, Debug)]
+pub struct Style {
+    pub glyph: String,
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+}
+
+impl Style {
+    fn plain(glyph: &str) -> Style {
+        Style {
+            glyph: glyph.to_string(),
+            fg: None,
+            bg: None,
+        }
+    }
+}
+
+/// The raw shape of a theme file, before colours are validated.
+#[derive(Deserialize)]
+struct ThemeFile {
+    #[serde(default)]
+    state: BTreeMap<String, StateFile>,
+    #[serde(default)]
+    gradient: Vec<StopFile>,
+}
+
+#[derive(Deserialize)]
+struct StateFile {
+    glyph: Option<String>,
+    fg: Option<String>,
+    bg: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StopFile {
+    age: u32,
+    color: String,
+}
+
+/// A complete palette: one `Style` per state plus an optional age gradient.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    states: Vec<Style>,
+    gradient: Vec<(u32, Color)>,
+    coloring: Coloring,
+    charset: Charset,
+}
+
+/// The default theme is the original uncoloured shading.
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            states: vec![Style::plain("░░"), Style::plain("▓▓")],
+            gradient: Vec::new(),
+            coloring: Coloring::State,
+            charset: Charset::Blocks,
+        }
+    }
+}
+
+impl Theme {
+    /// Reads and validates a theme file. Anything the file leaves out is
+    /// taken from the default theme.
+    pub fn load(path: &str) -> Result<Theme, ThemeError> {
+        let text = fs::read_to_string(path).map_err(ThemeError::Io)?;
+        let file: ThemeFile = toml::from_str(&text).map_err(ThemeError::Parse)?;
+
+        let mut theme = Theme::default();
+        let mut states = BTreeMap::new();
+
+        for (key, state) in file.state {
+            let index = key
+                .parse::<usize>()
+                .map_err(|_| ThemeError::BadState(key.clone()))?;
+            states.insert(index, state);
+        }
+        theme.apply(states)?;
+
+        for stop in file.gradient {
+            theme.gradient.push((stop.age, stop.color.parse()?));
+        }
+        theme.gradient.sort_by_key(|&(age, _)| age);
+
+        Ok(theme)
+    }
+
+    /// Overrides the styles of some states with `--state-style`, written as
+    /// `STATE=GLYPH[,FG[,BG]]`. An empty glyph keeps the one there was.
+    pub fn restyle(&mut self, specs: &[&str]) -> Result<(), ThemeError> {
+        let mut states = BTreeMap::new();
+
+        for spec in specs {
+            let bad = || ThemeError::BadStyle(spec.to_string());
+            let (index, style) = spec.split_once('=').ok_or_else(bad)?;
+            let index = index.trim().parse::<usize>().map_err(|_| bad())?;
+            let mut parts = style.split(',');
+            let glyph = parts.next().filter(|g| !g.is_empty());
+
+            states.insert(
+                index,
+                StateFile {
+                    glyph: glyph.map(String::from),
+                    fg: parts.next().map(String::from),
+                    bg: parts.next().map(String::from),
+                },
+            );
+        }
+
+        self.apply(states)
+    }
+
+    /// Applies the styles given for some states. States that are skipped
+    /// over get colours graded between the states either side of them, so
+    /// a rule whose cells decay through several states only needs the first
+    /// and last of them styled.
+    fn apply(&mut self, states: BTreeMap<usize, StateFile>) -> Result<(), ThemeError> {
+        let mut given = vec![true; self.states.len()];
+
+        for (index, state) in states {
+            while self.states.len() <= index {
+                let last = self.states[self.states.len() - 1].clone();
+                self.states.push(last);
+                given.push(false);
+            }
+
+            let style = &mut self.states[index];
+            if let Some(glyph) = state.glyph {
+                style.glyph = glyph;
+            }
+            if let Some(fg) = state.fg {
+                style.fg = Some(fg.parse()?);
+            }
+            if let Some(bg) = state.bg {
+                style.bg = Some(bg.parse()?);
+            }
+            given[index] = true;
+        }
+
+        for i in 0..self.states.len() {
+            if given[i] {
+                continue;
+            }
+
+            // The last state is always given, so there is one above.
+            let lo = (0..i).rev().find(|&j| given[j]).unwrap_or(0);
+            let hi = (i + 1..given.len()).find(|&j| given[j]).unwrap();
+            let t = (i - lo) as f32 / (hi - lo) as f32;
+            let grade = |a: Option<Color>, b: Option<Color>| match (a, b) {
+                (Some(a), Some(b)) => Some(a.lerp(b, t)),
+                (a, b) => a.or(b),
+            };
+
+            let (low, high) = (&self.states[lo], &self.states[hi]);
+            self.states[i] = Style {
+                glyph: low.glyph.clone(),
+                fg: grade(low.fg, high.fg),
+                bg: grade(low.bg, high.bg),
+            };
+        }
+
+        Ok(())
+    }
+
+    /// The same theme with cells coloured according to `coloring`.
+    pub fn color_by(self, coloring: Coloring) -> Theme {
+        Theme { coloring, ..self }
+    }
+
+    /// The same theme drawn with `charset`.
+    pub fn with_charset(self, charset: Charset) -> Theme {
+        Theme { charset, ..self }
+    }
+
+    /// How many states have a style of their own.
+    pub fn states(&self) -> usize {
+        self.states.len()
+    }
+
+    /// How many columns a cell takes up on screen.
+    pub fn cell_width(&self) -> usize {
+        let width = match &self.charset {
+            Charset::Blocks => self.states.iter().map(|s| s.glyph.width()).max(),
+            Charset::Emoji(glyphs) => glyphs.iter().map(|g| g.width()).max(),
+        };
+
+        width.unwrap_or(1).max(1)
+    }
+
+    /// The glyph a cell is drawn with.
+    pub fn glyph(&self, state: usize, age: u32) -> &str {
+        match &self.charset {
+            Charset::Blocks => &self.style(state).glyph,
+            Charset::Emoji(glyphs) => {
+                let i = match (state != 0, age) {
+                    (true, a) if a < MATURE => 0,
+                    (true, _) => 1,
+                    (false, a) if a < FADE => 2,
+                    (false, _) => 3,
+                };
+                &glyphs[i]
+            }
+        }
+    }
+
+    /// The style for a state, falling back to the highest defined state.
+    pub fn style(&self, state: usize) -> &Style {
+        &self.states[state.min(self.states.len() - 1)]
+    }
+
+    /// Where `age` falls on the gradient, if there is one.
+    fn age_color(&self, age: u32) -> Option<Color> {
+        let first = self.gradient.first()?;
+        if age <= first.0 {
+            return Some(first.1);
+        }
+
+        for pair in self.gradient.windows(2) {
+            let (a, ca) = pair[0];
+            let (b, cb) = pair[1];
+            if age <= b {
+                let t = (age - a) as f32 / (b - a).max(1) as f32;
+                return Some(ca.lerp(cb, t));
+            }
+        }
+
+        self.gradient.last().map(|&(_, c)| c)
+    }
+
+    /// The colour of a cell that has just changed, when colouring by
+    /// changes. Cells that have been as they are for a while get `None`.
+    fn diff_color(&self, state: usize, age: u32) -> Option<Color> {
+        if self.coloring != Coloring::Diff {
+            return None;
+        }
+
+        if state != 0 {
+            (age == 0).then_some(GREEN)
+        } else if age < FADE {
+            let style = self.style(0);
+            let dead = style.bg.or(style.fg).unwrap_or(BLACK);
+            Some(RED.lerp(dead, age as f32 / FADE as f32))
+        } else {
+            None
+        }
+    }
+
+    /// The colours a cell is drawn with, taking its age into account.
+    pub fn colors(&self, state: usize, age: u32) -> (Option<Color>, Option<Color>) {
+        let style = self.style(state);
+
+        if let Some(c) = self.diff_color(state, age) {
+            return (Some(c), style.bg);
+        }
+
+        let fg = if state != 0 {
+            self.age_color(age).or(style.fg)
+        } else {
+            style.fg
+        };
+
+        (fg, style.bg)
+    }
+
+    /// The single colour a cell is painted with when drawing pixels rather
+    /// than glyphs. Dead cells prefer the background and live cells the
+    /// foreground, falling back to black and white.
+    pub fn pixel(&self, state: usize, age: u32) -> Color {
+        if let Some(c) = self.diff_color(state, age) {
+            return c;
+        }
+
+        let (fg, bg) = self.colors(state, age);
+
+        if state == 0 {
+            bg.or(fg).unwrap_or(BLACK)
+        } else {
+            fg.or(bg).unwrap_or(WHITE)
+        }
+    }
+
+    /// Renders one row of cells, only emitting colour codes when they change
+    /// from one cell to the next.
+    pub fn render_row(&self, states: &[usize], ages: &[u32]) -> String {
+        let mut out = String::new();
+        let mut current = (None, None);
+
+        for (&state, &age) in states.iter().zip(ages) {
+            let colors = self.colors(state, age);
+            if colors != current {
+                out.push_str(&sgr(colors));
+                current = colors;
+            }
+            out.push_str(self.glyph(state, age));
+        }
+
+        if current != (None, None) {
+            out.push_str("\x1B[0m");
+        }
+
+        out
+    }
+
+    /// Renders one row of a zoomed out view, where every character stands
+    /// for a block of cells and `densities` are the fractions of them that
+    /// are alive. Denser blocks get a heavier shade and, if the theme has
+    /// colours, a colour nearer to that of live cells.
+    pub fn render_densities(&self, densities: &[f32]) -> String {
+        let width = self.cell_width();
+        // Ages that read as neither just born nor just died.
+        let (dead_age, live_age) = (u32::MAX, 1);
+        let colored =
+            self.colors(0, dead_age) != (None, None) || self.colors(1, live_age) != (None, None);
+        let (dead, live) = (self.pixel(0, dead_age), self.pixel(1, live_age));
+        let mut out = String::new();
+        let mut current = (None, None);
+
+        for &d in densities {
+            let shade = if d > 0.0 {
+                1 + (d * (SHADES.len() - 2) as f32).round() as usize
+            } else {
+                0
+            };
+
+            if colored {
+                let colors = (Some(dead.lerp(live, d)), self.style(0).bg);
+                if colors != current {
+                    out.push_str(&sgr(colors));
+                    current = colors;
+                }
+            }
+            for _ in 0..width {
+                out.push(SHADES[shade.min(SHADES.len() - 1)]);
+            }
+        }
+
+        if current != (None, None) {
+            out.push_str("\x1B[0m");
+        }
+
+        out
+    }
+}
+
+/// Shades for zoomed out views, from empty to full.
+const SHADES: [char; 5] = [' ', '░', '▒', '▓', '█'];
+
+/// The escape sequence that selects the given colours.
+pub fn sgr((fg, bg): (Option<Color>, Option<Color>)) -> String {
+    let mut code = String::from("\x1B[0");
+
+    if let Some(c) = fg {
+        code.push_str(&format!(";38;2;{};{};{}", c.r, c.g, c.b));
+    }
+    if let Some(c) = bg {
+        code.push_str(&format!(";48;2;{};{};{}", c.r, c.g, c.b));
+    }
+
+    code.push('m');
+    code
+}
@@ -0,0 +1,627 @@
+//! Everything that surrounds the grid on screen is put together here.
+//!
+//! The `Theme` knows how to draw cells; a `View` knows how to lay those
+//! cells out, and which decorations (the ruler, the crosshair and the status
+//! bar) to draw around them.
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::render::{kitty, sixel, Canvas, Output};
+use crate::stats::Stats;
+use crate::theme::Theme;
+use crate::Matrix;
+
+/// Columns between two labelled ticks on the ruler.
+const TICK: usize = 5;
+
+/// Display settings that are independent of the theme.
+#[derive(Debug, Default)]
+pub struct View {
+    /// Draw a border with row and column labels around the grid.
+    pub ruler: bool,
+    /// Mark the cursor's row and column on the ruler and print its
+    /// coordinates. Only has an effect while there is a cursor.
+    pub crosshair: bool,
+    /// The interactive cursor as `(row, column)`, if there is one.
+    pub cursor: Option<(usize, usize)>,
+    /// Print a line of statistics below the grid.
+    pub status: bool,
+    /// Whether the grid is drawn with characters or pixels.
+    pub output: Output,
+    /// Pixels per cell for the pixel outputs.
+    pub scale: usize,
+    /// The most rows and columns of cells that fit on screen, if we know.
+    pub window: Option<(usize, usize)>,
+    /// The world coordinates of the top left cell on screen.
+    pub origin: (usize, usize),
+    /// The terminal size in columns and rows, once `fit` has been called.
+    pub terminal: Option<(usize, usize)>,
+    /// How many cells across each character covers. Zero picks whatever it
+    /// takes to fit the whole world in the window.
+    pub zoom: usize,
+    /// How many generations of population to plot under the grid, if any.
+    pub sparkline: usize,
+    /// Say in the status bar that the run is paused.
+    pub paused: bool,
+    /// Opposite corners of the selected rectangle of cells, if there is
+    /// one, in world coordinates.
+    pub selection: Option<((usize, usize), (usize, usize))>,
+    /// A line telling the user what just happened, under everything else.
+    pub message: Option<String>,
+    /// Describe the cell under the cursor under the grid, unless there's
+    /// a message to show instead.
+    pub inspect: bool,
+    /// Lines to show in a box over the middle of the grid, such as help.
+    pub overlay: Option<Vec<String>>,
+}
+
+impl View {
+    /// Produces a complete frame, one line per terminal row.
+    ///
+    /// Pixel outputs replace the grid and the ruler with an image, but the
+    /// status bar is still printed underneath as text, ending with what the
+    /// world has settled into, if anything is said about it.
+    pub fn draw(
+        &self,
+        theme: &Theme,
+        matrix: &Matrix,
+        stats: &Stats,
+        settled: Option<&str>,
+    ) -> String {
+        if self.output == Output::Plain {
+            return matrix.to_string();
+        }
+
+        if !self.output.is_text() {
+            let canvas = Canvas::new(theme, matrix, self.scale);
+            let mut out = match self.output {
+                Output::Sixel => sixel::encode(&canvas),
+                Output::Kitty => kitty::kitty(&canvas),
+                Output::Iterm => kitty::iterm(&canvas),
+                _ => unreachable!(),
+            };
+            out.push('\n');
+            let overlay = self.overlay.iter().flatten().cloned();
+            for line in overlay.chain(self.footer(matrix, stats, settled)) {
+                out.push_str(&line);
+                out.push('\n');
+            }
+            return out;
+        }
+
+        let block = self.block(matrix);
+        let (rows, cols) = self.visible(matrix);
+        let mut lines: Vec<String> = rows
+            .iter()
+            .map(|&r| {
+                if block > 1 {
+                    let densities: Vec<f32> =
+                        cols.iter().map(|&c| density(matrix, r, c, block)).collect();
+                    return theme.render_densities(&densities);
+                }
+
+                let states: Vec<usize> = cols
+                    .iter()
+                    .map(|&c| matrix.rows[r * matrix.n + c])
+                    .collect();
+                let ages: Vec<u32> = cols
+                    .iter()
+                    .map(|&c| matrix.ages[r * matrix.n + c])
+                    .collect();
+
+                // The cursor and the selection are drawn in reverse video.
+                // Every run of cells ends by resetting its colours, which
+                // turns it off again.
+                let marked: Vec<bool> = cols.iter().map(|&c| self.marked(r, c)).collect();
+                let mut line = String::new();
+                let mut i = 0;
+                while i < cols.len() {
+                    let run = marked[i..].iter().take_while(|&&m| m == marked[i]).count();
+                    let cells = theme.render_row(&states[i..i + run], &ages[i..i + run]);
+                    if marked[i] {
+                        line.push_str(&format!("\x1B[7m{}\x1B[27m", cells));
+                    } else {
+                        line.push_str(&cells);
+                    }
+                    i += run;
+                }
+                line
+            })
+            .collect();
+
+        if self.ruler {
+            lines = self.ruler(lines, &rows, &cols, matrix.m, theme.cell_width());
+        }
+        if let Some(overlay) = &self.overlay {
+            lines = self.boxed(lines, overlay);
+        }
+
+        lines.extend(self.footer(matrix, stats, settled));
+
+        let mut out = lines.join("\n");
+        out.push('\n');
+        out
+    }
+
+    /// Draws `text` in a box over the middle of `lines`, leaving the rest
+    /// of them showing around it. Anything that doesn't fit is cut off.
+    fn boxed(&self, mut lines: Vec<String>, text: &[String]) -> Vec<String> {
+        let inner = text.iter().map(|l| visible_width(l)).max().unwrap_or(0);
+        let mut boxed = vec![format!("╭{}╮", "─".repeat(inner + 2))];
+        for line in text {
+            let pad = " ".repeat(inner - visible_width(line));
+            boxed.push(format!("│ {}{} │", line, pad));
+        }
+        boxed.push(format!("╰{}╯", "─".repeat(inner + 2)));
+
+        let across = lines.iter().map(|l| visible_width(l)).max().unwrap_or(0);
+        let left = across.saturating_sub(inner + 4) / 2;
+        let top = lines.len().saturating_sub(boxed.len()) / 2;
+        let room = self
+            .terminal
+            .map_or(usize::MAX, |(w, _)| w.saturating_sub(left));
+
+        for (line, b) in lines[top..].iter_mut().zip(boxed) {
+            let b: String = b.chars().take(room).collect();
+            *line = splice(line, left, &b);
+        }
+
+        lines
+    }
+
+    /// Whether the cell at `row`, `col` is under the cursor or selected.
+    fn marked(&self, row: usize, col: usize) -> bool {
+        let within = |a: usize, b: usize, x: usize| a.min(b) <= x && x <= a.max(b);
+
+        self.cursor == Some((row, col))
+            || self
+                .selection
+                .is_some_and(|((r0, c0), (r1, c1))| within(r0, r1, row) && within(c0, c1, col))
+    }
+
+    /// The lines under the grid: the population sparkline, the status bar
+    /// and any message, whichever there are. All are cut short rather than
+    /// wrap.
+    pub fn footer(&self, matrix: &Matrix, stats: &Stats, settled: Option<&str>) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        if self.sparkline > 0 {
+            let from = stats.trend.len().saturating_sub(self.sparkline);
+            lines.push(sparkline(&stats.trend[from..]));
+        }
+        if self.status {
+            lines.push(status_line(
+                matrix,
+                stats,
+                self.paused,
+                self.cursor,
+                settled,
+            ));
+        }
+        match (&self.message, self.cursor) {
+            (Some(message), _) => lines.push(message.clone()),
+            (None, Some((r, c))) if self.inspect => lines.push(inspect_line(matrix, r, c)),
+            _ => {}
+        }
+
+        lines
+            .into_iter()
+            .map(|line| match self.terminal {
+                Some((width, _)) => line.chars().take(width).collect::<String>() + "\x1B[K",
+                None => line + "\x1B[K",
+            })
+            .collect()
+    }
+
+    /// The side of the square block of cells behind each character.
+    pub fn block(&self, matrix: &Matrix) -> usize {
+        match (self.zoom, self.window) {
+            (0, Some((h, w))) => matrix.m.div_ceil(h).max(matrix.n.div_ceil(w)).max(1),
+            (0, None) => 1,
+            (z, _) => z,
+        }
+    }
+
+    /// The rows and columns of the world that are on screen, in order. The
+    /// window starts at `origin` and wraps around the edges like the world.
+    /// When zoomed out these are the first row and column of each block.
+    pub fn visible(&self, matrix: &Matrix) -> (Vec<usize>, Vec<usize>) {
+        let block = self.block(matrix);
+        let (h, w) = self.window.unwrap_or((matrix.m, matrix.n));
+        let rows = (0..h.min(matrix.m.div_ceil(block)))
+            .map(|i| (self.origin.0 + i * block) % matrix.m)
+            .collect();
+        let cols = (0..w.min(matrix.n.div_ceil(block)))
+            .map(|i| (self.origin.1 + i * block) % matrix.n)
+            .collect();
+
+        (rows, cols)
+    }
+
+    /// The world coordinates of the cell drawn at `column`, `row` on screen,
+    /// if a cell is drawn there. Only cells drawn one to a character can be
+    /// picked out.
+    pub fn cell_at(
+        &self,
+        matrix: &Matrix,
+        cell_width: usize,
+        (column, row): (usize, usize),
+    ) -> Option<(usize, usize)> {
+        if !self.output.is_text() || self.block(matrix) != 1 {
+            return None;
+        }
+
+        let (mut x, mut y) = (column, row);
+        if self.ruler {
+            x = x.checked_sub(gutter(matrix.m) + 1)?;
+            y = y.checked_sub(2)?;
+        }
+
+        let (rows, cols) = self.visible(matrix);
+        Some((*rows.get(y)?, *cols.get(x / cell_width.max(1))?))
+    }
+
+    /// Moves the window the least it takes to bring the cursor on screen,
+    /// if it isn't already.
+    pub fn reveal(&mut self, matrix: &Matrix) {
+        let (y, x) = match self.cursor {
+            Some(cursor) if self.block(matrix) == 1 => cursor,
+            _ => return,
+        };
+        let (h, w) = self.window.unwrap_or((matrix.m, matrix.n));
+
+        self.origin = (
+            follow(self.origin.0, y, h.min(matrix.m), matrix.m),
+            follow(self.origin.1, x, w.min(matrix.n), matrix.n),
+        );
+    }
+
+    /// Keeps the cursor and the window inside a world that may have changed
+    /// size, letting go of a selection that no longer fits.
+    pub fn clamp(&mut self, matrix: &Matrix) {
+        let inside = |(r, c): (usize, usize)| r < matrix.m && c < matrix.n;
+        let last = |(r, c): (usize, usize)| {
+            (
+                r.min(matrix.m.saturating_sub(1)),
+                c.min(matrix.n.saturating_sub(1)),
+            )
+        };
+
+        self.cursor = self.cursor.map(last);
+        self.origin = last(self.origin);
+        if self
+            .selection
+            .is_some_and(|(from, to)| !inside(from) || !inside(to))
+        {
+            self.selection = None;
+        }
+    }
+
+    /// Sizes the window to a terminal of `columns` by `rows` characters,
+    /// leaving room for the ruler and status bar. Worlds that fit are shown
+    /// whole; anything larger is cropped to the window.
+    pub fn fit(&mut self, size: (usize, usize), matrix: &Matrix, cell_width: usize) {
+        self.window = Some(self.room(size, matrix.m, cell_width));
+        self.terminal = Some(size);
+    }
+
+    /// How many rows and columns of cells fit in a terminal of `columns` by
+    /// `rows` characters, for a world of `m` rows, once the ruler and status
+    /// bar have taken their share.
+    pub fn room(
+        &self,
+        (columns, rows): (usize, usize),
+        m: usize,
+        cell_width: usize,
+    ) -> (usize, usize) {
+        // The cursor sits on the line below every frame, and one more line
+        // is kept free for messages.
+        let (mut chrome_rows, mut chrome_cols) = (2, 0);
+
+        if self.ruler {
+            chrome_rows += 3;
+            chrome_cols += gutter(m) + 2;
+        }
+        if self.sparkline > 0 {
+            chrome_rows += 1;
+        }
+        if self.status {
+            chrome_rows += 1;
+        }
+
+        let h = rows.saturating_sub(chrome_rows).max(1);
+        let w = (columns.saturating_sub(chrome_cols) / cell_width.max(1)).max(1);
+
+        (h, w)
+    }
+
+    /// Wraps the grid in a box, with a tick on every fifth row and column
+    /// and their numbers in the margins. `rows` and `cols` are the world
+    /// coordinates of what is on screen, which need not start at zero.
+    ///
+    /// With the crosshair on, the cursor's row and column are marked with
+    /// arrows on the border and its coordinates are printed beneath.
+    fn ruler(
+        &self,
+        lines: Vec<String>,
+        rows: &[usize],
+        cols: &[usize],
+        m: usize,
+        cell_width: usize,
+    ) -> Vec<String> {
+        let cursor = if self.crosshair { self.cursor } else { None };
+        let gutter = gutter(m);
+        let pad = " ".repeat(gutter);
+        let width = cols.len() * cell_width;
+        let mut out = Vec::with_capacity(lines.len() + 3);
+
+        let mut labels = String::new();
+        for (i, &c) in cols.iter().enumerate() {
+            if c % TICK == 0 && labels.chars().count() <= i * cell_width {
+                while labels.chars().count() < i * cell_width {
+                    labels.push(' ');
+                }
+                labels.push_str(&c.to_string());
+            }
+        }
+        let labels: String = labels.chars().take(width).collect();
+        out.push(format!("{} {}", pad, labels));
+
+        let mut top = String::new();
+        for &c in cols {
+            let tick = if cursor.map(|(_, x)| x) == Some(c) {
+                '▼'
+            } else if c % TICK == 0 {
+                '┴'
+            } else {
+                '─'
+            };
+            top.push(tick);
+            top.push_str(&"─".repeat(cell_width - 1));
+        }
+        out.push(format!("{}┌{}┐", pad, top));
+
+        for (&r, line) in rows.iter().zip(lines) {
+            let label = if r % TICK == 0 {
+                format!("{:>width$}", r, width = gutter - 1)
+            } else {
+                " ".repeat(gutter - 1)
+            };
+            let tick = if cursor.map(|(y, _)| y) == Some(r) {
+                '▶'
+            } else if r % TICK == 0 {
+                '┤'
+            } else {
+                '│'
+            };
+            out.push(format!("{} {}{}│", label, tick, line));
+        }
+
+        let bottom = match cursor {
+            Some((r, c)) => {
+                let coords = format!(" ({}, {}) ", r, c);
+                let rest = width.saturating_sub(coords.chars().count());
+                let coords: String = coords.chars().take(width).collect();
+                format!("{}{}", coords, "─".repeat(rest))
+            }
+            None => "─".repeat(width),
+        };
+        out.push(format!("{}└{}┘", pad, bottom));
+
+        out
+    }
+}
+
+/// The fraction of live cells in the `block` by `block` square with its top
+/// left corner at `row`, `col`. Blocks stop at the edges of the world rather
+/// than wrapping, so the last row and column of blocks may be smaller.
+fn density(matrix: &Matrix, row: usize, col: usize, block: usize) -> f32 {
+    let rows = row..(row + block).min(matrix.m);
+    let cols = col..(col + block).min(matrix.n);
+    let count = rows.len() * cols.len();
+    let live = rows
+        .flat_map(|r| matrix.rows[r * matrix.n..][cols.clone()].iter())
+        .filter(|&&c| c != 0)
+        .count();
+
+    live as f32 / count.max(1) as f32
+}
+
+/// Where a window of `shown` out of `size` rows or columns should start to
+/// show `target`, having started at `start`. A target just past either edge
+/// is brought in at that edge.
+fn follow(start: usize, target: usize, shown: usize, size: usize) -> usize {
+    let ahead = (target + size - start) % size;
+    if ahead < shown {
+        start
+    } else if ahead - (shown - 1) <= size - ahead {
+        (target + size + 1 - shown) % size
+    } else {
+        target
+    }
+}
+
+/// Width of the ruler's row labels plus the space after them.
+fn gutter(m: usize) -> usize {
+    (m.max(1) - 1).to_string().len() + 1
+}
+
+/// Plots populations with block elements, scaled so that the lowest is the
+/// shortest bar and the highest the tallest.
+fn sparkline(populations: &[usize]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let lo = populations.iter().min().copied().unwrap_or(0);
+    let hi = populations.iter().max().copied().unwrap_or(0);
+
+    populations
+        .iter()
+        .map(|&p| BARS[(p - lo) * (BARS.len() - 1) / (hi - lo).max(1)])
+        .collect()
+}
+
+/// The status bar. When drawn it is followed by the "erase to end of line"
+/// code so a shorter line doesn't leave bits of the previous one behind.
+fn status_line(
+    matrix: &Matrix,
+    stats: &Stats,
+    paused: bool,
+    cursor: Option<(usize, usize)>,
+    settled: Option<&str>,
+) -> String {
+    format!(
+        "gen {}{}{} │ pop {} (+{} -{}) │ {} │ {:.1} fps │ density {:.1}%{}",
+        stats.generation,
+        if paused { " (paused)" } else { "" },
+        cursor.map_or(String::new(), |(r, c)| format!(" │ edit ({}, {})", r, c)),
+        stats.population,
+        stats.births,
+        stats.deaths,
+        matrix.rules,
+        stats.fps,
+        stats.density() * 100.0,
+        settled.map_or(String::new(), |s| format!(" │ {}", s))
+    )
+}
+
+/// Everything about one cell: where it is, how long it has been alive or
+/// dead, its neighbours and what the rule will make of them.
+fn inspect_line(matrix: &Matrix, row: usize, col: usize) -> String {
+    let i = row * matrix.n + col;
+    let (state, age) = (matrix.rows[i], matrix.ages[i]);
+    let sum = matrix.neighbours(row, col);
+    let rules = &matrix.rules;
+
+    let now = match (state, age) {
+        (0, u32::MAX) => "never alive".to_string(),
+        (0, age) => format!("dead for {}", age),
+        (1, age) => format!("alive for {}", age),
+        (state, age) => format!("state {} for {}", state, age),
+    };
+    let next = match (state != 0, rules.b[sum], rules.s[sum]) {
+        (false, true, _) => format!("born next (B{})", sum),
+        (false, false, _) => format!("stays dead (no B{})", sum),
+        (true, true, _) => format!("survives (B{})", sum),
+        (true, false, true) => format!("survives (S{})", sum),
+        (true, false, false) => format!("dies (no S{})", sum),
+    };
+
+    format!(
+        "({}, {}) │ {} │ {} neighbours │ {}",
+        row, col, now, sum, next
+    )
+}
+
+/// What goes between the two halves of a split screen.
+pub const DIVIDER: &str = " │ ";
+
+/// The room each half of a split screen gets in a terminal of `columns` by
+/// `rows` characters.
+pub fn halve((columns, rows): (usize, usize)) -> (usize, usize) {
+    (columns.saturating_sub(DIVIDER.chars().count()) / 2, rows)
+}
+
+/// Puts two frames next to each other, padding the left one out so the
+/// right one lines up however long its lines are.
+pub fn side_by_side(left: &str, right: &str) -> String {
+    let left: Vec<&str> = left.lines().collect();
+    let right: Vec<&str> = right.lines().collect();
+    let width = left.iter().map(|l| visible_width(l)).max().unwrap_or(0);
+    let mut out = String::new();
+
+    for i in 0..left.len().max(right.len()) {
+        let l = left.get(i).copied().unwrap_or("");
+        out.push_str(l);
+        out.push_str(&" ".repeat(width - visible_width(l)));
+        out.push_str(DIVIDER);
+        out.push_str(right.get(i).copied().unwrap_or(""));
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Writes `text` over a line starting at column `at`, keeping the colours
+/// of whatever shows to either side of it. Short lines are padded out.
+fn splice(line: &str, at: usize, text: &str) -> String {
+    let left = cut(line, 0, at);
+    let pad = " ".repeat(at.saturating_sub(visible_width(&left)));
+    let right = cut(line, at + visible_width(text), usize::MAX);
+
+    format!("{}{}\x1B[0m{}\x1B[0m{}", left, pad, text, right)
+}
+
+/// The columns of a line from `from` up to `to`, along with the colours
+/// they are drawn in. Wide characters cut in half become spaces.
+fn cut(line: &str, from: usize, to: usize) -> String {
+    let mut style = String::new();
+    let mut out = String::new();
+    let mut width = 0;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1B' {
+            let mut seq = String::from(c);
+            if let Some(next) = chars.next() {
+                seq.push(next);
+                if next == '[' {
+                    for c in chars.by_ref() {
+                        seq.push(c);
+                        if c.is_ascii_alphabetic() {
+                            break;
+                        }
+                    }
+                }
+            }
+
+            // Colours set before `from` still apply to what comes after.
+            if width < from {
+                if seq == "\x1B[0m" {
+                    style.clear();
+                } else if seq.ends_with('m') {
+                    style.push_str(&seq);
+                }
+            } else if width < to {
+                out.push_str(&seq);
+            }
+            continue;
+        }
+
+        let start = width;
+        width += c.width().unwrap_or(0);
+        if width <= from || start >= to {
+            continue;
+        }
+        if start < from || width > to {
+            out.push_str(&" ".repeat(width.min(to) - start.max(from)));
+        } else {
+            out.push(c);
+        }
+    }
+
+    style + &out
+}
+
+/// How many terminal columns a line takes up, skipping escape sequences.
+/// Wide characters such as emoji count as two columns.
+pub fn visible_width(line: &str) -> usize {
+    let mut width = 0;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1B' {
+            // CSI sequences end with a letter, anything else we treat as a
+            // two-character escape.
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+        } else {
+            width += c.width().unwrap_or(0);
+        }
+    }
+
+    width
+}
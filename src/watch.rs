@@ -0,0 +1,46 @@
+//! Watching the pattern and rule files a run started from, so that saving
+//! one in an editor shows what it does straight away.
+//!
+//! Files are watched by looking at when they were last modified every so
+//! often, which is plenty for someone saving by hand and needs nothing
+//! from the operating system.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// A file, and when it was last seen to change.
+pub struct Watch {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl Watch {
+    pub fn new(path: &Path) -> Watch {
+        Watch {
+            path: path.to_path_buf(),
+            modified: modified(path),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the file has changed since it was last asked about. Some
+    /// editors save by removing the file and writing it again, so a file
+    /// that's missing for a moment hasn't changed.
+    pub fn changed(&mut self) -> bool {
+        match modified(&self.path) {
+            Some(time) if Some(time) != self.modified => {
+                self.modified = Some(time);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}